@@ -0,0 +1,26 @@
+// cargo bench --no-default-features --bench maze
+
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use game::map::{generate_maze, maze_to_grid};
+use std::hint::black_box;
+
+fn bench_generate(c: &mut Criterion) {
+    let mut group = c.benchmark_group("generate_maze");
+    group.sample_size(10);
+    for size in [20, 100, 500, 1000, 2000] {
+        group.bench_with_input(BenchmarkId::from_parameter(size), &size, |b, &size| {
+            b.iter(|| generate_maze(black_box(size), black_box(size), 42))
+        });
+    }
+    group.finish();
+}
+
+fn bench_to_grid(c: &mut Criterion) {
+    let maze = generate_maze(500, 500, 42);
+    c.bench_function("maze_to_grid 500x500", |b| {
+        b.iter(|| maze_to_grid(black_box(&maze)))
+    });
+}
+
+criterion_group!(benches, bench_generate, bench_to_grid);
+criterion_main!(benches);
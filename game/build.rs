@@ -1,5 +1,11 @@
 fn main() {
-    println!("cargo:rustc-link-search=native=C:/Users/admar/Desktop/multiplayer-fps/game/target/debug");
+    // the headless server does not need SDL2 at all
+    if std::env::var_os("CARGO_FEATURE_CLIENT").is_none() {
+        return;
+    }
+    println!(
+        "cargo:rustc-link-search=native=C:/Users/admar/Desktop/multiplayer-fps/game/target/debug"
+    );
     println!("cargo:rustc-link-lib=dylib=SDL2");
 }
-// This build script links the SDL2 library dynamically
\ No newline at end of file
+// This build script links the SDL2 library dynamically
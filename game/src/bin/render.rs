@@ -0,0 +1,74 @@
+// headless screenshot: renders one frame like the client would, without a window, to a png
+// usage: render <out.png> [map options, same as the client] [--pose <x>,<y>,<degrees>]
+//   [--resolution <w>x<h>] [--other <x>,<y>[,<degrees>]]...
+// the pose defaults to the first spawn looking east (0 degrees), the resolution to the
+// client window, 1200x1000. textures are loaded from textures/ like the client does
+
+use game::config::{MapConfig, take_flag};
+use game::render::{Actors, OtherPlayer, Player, render_image};
+use game::textures::TextureSet;
+
+fn floats<const N: usize>(flag: &str, v: &str) -> Result<[f32; N], String> {
+    let bad = || format!("{flag} expects {N} comma separated numbers, got {v:?}");
+    let parts: Vec<f32> = v
+        .split(',')
+        .map(|p| p.trim().parse().map_err(|_| bad()))
+        .collect::<Result<_, _>>()?;
+    parts.try_into().map_err(|_| bad())
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    let map = MapConfig::take_from_args(&mut args)?;
+    let pose = take_flag(&mut args, "--pose")?;
+    let resolution = take_flag(&mut args, "--resolution")?;
+    let mut others = Vec::new();
+    while let Some(v) = take_flag(&mut args, "--other")? {
+        // facing east unless told otherwise
+        let [x, y, degrees] = match floats::<2>("--other", &v) {
+            Ok([x, y]) => [x, y, 0.0],
+            Err(_) => floats("--other", &v)?,
+        };
+        others.push(OtherPlayer {
+            angle: degrees.to_radians(),
+            ..OtherPlayer::at(x, y)
+        });
+    }
+    let out = match args.as_slice() {
+        [out] => out.clone(),
+        [] => return Err("usage: render <out.png> [options], see src/bin/render.rs".into()),
+        _ => return Err(format!("unexpected arguments: {}", args.join(" ")).into()),
+    };
+
+    let (width, height) = match resolution {
+        Some(v) => {
+            let [w, h] = floats::<2>("--resolution", &v.replace('x', ","))?;
+            if w < 1.0 || h < 5.0 || w.fract() != 0.0 || h.fract() != 0.0 {
+                return Err(format!("--resolution expects <w>x<h> like 320x240, got {v:?}").into());
+            }
+            (w as usize, h as usize)
+        }
+        None => (1200, 1000),
+    };
+
+    let level = map.build_level()?;
+    let (sx, sy) = level.spawns.first().copied().unwrap_or((1, 1));
+    let [x, y, degrees] = match pose {
+        Some(v) => floats("--pose", &v)?,
+        None => [sx as f32 + 0.5, sy as f32 + 0.5, 0.0],
+    };
+    let player = Player::at(x, y, degrees.to_radians());
+
+    let textures = TextureSet::load_dir("textures")?;
+    let image = render_image(
+        width,
+        height,
+        &level.tiles,
+        &textures,
+        &player,
+        Actors::players(&others),
+    );
+    image.save_png(&out)?;
+    println!("{width}x{height} frame written to {out}");
+    Ok(())
+}
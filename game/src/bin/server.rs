@@ -0,0 +1,105 @@
+// headless server, no sdl2 window
+// usage: server [bind address] [--seed <u64>] [--size <w>x<h>] [--maze <algorithm>] [--braid <0-100>]
+//   [--rooms <n>[:<min>-<max>]] [--plazas <n>[:<min>-<max>]] [--wide <n>[:<min>-<max>]]
+//   [--map <file>] [--export-map <file>] [--rotate <minutes>]
+// the bind address defaults to 0.0.0.0:4000, --rotate moves on to the next seed's maze every
+// so many minutes. what goes out to each client is printed every REPORT_EVERY
+
+use game::braid::MazeMetrics;
+use game::config::{MapConfig, take_flag};
+use game::server::{Bandwidth, Server, TICK_RATE};
+use std::time::{Duration, Instant};
+
+const REPORT_EVERY: Duration = Duration::from_secs(10);
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    let map = MapConfig::take_from_args(&mut args)?;
+    let export = take_flag(&mut args, "--export-map")?;
+    let rotate = match take_flag(&mut args, "--rotate")? {
+        Some(v) => Some(
+            v.parse::<f32>()
+                .ok()
+                .filter(|m| *m > 0.0)
+                .ok_or_else(|| format!("--rotate expects minutes, got {v:?}"))?,
+        ),
+        None => None,
+    };
+    let addr = match args.as_slice() {
+        [] => "0.0.0.0:4000".to_string(),
+        [addr] => addr.clone(),
+        _ => return Err(format!("unexpected arguments: {}", args.join(" ")).into()),
+    };
+
+    let level = map.build_level()?;
+    // a generated maze as a starting point for a hand made map
+    if let Some(path) = export {
+        level.save(&path)?;
+        println!("map written to {path}");
+    }
+    let from_file = map.file.is_some();
+    let title = format!("{} v{} by {}", level.name, level.version, level.author);
+
+    let mut server = Server::with_level(&addr, map, level)?;
+    if from_file {
+        println!(
+            "server listening on {} at {} ticks/s, map {}",
+            server.local_addr()?,
+            TICK_RATE,
+            title
+        );
+    } else {
+        println!(
+            "server listening on {} at {} ticks/s, map seed {} ({})",
+            server.local_addr()?,
+            TICK_RATE,
+            server.map().seed,
+            server.map().algorithm
+        );
+        // for level designers tuning --maze and --braid
+        let m = MazeMetrics::measure(&server.map().build_maze());
+        println!(
+            "map: {} cells, {} dead ends, {} loops, {} corridors averaging {:.1} cells",
+            m.cells, m.dead_ends, m.loops, m.corridors, m.avg_corridor_len
+        );
+    }
+    if let Some(minutes) = rotate {
+        server.rotate_maps(minutes * 60.0)?;
+        println!("a new maze every {minutes} minutes");
+    }
+    let mut reported = Instant::now();
+    let mut before: Vec<(u32, Bandwidth)> = Vec::new();
+    server.run(|server| {
+        let seconds = reported.elapsed().as_secs_f32();
+        if seconds < REPORT_EVERY.as_secs_f32() {
+            return;
+        }
+        reported = Instant::now();
+        let now = server.bandwidth();
+        for &(id, total) in &now {
+            // a client that just joined has nothing before
+            let earlier = before
+                .iter()
+                .find(|(b, _)| *b == id)
+                .map_or(Bandwidth::default(), |&(_, b)| b);
+            println!("{}", report(id, &total.since(&earlier), seconds));
+        }
+        before = now;
+    })?;
+    Ok(())
+}
+
+// one line per client: bytes a second, and how many snapshots went out as deltas and at
+// what share of their full size
+fn report(id: u32, sent: &Bandwidth, seconds: f32) -> String {
+    let rate = sent.bytes as f32 / seconds / 1000.0;
+    let share = if sent.full_bytes == 0 {
+        0.0
+    } else {
+        sent.snapshot_bytes as f32 / sent.full_bytes as f32 * 100.0
+    };
+    format!(
+        "player {id}: {rate:.1} kB/s, {}/{} snapshots as deltas at {share:.0}% of full size",
+        sent.deltas, sent.snapshots
+    )
+}
@@ -0,0 +1,139 @@
+// braiding: knock down walls at dead ends so the maze gets loops and nobody gets cornered.
+// a perfect maze has exactly one path between two cells, every wall removed here adds one loop
+
+use crate::map::{Cell, carve, neighbor};
+use rand::seq::SliceRandom;
+use rand::{Rng, RngCore};
+
+// a dead end is a cell with only one opening
+fn is_dead_end(cell: &Cell) -> bool {
+    cell.walls().iter().filter(|&&wall| wall).count() == 3
+}
+
+// removes `percent` (0..=100) of the dead ends, returns how many walls were knocked down.
+// when a dead end sits next to another dead end the wall between them goes first,
+// that fixes two dead ends with one loop
+pub fn braid(maze: &mut [Vec<Cell>], percent: u8, rng: &mut dyn RngCore) -> usize {
+    let h = maze.len();
+    let w = maze.first().map_or(0, |row| row.len());
+
+    let mut dead_ends: Vec<(usize, usize)> = (0..h)
+        .flat_map(|y| (0..w).map(move |x| (x, y)))
+        .filter(|&(x, y)| is_dead_end(&maze[y][x]))
+        .collect();
+    dead_ends.shuffle(rng);
+    let target = (dead_ends.len() * percent.min(100) as usize).div_ceil(100);
+
+    let mut removed = 0;
+    let mut fixed = 0;
+    for (x, y) in dead_ends {
+        if fixed >= target {
+            break;
+        }
+        // an earlier removal may already have opened this one
+        if !is_dead_end(&maze[y][x]) {
+            continue;
+        }
+        let closed: Vec<usize> = (0..4)
+            .filter(|&dir| maze[y][x].walls()[dir] && neighbor(x, y, dir, w, h).is_some())
+            .collect();
+        // only a 1 wide maze has dead ends with nothing to open
+        if closed.is_empty() {
+            continue;
+        }
+        let paired: Vec<usize> = closed
+            .iter()
+            .copied()
+            .filter(|&dir| {
+                let (nx, ny) = neighbor(x, y, dir, w, h).unwrap();
+                is_dead_end(&maze[ny][nx])
+            })
+            .collect();
+        let pick = if paired.is_empty() { &closed } else { &paired };
+        let dir = pick[rng.random_range(0..pick.len())];
+        let (nx, ny) = neighbor(x, y, dir, w, h).unwrap();
+        fixed += 1 + usize::from(is_dead_end(&maze[ny][nx]));
+        carve(maze, x, y, dir);
+        removed += 1;
+    }
+    removed
+}
+
+// numbers for tuning arenas, see MazeMetrics::measure
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct MazeMetrics {
+    pub cells: usize,
+    pub dead_ends: usize,
+    // independent loops (cycle rank): passages - cells + 1 for a connected maze, 0 when perfect
+    pub loops: usize,
+    // corridors run between junctions and dead ends, length counted in cell steps
+    pub corridors: usize,
+    pub avg_corridor_len: f32,
+}
+
+impl MazeMetrics {
+    pub fn measure(maze: &[Vec<Cell>]) -> Self {
+        let h = maze.len();
+        let w = maze.first().map_or(0, |row| row.len());
+        let openings = |x: usize, y: usize| -> Vec<usize> {
+            (0..4)
+                .filter(|&dir| !maze[y][x].walls()[dir] && neighbor(x, y, dir, w, h).is_some())
+                .collect()
+        };
+
+        let mut passages = 0;
+        let mut dead_ends = 0;
+        let mut walks = 0;
+        let mut walked = 0;
+        for y in 0..h {
+            for x in 0..w {
+                let open = openings(x, y);
+                passages += open.len();
+                if open.len() == 1 {
+                    dead_ends += 1;
+                }
+                // plain corridor cell, the walks below pass through it
+                if open.len() == 2 {
+                    continue;
+                }
+                // follow every opening until the next junction or dead end
+                for &first in &open {
+                    let (mut px, mut py) = (x, y);
+                    let (mut cx, mut cy) = neighbor(x, y, first, w, h).unwrap();
+                    let mut len = 1;
+                    loop {
+                        let next = openings(cx, cy);
+                        if next.len() != 2 {
+                            break;
+                        }
+                        let back = next
+                            .iter()
+                            .position(|&d| neighbor(cx, cy, d, w, h) == Some((px, py)))
+                            .unwrap();
+                        let (nx, ny) = neighbor(cx, cy, next[1 - back], w, h).unwrap();
+                        (px, py, cx, cy) = (cx, cy, nx, ny);
+                        len += 1;
+                    }
+                    walks += 1;
+                    walked += len;
+                }
+            }
+        }
+        // every passage was counted from both of its cells
+        let passages = passages / 2;
+        let cells = w * h;
+        // each corridor was walked once from each end
+        let corridors = walks / 2;
+        Self {
+            cells,
+            dead_ends,
+            loops: (passages + 1).saturating_sub(cells),
+            corridors,
+            avg_corridor_len: if walks == 0 {
+                0.0
+            } else {
+                walked as f32 / walks as f32
+            },
+        }
+    }
+}
@@ -0,0 +1,291 @@
+// the client end of a game on a Server, no sdl2 in here so a test can drive it over loopback.
+// joins, rebuilds the level from the Welcome and then once a frame: reads what came in, moves
+// our own player right away with the frame's input (prediction.rs) and sends that input.
+// every snapshot puts us back where the server has us and replays what it hasn't seen yet,
+// everyone else is drawn INTERP_DELAY in the past between the snapshots (interpolation.rs).
+// every snapshot is acked so the server can send the next ones as deltas against it.
+// names, weapon picks and chat go out on the reliable channels (reliable.rs), kills, chat,
+// joins and leaves and map changes come in on them and are handed back from update
+
+use crate::config::MapConfig;
+use crate::interpolation::Interpolator;
+use crate::mapfile::MapFile;
+use crate::mechanics::PlayerInput;
+use crate::prediction::Prediction;
+use crate::protocol::{Baselines, ClientMessage, MAX_PACKET, PlayerState, ServerMessage};
+use crate::reliable::{Channel, Reliable};
+use crate::render::{OtherPlayer, Player};
+use crate::weapons::{ProjectileState, Weapon};
+use std::io;
+use std::net::{ToSocketAddrs, UdpSocket};
+use std::time::{Duration, Instant};
+
+// how long connect waits for a Welcome, the Join goes out again every JOIN_RESEND
+pub const JOIN_TIMEOUT: Duration = Duration::from_secs(5);
+const JOIN_RESEND: Duration = Duration::from_millis(250);
+// the server is gone when nothing came from it for this long, the same time it gives us
+const SERVER_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub struct Connection {
+    socket: UdpSocket,
+    // our player id on the server
+    pub id: u32,
+    // what the server is playing, the level is rebuilt from it
+    pub map: MapConfig,
+    pub level: MapFile,
+    // our own map options, for a hand made map the server switches to later
+    local: MapConfig,
+    // where the prediction has us, draw view() instead
+    pub player: Player,
+    prediction: Prediction,
+    // everyone else, from all the snapshots
+    interpolator: Interpolator,
+    // the snapshots deltas are decoded against
+    baselines: Baselines,
+    // rockets and grenades in flight, as of the newest snapshot
+    pub projectiles: Vec<ProjectileState>,
+    // newest snapshot tick, older projectiles arriving late are ignored
+    newest: Option<u32>,
+    last_heard: Instant,
+    reliable: Reliable,
+}
+
+impl Connection {
+    // joins and waits for the Welcome. `local` is only used for a hand made map, the server
+    // just sends its name and version so the client needs the same file passed with --map
+    pub fn connect<A: ToSocketAddrs>(addr: A, local: &MapConfig) -> Result<Self, String> {
+        let socket = UdpSocket::bind("0.0.0.0:0").map_err(|e| format!("can't bind: {e}"))?;
+        socket
+            .connect(addr)
+            .map_err(|e| format!("can't reach the server: {e}"))?;
+        socket
+            .set_read_timeout(Some(JOIN_RESEND))
+            .map_err(|e| e.to_string())?;
+        let started = Instant::now();
+        let mut buf = [0u8; MAX_PACKET];
+        let (id, welcome) = loop {
+            if started.elapsed() > JOIN_TIMEOUT {
+                return Err(format!("no answer from the server in {JOIN_TIMEOUT:?}"));
+            }
+            // lost joins are simply sent again, the server answers a repeat with the same id
+            let _ = socket.send(&ClientMessage::join().encode());
+            let Ok(len) = socket.recv(&mut buf) else {
+                continue;
+            };
+            match ServerMessage::decode(&buf[..len]) {
+                Ok(ServerMessage::Error { code, message }) => {
+                    return Err(format!("server refused: {code}: {message}"));
+                }
+                Ok(welcome @ ServerMessage::Welcome { id, .. }) => break (id, welcome),
+                // snapshots can overtake a lost welcome, wait for the next one
+                _ => {}
+            }
+        };
+        socket.set_nonblocking(true).map_err(|e| e.to_string())?;
+        let (map, level) = welcome_level(&welcome, local)?;
+        Ok(Self {
+            socket,
+            id,
+            map,
+            level,
+            local: local.clone(),
+            // put where the server has us by the first snapshot
+            player: Player::at(0.0, 0.0, 0.0),
+            prediction: Prediction::new(),
+            interpolator: Interpolator::new(),
+            baselines: Baselines::new(),
+            projectiles: Vec::new(),
+            newest: None,
+            last_heard: Instant::now(),
+            reliable: Reliable::new(),
+        })
+    }
+
+    // once a frame: everything that came in, then the frame's input moves us and goes out.
+    // gives back what came in on the reliable channels in order, a Welcome among them has
+    // already switched the level
+    pub fn update(&mut self, input: PlayerInput) -> io::Result<Vec<ServerMessage>> {
+        let mut delivered = Vec::new();
+        let mut buf = [0u8; MAX_PACKET];
+        loop {
+            let len = match self.socket.recv(&mut buf) {
+                Ok(len) => len,
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => break,
+                // the server isn't up (yet, or any more), the timeout below decides
+                Err(e) if e.kind() == io::ErrorKind::ConnectionRefused => break,
+                Err(e) if e.kind() == io::ErrorKind::ConnectionReset => continue,
+                Err(e) => return Err(e),
+            };
+            // a delta against a snapshot we no longer have fails too, the server goes back to
+            // full ones when the acks stop
+            let Ok(msg) = self.baselines.decode(&buf[..len]) else {
+                continue;
+            };
+            self.last_heard = Instant::now();
+            match msg {
+                ServerMessage::Snapshot {
+                    tick,
+                    players,
+                    projectiles,
+                } => {
+                    self.send(&ClientMessage::SnapshotAck { tick })?;
+                    self.snapshot(tick, players, projectiles);
+                }
+                ServerMessage::Reliable(packet) => {
+                    self.reliable.receive(&packet);
+                    while let Some((_, payload)) = self.reliable.poll() {
+                        // no packets inside packets, and nothing the server wouldn't send
+                        match ServerMessage::decode(&payload) {
+                            Ok(ServerMessage::Reliable(_)) | Err(_) => {}
+                            // right away, the snapshots after it are on the new level
+                            Ok(welcome @ ServerMessage::Welcome { .. }) => {
+                                (self.map, self.level) = welcome_level(&welcome, &self.local)
+                                    .map_err(io::Error::other)?;
+                                delivered.push(welcome);
+                            }
+                            Ok(msg) => delivered.push(msg),
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        if self.last_heard.elapsed() > SERVER_TIMEOUT {
+            return Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                format!("nothing from the server in {SERVER_TIMEOUT:?}"),
+            ));
+        }
+
+        let msg = self
+            .prediction
+            .predict(&mut self.player, &self.level.tiles, input);
+        self.send(&msg)?;
+        self.prediction.smooth(input.dt);
+        self.interpolator.advance(input.dt);
+        if let Some(packet) = self.reliable.update(input.dt) {
+            self.send(&ClientMessage::Reliable(packet))?;
+        }
+        Ok(delivered)
+    }
+
+    // where to draw us, the prediction with what is left of the last correction
+    pub fn view(&self) -> Player {
+        self.prediction.view(&self.player)
+    }
+
+    // everyone but us, as they were INTERP_DELAY ago
+    pub fn others(&self) -> Vec<OtherPlayer> {
+        self.interpolator.players(self.id)
+    }
+
+    // these three go out with the next update and again until the server has them
+    pub fn select_weapon(&mut self, weapon: Weapon) -> Result<(), String> {
+        let msg = ClientMessage::SelectWeapon { weapon };
+        self.reliable.send(Channel::Game, msg.encode())
+    }
+
+    pub fn set_name(&mut self, name: &str) -> Result<(), String> {
+        let name = name.to_string();
+        let msg = ClientMessage::SetName { name };
+        self.reliable.send(Channel::Game, msg.encode())
+    }
+
+    pub fn say(&mut self, text: &str) -> Result<(), String> {
+        let text = text.to_string();
+        let msg = ClientMessage::Say { text };
+        self.reliable.send(Channel::Chat, msg.encode())
+    }
+
+    fn snapshot(
+        &mut self,
+        tick: u32,
+        players: Vec<PlayerState>,
+        projectiles: Vec<ProjectileState>,
+    ) {
+        if let Some(me) = players.iter().find(|p| p.id == self.id) {
+            self.prediction
+                .reconcile(&mut self.player, &self.level.tiles, me);
+        }
+        // a late one still fills its gap in the past
+        self.interpolator.push(tick, players);
+        if self.newest.is_none_or(|newest| tick > newest) {
+            self.newest = Some(tick);
+            self.projectiles = projectiles;
+        }
+    }
+
+    fn send(&self, msg: &ClientMessage) -> io::Result<()> {
+        match self.socket.send(&msg.encode()) {
+            Ok(_) => Ok(()),
+            // a full buffer or a server that went away, the timeout in update notices the latter
+            Err(e)
+                if matches!(
+                    e.kind(),
+                    io::ErrorKind::WouldBlock | io::ErrorKind::ConnectionRefused
+                ) =>
+            {
+                Ok(())
+            }
+            Err(e) => Err(e),
+        }
+    }
+}
+
+// the server hears about it right away instead of timing us out
+impl Drop for Connection {
+    fn drop(&mut self) {
+        let _ = self.send(&ClientMessage::Leave);
+    }
+}
+
+// the map a Welcome describes: a generated maze comes out the same from its options, a hand
+// made map has to be the file the server loaded
+pub fn welcome_level(
+    welcome: &ServerMessage,
+    local: &MapConfig,
+) -> Result<(MapConfig, MapFile), String> {
+    let ServerMessage::Welcome {
+        seed,
+        width,
+        height,
+        algorithm,
+        braid,
+        rooms,
+        map_name,
+        map_version,
+        sky,
+        ..
+    } = welcome
+    else {
+        return Err("expected a welcome".to_string());
+    };
+    let map = MapConfig {
+        seed: *seed,
+        width: *width as usize,
+        height: *height as usize,
+        algorithm: *algorithm,
+        braid: *braid,
+        rooms: *rooms,
+        file: None,
+        sky: *sky,
+    };
+    if map_name.is_empty() {
+        let level = map.build_level()?;
+        return Ok((map, level));
+    }
+    let wrong =
+        || format!("the server plays map {map_name:?} v{map_version}, pass that file with --map");
+    let Some(file) = &local.file else {
+        return Err(wrong());
+    };
+    let map = MapConfig {
+        file: Some(file.clone()),
+        ..map
+    };
+    let level = map.build_level()?;
+    if level.name != *map_name || level.version != *map_version {
+        return Err(wrong());
+    }
+    Ok((map, level))
+}
@@ -0,0 +1,186 @@
+// map options shared by the client and the server command lines
+
+use crate::braid::braid;
+use crate::map::{Cell, generate_maze_using, maze_to_grid, random_seed};
+use crate::mapfile::MapFile;
+use crate::mazegen::MazeAlgorithm;
+use crate::rooms::{Carve, RoomConfig, carve_rooms};
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use std::path::PathBuf;
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct MapConfig {
+    pub seed: u64,
+    // size in maze cells, the grid is w * 2 + 1 by h * 2 + 1
+    pub width: usize,
+    pub height: usize,
+    pub algorithm: MazeAlgorithm,
+    // percent of dead ends turned into loops, 0 keeps the maze perfect
+    pub braid: u8,
+    pub rooms: RoomConfig,
+    // hand made map, when set the maze options above are ignored
+    pub file: Option<PathBuf>,
+    // open sky instead of a ceiling, also turned on by `sky: yes` in a map file
+    pub sky: bool,
+}
+
+impl Default for MapConfig {
+    fn default() -> Self {
+        Self {
+            seed: random_seed(),
+            width: 20,
+            height: 15,
+            algorithm: MazeAlgorithm::default(),
+            braid: 0,
+            rooms: RoomConfig::default(),
+            file: None,
+            sky: false,
+        }
+    }
+}
+
+impl MapConfig {
+    // pulls the map flags out of args and leaves everything else for the caller
+    //   --seed <u64>   --size <w>x<h>   --maze <algorithm>   --braid <0-100>
+    //   --rooms <n>[:<min>-<max>]   --plazas <n>[:<min>-<max>]   --wide <n>[:<min>-<max>]
+    //   --map <file>   --sky
+    pub fn take_from_args(args: &mut Vec<String>) -> Result<Self, String> {
+        let mut cfg = Self::default();
+        let mut rest = Vec::new();
+        let mut it = std::mem::take(args).into_iter();
+        while let Some(arg) = it.next() {
+            match arg.as_str() {
+                "--seed" => {
+                    let v = flag_value(&arg, it.next())?;
+                    cfg.seed = v
+                        .parse()
+                        .map_err(|_| format!("--seed expects a number, got {v:?}"))?;
+                }
+                "--size" => {
+                    let v = flag_value(&arg, it.next())?;
+                    (cfg.width, cfg.height) = parse_size(&v)?;
+                }
+                "--sky" => cfg.sky = true,
+                "--maze" => {
+                    cfg.algorithm = flag_value(&arg, it.next())?.parse()?;
+                }
+                "--braid" => {
+                    let v = flag_value(&arg, it.next())?;
+                    cfg.braid = v
+                        .parse()
+                        .ok()
+                        .filter(|&p| p <= 100)
+                        .ok_or_else(|| format!("--braid expects a percent 0-100, got {v:?}"))?;
+                }
+                "--rooms" => {
+                    cfg.rooms.rooms =
+                        parse_carve(&arg, &flag_value(&arg, it.next())?, cfg.rooms.rooms)?;
+                }
+                "--plazas" => {
+                    cfg.rooms.plazas =
+                        parse_carve(&arg, &flag_value(&arg, it.next())?, cfg.rooms.plazas)?;
+                }
+                "--wide" => {
+                    cfg.rooms.wide_corridors = parse_carve(
+                        &arg,
+                        &flag_value(&arg, it.next())?,
+                        cfg.rooms.wide_corridors,
+                    )?;
+                }
+                "--map" => {
+                    cfg.file = Some(PathBuf::from(flag_value(&arg, it.next())?));
+                }
+                _ => rest.push(arg),
+            }
+        }
+        *args = rest;
+        Ok(cfg)
+    }
+
+    pub fn build_maze(&self) -> Vec<Vec<Cell>> {
+        let generator = self.algorithm.generator();
+        let mut maze = generate_maze_using(generator, self.width, self.height, self.seed);
+        if self.braid > 0 {
+            // own stream so braiding doesn't change how the maze itself comes out
+            let mut rng = ChaCha8Rng::seed_from_u64(self.seed);
+            rng.set_stream(1);
+            braid(&mut maze, self.braid, &mut rng);
+        }
+        maze
+    }
+
+    // the map to play: the --map file if there is one, the generated maze otherwise
+    pub fn build_level(&self) -> Result<MapFile, String> {
+        let mut level = match &self.file {
+            Some(path) => MapFile::load(path)?,
+            None => {
+                let name = format!("{} {}", self.algorithm, self.seed);
+                MapFile::from_grid(&name, "generated", &self.build_grid())
+            }
+        };
+        level.add_auto_spawns();
+        if self.sky {
+            level.tiles.set_open_sky(true);
+        }
+        Ok(level)
+    }
+
+    pub fn build_grid(&self) -> Vec<Vec<u8>> {
+        let mut grid = maze_to_grid(&self.build_maze());
+        if !self.rooms.is_empty() {
+            let mut rng = ChaCha8Rng::seed_from_u64(self.seed);
+            rng.set_stream(2);
+            carve_rooms(&mut grid, &self.rooms, &mut rng);
+        }
+        grid
+    }
+}
+
+// removes `flag <value>` from args, for the one-off flags of each binary
+pub fn take_flag(args: &mut Vec<String>, flag: &str) -> Result<Option<String>, String> {
+    let Some(i) = args.iter().position(|a| a == flag) else {
+        return Ok(None);
+    };
+    if i + 1 >= args.len() {
+        return Err(format!("{flag} needs a value"));
+    }
+    let value = args.remove(i + 1);
+    args.remove(i);
+    Ok(Some(value))
+}
+
+fn flag_value(flag: &str, v: Option<String>) -> Result<String, String> {
+    v.ok_or_else(|| format!("{flag} needs a value"))
+}
+
+fn parse_size(v: &str) -> Result<(usize, usize), String> {
+    let bad = || format!("--size expects <w>x<h> like 20x15, got {v:?}");
+    let (w, h) = v.split_once('x').ok_or_else(bad)?;
+    let w: usize = w.parse().map_err(|_| bad())?;
+    let h: usize = h.parse().map_err(|_| bad())?;
+    if w == 0 || h == 0 || w > u16::MAX as usize || h > u16::MAX as usize {
+        return Err(bad());
+    }
+    Ok((w, h))
+}
+
+// "4" keeps the default sizes, "4:3-6" also sets them (in grid tiles)
+fn parse_carve(flag: &str, v: &str, default: Carve) -> Result<Carve, String> {
+    let bad = || format!("{flag} expects <count>[:<min>-<max>] like 4:3-6, got {v:?}");
+    let (count, sizes) = match v.split_once(':') {
+        Some((count, sizes)) => (count, Some(sizes)),
+        None => (v, None),
+    };
+    let mut carve = default;
+    carve.count = count.parse().map_err(|_| bad())?;
+    if let Some(sizes) = sizes {
+        let (min, max) = sizes.split_once('-').ok_or_else(bad)?;
+        carve.min = min.parse().map_err(|_| bad())?;
+        carve.max = max.parse().map_err(|_| bad())?;
+        if carve.min == 0 || carve.min > carve.max {
+            return Err(bad());
+        }
+    }
+    Ok(carve)
+}
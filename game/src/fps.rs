@@ -1,6 +1,4 @@
-use sdl2::pixels::Color;
-use sdl2::rect::Rect;
-use sdl2::render::TextureQuery;
+use crate::text::draw_text;
 use sdl2::render::{Canvas, TextureCreator};
 use sdl2::ttf::{Font, Sdl2TtfContext};
 use sdl2::video::Window;
@@ -41,19 +39,19 @@ impl<'a> FpsCounter<'a> {
         texture_creator: &TextureCreator<sdl2::video::WindowContext>,
     ) -> Result<(), String> {
         let text = format!("FPS: {:.1}", self.fps);
-        let surface = self
-            .font
-            .render(&text)
-            .blended(Color::RGB(255, 255, 255))
-            .map_err(|e| e.to_string())?;
-        let texture = texture_creator
-            .create_texture_from_surface(&surface)
-            .map_err(|e| e.to_string())?;
-
-        let TextureQuery { width, height, .. } = texture.query();
-        let target = Rect::new(20, 20, width, height);
+        self.draw_text(canvas, texture_creator, &text, 20, 20)
+    }
 
-        canvas.copy(&texture, None, Some(target))?;
-        Ok(())
+    // any other hud line with the same font, e.g. the map seed
+    pub fn draw_text(
+        &self,
+        canvas: &mut Canvas<Window>,
+        texture_creator: &TextureCreator<sdl2::video::WindowContext>,
+        text: &str,
+        x: i32,
+        y: i32,
+    ) -> Result<(), String> {
+        let white = (text, (255, 255, 255));
+        draw_text(&self.font, canvas, texture_creator, white, |_, _| (x, y))
     }
 }
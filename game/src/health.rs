@@ -0,0 +1,79 @@
+// health, armor and the life of a player: taking hits, dying, watching while dead and coming
+// back. online the server is the only one applying damage (Server::tick), clients read the
+// result from the snapshots and the kills from ServerMessage::Kill
+
+use crate::render::{OtherPlayer, Player};
+use crate::weapons::Weapon;
+
+pub const MAX_HEALTH: u8 = 100;
+pub const MAX_ARMOR: u8 = 100;
+// what everyone (re)spawns with
+pub const SPAWN_ARMOR: u8 = 50;
+// seconds between dying and coming back
+pub const RESPAWN_DELAY: f32 = 3.0;
+// share of every hit the armor takes while there is some left
+const ARMOR_SHARE: f32 = 2.0 / 3.0;
+
+// one death for the killfeed. killer == victim when someone caught their own splash
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Kill {
+    pub killer: u32,
+    pub victim: u32,
+    pub weapon: Weapon,
+}
+
+// armor soaks up its share first, health takes the rest. true when this hit killed,
+// hitting someone already dead does nothing
+pub fn apply_damage(player: &mut Player, damage: u8) -> bool {
+    if player.health == 0 {
+        return false;
+    }
+    let soaked = ((damage as f32 * ARMOR_SHARE).round() as u8).min(player.armor);
+    player.armor -= soaked;
+    player.health = player.health.saturating_sub(damage - soaked);
+    player.health == 0
+}
+
+// back on their feet at (x, y) with full health and the spawn armor, looking where they did
+pub fn respawn(player: &mut Player, (x, y): (f32, f32)) {
+    player.x = x;
+    player.y = y;
+    player.health = MAX_HEALTH;
+    player.armor = SPAWN_ARMOR;
+}
+
+// the killfeed line, with the names already looked up
+pub fn kill_text(killer: &str, victim: &str, kill: &Kill) -> String {
+    if kill.killer == kill.victim {
+        format!("{victim} [{}]", kill.weapon.name())
+    } else {
+        format!("{killer} [{}] {victim}", kill.weapon.name())
+    }
+}
+
+// while dead the camera stays where we fell, or follows whoever we are spectating as long
+// as they are alive
+pub fn death_camera(dead: &Player, others: &[OtherPlayer], watching: Option<u32>) -> Player {
+    match others.iter().find(|o| o.alive && Some(o.id) == watching) {
+        Some(o) => Player {
+            x: o.x,
+            y: o.y,
+            angle: o.angle,
+            ..*dead
+        },
+        None => *dead,
+    }
+}
+
+// the next living player to spectate after `watching` in id order, wrapping around,
+// None (our own body) when nobody else is alive
+pub fn next_spectate(others: &[OtherPlayer], watching: Option<u32>) -> Option<u32> {
+    let mut alive: Vec<u32> = others.iter().filter(|o| o.alive).map(|o| o.id).collect();
+    alive.sort_unstable();
+    let after = watching.unwrap_or(0);
+    alive
+        .iter()
+        .copied()
+        .find(|&id| id > after)
+        .or(alive.first().copied())
+}
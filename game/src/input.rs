@@ -0,0 +1,57 @@
+// the only place that knows about sdl keys and the mouse: everything the player does in a
+// frame is gathered here into one PlayerInput, the rest of the game only sees that
+
+use crate::mechanics::PlayerInput;
+use sdl2::event::Event;
+use sdl2::keyboard::{KeyboardState, Scancode};
+use sdl2::mouse::MouseState;
+
+pub struct InputCollector {
+    // radians of turn per pixel of mouse motion
+    pub sensitivity: f32,
+    // mouse turn since the last build
+    yaw: f32,
+    tick: u32,
+}
+
+impl InputCollector {
+    pub fn new(sensitivity: f32) -> Self {
+        Self {
+            sensitivity,
+            yaw: 0.0,
+            tick: 0,
+        }
+    }
+
+    // every event of the frame goes through here, mouse motion adds up until the next build
+    pub fn handle_event(&mut self, event: &Event) {
+        if let Event::MouseMotion { xrel, .. } = event {
+            self.yaw += *xrel as f32 * self.sensitivity;
+        }
+    }
+
+    // the frame's input: W/S, D/A and Right/Left held right now, the left button for fire
+    // and the mouse turn gathered since the last call
+    pub fn build(&mut self, kbd: &KeyboardState, mouse: &MouseState, dt: f32) -> PlayerInput {
+        let axis = |pos: Scancode, neg: Scancode| -> f32 {
+            let mut v = 0.0;
+            if kbd.is_scancode_pressed(pos) {
+                v += 1.0;
+            }
+            if kbd.is_scancode_pressed(neg) {
+                v -= 1.0;
+            }
+            v
+        };
+        self.tick += 1;
+        PlayerInput {
+            tick: self.tick,
+            dt,
+            forward: axis(Scancode::W, Scancode::S),
+            strafe: axis(Scancode::D, Scancode::A),
+            turn: axis(Scancode::Right, Scancode::Left),
+            yaw: std::mem::take(&mut self.yaw),
+            buttons: if mouse.left() { PlayerInput::FIRE } else { 0 },
+        }
+    }
+}
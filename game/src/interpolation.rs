@@ -0,0 +1,151 @@
+// snapshot interpolation for everyone but us: snapshots are kept with the server time they
+// were made at (tick / TICK_RATE) and other players are drawn INTERP_DELAY in the past,
+// between the two snapshots around that moment. packets arriving a little late or out of
+// order then don't make anyone jitter. when nothing new comes in at all they keep going the
+// way they were for at most MAX_EXTRAPOLATION and then stop
+
+use crate::protocol::PlayerState;
+use crate::render::OtherPlayer;
+use crate::server::TICK_RATE;
+use std::collections::VecDeque;
+use std::f32::consts::PI;
+
+// how far in the past others are drawn, three snapshots at 30 ticks per second
+pub const INTERP_DELAY: f64 = 0.1;
+// longest we guess past the newest snapshot before freezing everyone
+pub const MAX_EXTRAPOLATION: f64 = 0.25;
+// a second's worth, older ones are never needed
+const MAX_SNAPSHOTS: usize = 32;
+// further apart than this between two snapshots is a respawn, not a walk
+const TELEPORT_DISTANCE: f32 = 1.0;
+// with the clock this far off (a hitch, a long loss) it jumps instead of catching up
+const RESYNC: f64 = 0.5;
+// share of the clock error taken out with every new snapshot, small so jitter averages out
+const CLOCK_PULL: f64 = 0.1;
+
+#[derive(Debug, Default)]
+pub struct Interpolator {
+    // (server time, players) oldest first
+    snapshots: VecDeque<(f64, Vec<PlayerState>)>,
+    // server time we are drawing, None before the first snapshot
+    clock: Option<f64>,
+}
+
+impl Interpolator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // a snapshot came in, late ones go where they belong and repeats are dropped. a new
+    // newest one pulls the clock a bit towards INTERP_DELAY behind it
+    pub fn push(&mut self, tick: u32, players: Vec<PlayerState>) {
+        let time = tick as f64 / TICK_RATE as f64;
+        let at = self.snapshots.partition_point(|&(t, _)| t < time);
+        if self.snapshots.get(at).is_some_and(|&(t, _)| t == time) {
+            return;
+        }
+        let newest = at == self.snapshots.len();
+        self.snapshots.insert(at, (time, players));
+        while self.snapshots.len() > MAX_SNAPSHOTS {
+            self.snapshots.pop_front();
+        }
+        if !newest {
+            return;
+        }
+        let target = time - INTERP_DELAY;
+        self.clock = Some(match self.clock {
+            Some(clock) if (target - clock).abs() <= RESYNC => {
+                clock + (target - clock) * CLOCK_PULL
+            }
+            _ => target,
+        });
+    }
+
+    // once a frame, the clock runs with real time between snapshots
+    pub fn advance(&mut self, dt: f32) {
+        if let Some(clock) = &mut self.clock {
+            *clock += dt as f64;
+        }
+    }
+
+    // the server time being drawn
+    pub fn clock(&self) -> Option<f64> {
+        self.clock
+    }
+
+    // everyone except `me` as they were at the clock. anim_time runs with the clock so walk
+    // cycles keep going between snapshots
+    pub fn players(&self, me: u32) -> Vec<OtherPlayer> {
+        let Some(clock) = self.clock else {
+            return Vec::new();
+        };
+        let n = self.snapshots.len();
+        if n < 2 {
+            // nothing to blend yet
+            return self.snapshots.back().map_or(Vec::new(), |(_, players)| {
+                others(players, me)
+                    .map(|p| animated(p.to_other(), clock))
+                    .collect()
+            });
+        }
+        // the snapshots on both sides of the clock, past the newest the last two
+        let after = self
+            .snapshots
+            .partition_point(|&(t, _)| t <= clock)
+            .clamp(1, n - 1);
+        let (ta, a) = &self.snapshots[after - 1];
+        let (tb, b) = &self.snapshots[after];
+        // extrapolating only goes so far
+        let time = clock.min(self.snapshots[n - 1].0 + MAX_EXTRAPOLATION);
+        let s = (((time - ta) / (tb - ta)) as f32).max(0.0);
+        // who is there comes from the snapshot we are at, past b they may have left or joined
+        let current = if s < 1.0 { a } else { b };
+        others(current, me)
+            .map(|p| match (by_id(a, p.id), by_id(b, p.id)) {
+                (Some(pa), Some(pb)) => blend(pa, pb, s),
+                // only in one of them, stays where it was seen
+                _ => p.to_other(),
+            })
+            .map(|p| animated(p, clock))
+            .collect()
+    }
+}
+
+fn others(players: &[PlayerState], me: u32) -> impl Iterator<Item = &PlayerState> {
+    players.iter().filter(move |p| p.id != me)
+}
+
+fn by_id(players: &[PlayerState], id: u32) -> Option<&PlayerState> {
+    players.iter().find(|p| p.id == id)
+}
+
+fn animated(p: OtherPlayer, clock: f64) -> OtherPlayer {
+    OtherPlayer {
+        anim_time: clock as f32,
+        ..p
+    }
+}
+
+// a at s = 0, b at s = 1, past 1 keeps going the same way. everything but the position and
+// facing comes from the snapshot we are closer to
+fn blend(a: &PlayerState, b: &PlayerState, s: f32) -> OtherPlayer {
+    let far = (b.x - a.x).hypot(b.y - a.y) > TELEPORT_DISTANCE;
+    // no sliding across the map after a respawn, or out of a dead body
+    if far || a.alive != b.alive {
+        return if s < 0.5 { a.to_other() } else { b.to_other() };
+    }
+    let nearest = if s < 0.5 { a } else { b };
+    OtherPlayer {
+        x: a.x + (b.x - a.x) * s,
+        y: a.y + (b.y - a.y) * s,
+        angle: lerp_angle(a.angle, b.angle, s),
+        ..nearest.to_other()
+    }
+}
+
+// the short way round from a to b, so 3.0 -> -3.0 goes through PI and not through 0.
+// the result is back in -PI..PI like every other angle
+pub fn lerp_angle(a: f32, b: f32, s: f32) -> f32 {
+    let delta = (b - a + PI).rem_euclid(2.0 * PI) - PI;
+    (a + delta * s + PI).rem_euclid(2.0 * PI) - PI
+}
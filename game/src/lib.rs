@@ -0,0 +1,24 @@
+// shared game code used by both the sdl2 client (main.rs) and the headless server (bin/server.rs)
+pub mod braid;
+pub mod client;
+pub mod config;
+pub mod health;
+#[cfg(feature = "client")]
+pub mod input;
+pub mod interpolation;
+pub mod map;
+pub mod mapfile;
+pub mod mazegen;
+pub mod mechanics;
+pub mod prediction;
+pub mod protocol;
+pub mod raycast;
+pub mod reliable;
+pub mod render;
+pub mod rooms;
+pub mod server;
+pub mod spawn;
+pub mod sprites;
+pub mod textures;
+pub mod tilemap;
+pub mod weapons;
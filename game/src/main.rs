@@ -1,56 +1,167 @@
 mod fps;
-mod map;
-mod mechanics;
-mod render;
 mod sdl2;
+mod text;
 
 use ::sdl2::event::Event;
 use ::sdl2::keyboard::Keycode;
 use ::sdl2::pixels::Color;
+use ::sdl2::pixels::PixelFormatEnum;
 use ::sdl2::rect::Rect;
 use std::time::Instant;
 
 use ::sdl2::ttf;
 use fps::FpsCounter;
-use map::{draw_minimap_from_grid, generate_maze, maze_to_grid};
-use mechanics::update_player;
-use render::{Player, cast_and_draw_columns};
+use game::client::Connection;
+use game::config::{MapConfig, take_flag};
+use game::health::{
+    Kill, MAX_HEALTH, RESPAWN_DELAY, apply_damage, death_camera, kill_text, next_spectate, respawn,
+};
+use game::input::InputCollector;
+use game::mapfile::MapFile;
+use game::mechanics::{PlayerInput, update_player};
+use game::protocol::{MAX_CHAT_LEN, ServerMessage};
+use game::render::{Actors, OtherPlayer, Player, Team, present_frame, render_frame};
+use game::spawn::choose_spawn;
+use game::sprites::Animation;
+use game::textures::TextureSet;
+use game::weapons::{Projectile, Trigger, Weapon, fire, update_projectiles};
 use sdl2::sdl2_win;
-
-use crate::render::OtherPlayer;
+use text::Text;
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // usage: game [--seed <u64>] [--size <w>x<h>] [--maze <algorithm>] [--braid <0-100>]
+    //   [--rooms <n>[:<min>-<max>]] [--plazas <n>[:<min>-<max>]] [--wide <n>[:<min>-<max>]]
+    //   [--map <file>] [--export-map <file>] [--connect <address>] [--name <name>]
+    // with --connect the map comes from the server, only a hand made one needs --map
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    let map_cfg = MapConfig::take_from_args(&mut args)?;
+    let export = take_flag(&mut args, "--export-map")?;
+    let server = take_flag(&mut args, "--connect")?;
+    let name = take_flag(&mut args, "--name")?;
+    if !args.is_empty() {
+        return Err(format!("unexpected arguments: {}", args.join(" ")).into());
+    }
+
     // let (mut canvas, mut event_pump) = sdl2_win("Maze FPS", 1200, 1200)?; // for 2k monitor
     let (mut canvas, mut event_pump) = sdl2_win("Maze FPS", 1200, 1000)?; // for 1080p monitor
 
     let ttf_context = ttf::init()?;
     let texture_creator = canvas.texture_creator();
+    let wall_textures = TextureSet::load_dir("textures")?;
+    // the 3d view and the minimap are drawn in software and uploaded once per frame
+    let (frame_w, frame_h) = (1200, 1000);
+    let mut frame = vec![0u32; frame_w * frame_h];
+    // sprite depth per pixel, reused like the frame
+    let mut depth = Vec::new();
+    let mut frame_texture = texture_creator.create_texture_streaming(
+        PixelFormatEnum::RGBA8888,
+        frame_w as u32,
+        frame_h as u32,
+    )?;
 
     let mut fps_counter = FpsCounter::new(&ttf_context, "font/Regular.ttf", 24)?;
+    // smaller text for the names over other players
+    let tag_font = Text::new(&ttf_context, "font/Regular.ttf", 16)?;
 
-    let maze = generate_maze(20, 15);
-    let grid = maze_to_grid(&maze);
-
-    let mut player = Player {
-        x: 1.5,
-        y: 1.5,
-        angle: 0.0,
-        fov: std::f32::consts::FRAC_PI_3,
-        move_speed: 3.0,
-        rot_speed: 2.5,
+    // online the server moves everyone and decides every hit, offline it all happens here
+    let mut net = match &server {
+        Some(addr) => Some(Connection::connect(addr, &map_cfg)?),
+        None => None,
+    };
+    let (level, mut seed_text) = match &mut net {
+        Some(conn) => {
+            if let Some(name) = &name {
+                conn.set_name(name)?;
+            }
+            (conn.level.clone(), map_text(&conn.map, &conn.level))
+        }
+        None => {
+            let level = map_cfg.build_level()?;
+            let text = map_text(&map_cfg, &level);
+            (level, text)
+        }
     };
+    if let Some(path) = export {
+        level.save(&path)?;
+    }
+    let spawns = level.spawns;
+    let mut tiles = level.tiles;
+    let (sx, sy) = choose_spawn(&tiles, &spawns, &[], &[]);
+
+    let mut player = Player::at(sx, sy, 0.0);
+    // we are id 1 offline, the dummies come after. online the server tells us
+    let me = net.as_ref().map_or(1, |conn| conn.id);
+    // seconds until we (or a dummy, by id) come back, and who we watch meanwhile
+    let mut respawns: Vec<(u32, f32)> = Vec::new();
+    let mut watching: Option<u32> = None;
+    // newest last, lines go away after KILLFEED_TIME seconds
+    const KILLFEED_TIME: f32 = 5.0;
+    let mut killfeed: Vec<(String, f32)> = Vec::new();
+    // chat and who came and went, the same way bottom left. return starts a line online
+    const CHAT_TIME: f32 = 8.0;
+    const CHAT_LINES: usize = 5;
+    let mut chat: Vec<(String, f32)> = Vec::new();
+    let mut typing: Option<String> = None;
+
+    // test dummies to shoot at offline, each spawned away from the ones already standing
+    let mut others: Vec<OtherPlayer> = Vec::new();
+    let dummies = if net.is_some() { 0 } else { 3 };
+    for (i, team) in [Team::Red, Team::Blue, Team::None]
+        .into_iter()
+        .take(dummies)
+        .enumerate()
+    {
+        let mut taken: Vec<_> = others.iter().map(|o| (o.x, o.y)).collect();
+        taken.push((player.x, player.y));
+        let (x, y) = choose_spawn(&tiles, &spawns, &taken, &taken);
+        others.push(OtherPlayer {
+            id: i as u32 + 2,
+            name: format!("dummy {}", i + 1),
+            team,
+            animation: Animation::Walk,
+            ..OtherPlayer::at(x, y)
+        });
+    }
 
-    let mut others = vec![
-        OtherPlayer { x: 3.5, y: 3.5 },
-        OtherPlayer { x: 5.5, y: 5.5 },
-        OtherPlayer { x: 7.5, y: 7.5 },
-    ];
+    // left mouse fires, 1 to 4 pick the weapon. offline shots are resolved the same way the
+    // server does, our own splash hurts us too
+    let mut trigger = Trigger::default();
+    let mut projectiles: Vec<Projectile> = Vec::new();
+    let mut next_projectile_id = 1;
 
     let mut last = Instant::now();
-    let mouse_sensitivity: f32 = 0.0025;
+    let mut input = InputCollector::new(0.0025);
 
     'game: loop {
+        let mut pick = None;
+        let mut said = None;
         for event in event_pump.poll_iter() {
+            input.handle_event(&event);
+            // while typing the keys go into the line
+            if let Some(line) = &mut typing {
+                match event {
+                    Event::Quit { .. } => break 'game,
+                    Event::TextInput { text, .. } if line.len() + text.len() <= MAX_CHAT_LEN => {
+                        line.push_str(&text)
+                    }
+                    Event::KeyDown {
+                        keycode: Some(Keycode::Backspace),
+                        ..
+                    } => {
+                        line.pop();
+                    }
+                    Event::KeyDown {
+                        keycode: Some(Keycode::Return),
+                        ..
+                    } => said = typing.take(),
+                    Event::KeyDown {
+                        keycode: Some(Keycode::Escape),
+                        ..
+                    } => typing = None,
+                    _ => {}
+                }
+                continue;
+            }
             match event {
                 Event::Quit { .. }
                 | Event::KeyDown {
@@ -59,44 +170,297 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 } => {
                     break 'game;
                 }
-                Event::MouseMotion { xrel, .. } => {
-                    player.angle += (xrel as f32) * mouse_sensitivity;
-                    use std::f32::consts::PI;
-                    if player.angle > PI {
-                        player.angle -= 2.0 * PI;
-                    }
-                    if player.angle < -PI {
-                        player.angle += 2.0 * PI;
-                    }
-                }
+                Event::KeyDown {
+                    keycode: Some(Keycode::Num1),
+                    ..
+                } => pick = Some(Weapon::Pistol),
+                Event::KeyDown {
+                    keycode: Some(Keycode::Num2),
+                    ..
+                } => pick = Some(Weapon::Rifle),
+                Event::KeyDown {
+                    keycode: Some(Keycode::Num3),
+                    ..
+                } => pick = Some(Weapon::Rocket),
+                Event::KeyDown {
+                    keycode: Some(Keycode::Num4),
+                    ..
+                } => pick = Some(Weapon::Grenade),
+                Event::KeyDown {
+                    keycode: Some(Keycode::Return),
+                    ..
+                } if net.is_some() => typing = Some(String::new()),
+                // while dead, space switches who we watch
+                Event::KeyDown {
+                    keycode: Some(Keycode::Space),
+                    ..
+                } if player.health == 0 => watching = next_spectate(&others, watching),
                 _ => {}
             }
         }
 
+        if let Some(weapon) = pick {
+            trigger.weapon = weapon;
+            if let Some(conn) = &mut net {
+                conn.select_weapon(weapon)?;
+            }
+        }
+        if let (Some(conn), Some(line)) = (&mut net, said) {
+            conn.say(&line)?;
+        }
+
         let now = Instant::now();
         let dt = (now - last).as_secs_f32();
         last = now;
 
-        let kbd = event_pump.keyboard_state();
-        update_player(&mut player, &grid, &kbd, dt);
+        let mut cmd = input.build(&event_pump.keyboard_state(), &event_pump.mouse_state(), dt);
+        if typing.is_some() {
+            // standing still while typing, W and S are letters now
+            cmd = PlayerInput {
+                tick: cmd.tick,
+                dt,
+                ..PlayerInput::default()
+            };
+        }
+        let shots: Vec<_>;
+        if let Some(conn) = &mut net {
+            let delivered = conn.update(cmd)?;
+            player = conn.view();
+            others = conn.others();
+            shots = conn.projectiles.clone();
+            for msg in delivered {
+                match msg {
+                    ServerMessage::Kill {
+                        killer,
+                        victim,
+                        weapon,
+                    } => {
+                        let kill = Kill {
+                            killer,
+                            victim,
+                            weapon,
+                        };
+                        let line = kill_text(
+                            &name_of(&others, me, killer),
+                            &name_of(&others, me, victim),
+                            &kill,
+                        );
+                        killfeed.push((line, KILLFEED_TIME));
+                        if victim == me {
+                            respawns.push((me, RESPAWN_DELAY));
+                        }
+                    }
+                    ServerMessage::Chat { from, text } => {
+                        chat.push((format!("{}: {text}", name_of(&others, me, from)), CHAT_TIME));
+                    }
+                    ServerMessage::Joined { name, .. } => {
+                        chat.push((format!("{name} joined"), CHAT_TIME));
+                    }
+                    ServerMessage::Left { id } => {
+                        chat.push((format!("{} left", name_of(&others, me, id)), CHAT_TIME));
+                    }
+                    ServerMessage::Welcome { .. } => {
+                        tiles = conn.level.tiles.clone();
+                        seed_text = map_text(&conn.map, &conn.level);
+                        watching = None;
+                    }
+                    _ => {}
+                }
+            }
+            // only a countdown to show, the server brings us back
+            for (_, left) in &mut respawns {
+                *left -= dt;
+            }
+            respawns.retain(|r| r.1 > 0.0);
+        } else {
+            let alive = player.health > 0;
+            // the dead stand still
+            if alive {
+                update_player(&mut player, &tiles, &cmd);
+            }
+            let mut targets: Vec<_> = others
+                .iter()
+                .filter(|o| o.alive)
+                .map(|o| (o.id, o.x, o.y))
+                .collect();
+            if alive {
+                targets.push((me, player.x, player.y));
+            }
+            let mut hits = Vec::new();
+            if trigger.update(alive && cmd.fire(), dt) {
+                let shooter = (me, player.x, player.y, player.angle);
+                hits.extend(fire(
+                    trigger.weapon,
+                    &tiles,
+                    shooter,
+                    &targets,
+                    &mut projectiles,
+                    &mut next_projectile_id,
+                ));
+            }
+            hits.extend(update_projectiles(&mut projectiles, &tiles, &targets, dt));
+            let mut kills = Vec::new();
+            for hit in hits {
+                let killed = if hit.target == me {
+                    apply_damage(&mut player, hit.damage)
+                } else if let Some(dummy) = others.iter_mut().find(|o| o.id == hit.target) {
+                    // dummies have no armor
+                    let was_alive = dummy.alive;
+                    dummy.health = dummy.health.saturating_sub(hit.damage);
+                    dummy.alive = dummy.health > 0;
+                    was_alive && !dummy.alive
+                } else {
+                    false
+                };
+                if killed {
+                    respawns.push((hit.target, RESPAWN_DELAY));
+                    kills.push(Kill {
+                        killer: hit.attacker,
+                        victim: hit.target,
+                        weapon: hit.weapon,
+                    });
+                }
+            }
+            for kill in kills {
+                let (killer, victim) = (
+                    name_of(&others, me, kill.killer),
+                    name_of(&others, me, kill.victim),
+                );
+                killfeed.push((kill_text(&killer, &victim, &kill), KILLFEED_TIME));
+            }
 
-        // // --- render world for 2k---
-        // cast_and_draw_columns(&mut canvas, &grid, &player, 1200, 1200, 300)?;
-        // canvas.set_draw_color(Color::RGB(0, 0, 0));
-        // canvas.fill_rect(Rect::new(0, 900, 1200, 300))?;
-        // draw_minimap_from_grid(&mut canvas, &grid, &player, 10, 8, 340, 920)?;
+            // everyone comes back away from whoever else is alive
+            for (id, left) in &mut respawns {
+                *left -= dt;
+                if *left > 0.0 {
+                    continue;
+                }
+                let mut alive: Vec<_> = others
+                    .iter()
+                    .filter(|o| o.alive && o.id != *id)
+                    .map(|o| (o.x, o.y))
+                    .collect();
+                if *id != me && player.health > 0 {
+                    alive.push((player.x, player.y));
+                }
+                let at = choose_spawn(&tiles, &spawns, &alive, &alive);
+                if *id == me {
+                    respawn(&mut player, at);
+                    watching = None;
+                } else if let Some(dummy) = others.iter_mut().find(|o| o.id == *id) {
+                    *dummy = OtherPlayer {
+                        x: at.0,
+                        y: at.1,
+                        health: MAX_HEALTH,
+                        alive: true,
+                        ..dummy.clone()
+                    };
+                }
+            }
+            respawns.retain(|r| r.1 > 0.0);
+            for other in &mut others {
+                other.anim_time += dt;
+            }
+            shots = projectiles.iter().map(Projectile::state).collect();
+        }
+        for line in killfeed.iter_mut().chain(&mut chat) {
+            line.1 -= dt;
+        }
+        killfeed.retain(|line| line.1 > 0.0);
+        chat.retain(|line| line.1 > 0.0);
+        if chat.len() > CHAT_LINES {
+            chat.drain(..chat.len() - CHAT_LINES);
+        }
+        let camera = if player.health > 0 {
+            player
+        } else {
+            death_camera(&player, &others, watching)
+        };
 
-        // --- render world for 1080p---
-        cast_and_draw_columns(&mut canvas, &grid, &player, &others, 1200, 1000, 200)?;
-        canvas.set_draw_color(Color::RGB(0, 0, 0)); // color for the map background at the bottom
-        canvas.fill_rect(Rect::new(0, 800, 1200, 200))?; // draw black rectangle at the bottom
-        draw_minimap_from_grid(&mut canvas, &grid, &player, 8, 6, 340, 800)?;
+        // --- render world + minimap (for 2k use 1200x1200 in sdl2_win and frame_w/h) ---
+        let tags = render_frame(
+            &mut frame,
+            &mut depth,
+            frame_w,
+            frame_h,
+            &tiles,
+            &wall_textures,
+            &camera,
+            Actors {
+                others: &others,
+                projectiles: &shots,
+            },
+        );
+        present_frame(&mut frame_texture, &frame, frame_w)?;
+        canvas.copy(&frame_texture, None, None)?;
+        for tag in &tags {
+            let pos = (tag.x, tag.y);
+            tag_font.draw_tag(
+                &mut canvas,
+                &texture_creator,
+                &tag.name,
+                pos,
+                tag.team.color(),
+            )?;
+        }
+        // crosshair in the middle of the 3d view, the bottom fifth is the minimap
+        let (cx, cy) = (frame_w as i32 / 2, (frame_h * 4 / 5) as i32 / 2);
+        canvas.set_draw_color(Color::RGB(255, 255, 255));
+        canvas.fill_rect(Rect::new(cx - 8, cy - 1, 16, 2))?;
+        canvas.fill_rect(Rect::new(cx - 1, cy - 8, 2, 16))?;
         // --- update + draw FPS ---
         fps_counter.update();
         fps_counter.draw(&mut canvas, &texture_creator)?;
+        fps_counter.draw_text(&mut canvas, &texture_creator, &seed_text, 20, 50)?;
+        let status = match respawns.iter().find(|r| r.0 == me) {
+            Some((_, left)) => format!("dead, back in {left:.1}s (space to spectate)"),
+            // online the server decides when
+            None if player.health == 0 => "dead (space to spectate)".to_string(),
+            None => format!(
+                "health {}  armor {}  {}",
+                player.health,
+                player.armor,
+                trigger.weapon.name()
+            ),
+        };
+        fps_counter.draw_text(&mut canvas, &texture_creator, &status, 20, 80)?;
+        for (i, (line, _)) in killfeed.iter().enumerate() {
+            let y = 20 + 30 * i as i32;
+            fps_counter.draw_text(&mut canvas, &texture_creator, line, frame_w as i32 - 360, y)?;
+        }
+        // newest at the bottom, just above the minimap
+        let mut lines: Vec<String> = chat.iter().map(|(line, _)| line.clone()).collect();
+        if let Some(line) = &typing {
+            lines.push(format!("say: {line}_"));
+        }
+        let bottom = (frame_h * 4 / 5) as i32 - 40;
+        for (i, line) in lines.iter().rev().enumerate() {
+            let y = bottom - 30 * i as i32;
+            fps_counter.draw_text(&mut canvas, &texture_creator, line, 20, y)?;
+        }
 
         canvas.present();
     }
 
     Ok(())
 }
+
+// for the killfeed and the chat, someone we haven't seen yet by the server's default name
+fn name_of(others: &[OtherPlayer], me: u32, id: u32) -> String {
+    if id == me {
+        return "you".to_string();
+    }
+    match others.iter().find(|o| o.id == id) {
+        Some(o) => o.name.clone(),
+        None => format!("player {id}"),
+    }
+}
+
+// the map line on screen, a hand made map by name and a generated one by seed
+fn map_text(map: &MapConfig, level: &MapFile) -> String {
+    if map.file.is_some() {
+        format!("map: {} v{}", level.name, level.version)
+    } else {
+        format!("seed: {} ({})", map.seed, map.algorithm)
+    }
+}
@@ -1,10 +1,11 @@
-use crate::render::Player;
+use crate::mazegen::MazeGenerator;
+use crate::render::{Actors, Player, draw_line, fill_rect, rgb};
+use crate::tilemap::{Tile, TileMap};
+use crate::weapons::ProjectileKind;
+use rand::Rng;
+use rand::SeedableRng;
 use rand::seq::SliceRandom;
-use rand::thread_rng;
-use sdl2::pixels::Color;
-use sdl2::rect::Rect;
-use sdl2::render::Canvas;
-use sdl2::video::Window;
+use rand_chacha::ChaCha8Rng;
 #[derive(Debug, Clone, Copy)]
 pub struct Cell {
     visited: bool,
@@ -12,54 +13,149 @@ pub struct Cell {
 }
 
 impl Cell {
-    fn new() -> Self {
+    pub(crate) fn new() -> Self {
         Self {
             visited: false,
             walls: [true; 4],
         }
     }
+
+    // top, right, bottom, left, true means the wall is there
+    pub fn walls(&self) -> [bool; 4] {
+        self.walls
+    }
 }
 
 //for genrating a random maze where each x,y is a cell with 4 walls and flag visited
-pub fn generate_maze(w: usize, h: usize) -> Vec<Vec<Cell>> {
+// the same seed gives the same maze on every machine, so the server only has to send the seed
+pub fn generate_maze(w: usize, h: usize, seed: u64) -> Vec<Vec<Cell>> {
+    // chacha8 instead of StdRng because StdRng may change between rand releases
+    generate_maze_with_rng(w, h, &mut ChaCha8Rng::seed_from_u64(seed))
+}
+
+pub fn generate_maze_with_rng<R: Rng>(w: usize, h: usize, rng: &mut R) -> Vec<Vec<Cell>> {
     let mut grid = vec![vec![Cell::new(); w]; h];
-    backtrack(0, 0, w, h, &mut grid);
+    backtrack(0, 0, w, h, &mut grid, rng);
     grid
 }
-// recursive backtracking to gernerate a random maze of size w x h
-fn backtrack(x: usize, y: usize, w: usize, h: usize, grid: &mut Vec<Vec<Cell>>) {
-    grid[y][x].visited = true;
 
+// same as generate_maze but with any algorithm from mazegen.rs
+pub fn generate_maze_using(
+    generator: &dyn MazeGenerator,
+    w: usize,
+    h: usize,
+    seed: u64,
+) -> Vec<Vec<Cell>> {
+    generator.generate(w, h, &mut ChaCha8Rng::seed_from_u64(seed))
+}
+
+// a fresh seed for when the player didn't pick one
+pub fn random_seed() -> u64 {
+    rand::rng().random()
+}
+
+// (dx, dy, wall_index, opposite_wall_index) for example
+// (0, -1, 0, 2) means moving up (dy=-1) removes the top wall of current cell (index 0) and 2 is the bottom wall of the neighbor cell
+pub(crate) const DIRS: [(isize, isize, usize, usize); 4] =
+    [(0, -1, 0, 2), (1, 0, 1, 3), (0, 1, 2, 0), (-1, 0, 3, 1)];
+
+// the cell next to (x, y) in direction dir (index into DIRS), None at the maze edge
+pub(crate) fn neighbor(
+    x: usize,
+    y: usize,
+    dir: usize,
+    w: usize,
+    h: usize,
+) -> Option<(usize, usize)> {
+    let (dx, dy, _, _) = DIRS[dir];
+    let nx = x as isize + dx;
+    let ny = y as isize + dy;
+    if nx >= 0 && ny >= 0 && (nx as usize) < w && (ny as usize) < h {
+        Some((nx as usize, ny as usize))
+    } else {
+        None
+    }
+}
+
+// remove the wall between (x, y) and its neighbor in direction dir, the neighbor must exist
+pub(crate) fn carve(maze: &mut [Vec<Cell>], x: usize, y: usize, dir: usize) -> (usize, usize) {
+    let (dx, dy, wall, opp_wall) = DIRS[dir];
+    let nx = (x as isize + dx) as usize;
+    let ny = (y as isize + dy) as usize;
+    maze[y][x].walls[wall] = false;
+    maze[ny][nx].walls[opp_wall] = false;
+    (nx, ny)
+}
+
+// one cell on the backtracking path: its position, its shuffled directions
+// (indexes into DIRS) and how many of them we already tried
+struct Frame {
+    x: u32,
+    y: u32,
+    dirs: [u8; 4],
+    next: u8,
+}
+
+fn frame<R: Rng + ?Sized>(x: usize, y: usize, rng: &mut R) -> Frame {
     // shuffle directions to ensure random
-        // each tuple is (dx, dy, wall_index, opposite_wall_index) for example 
-        // (0, -1, 0, 2) means moving up (dy=-1) removes the top wall of current cell (index 0) and 2 is the bottom wall of the neighbor cell
-    let mut dirs = vec![(0, -1, 0, 2), (1, 0, 1, 3), (0, 1, 2, 0), (-1, 0, 3, 1)];
-    dirs.shuffle(&mut thread_rng());
+    let mut dirs = [0, 1, 2, 3];
+    dirs.shuffle(rng);
+    Frame {
+        x: x as u32,
+        y: y as u32,
+        dirs,
+        next: 0,
+    }
+}
+
+// backtracking to gernerate a random maze of size w x h
+// it used to recurse once per cell which overflowed the stack around 500x500,
+// now the path lives in a Vec so only memory limits the size.
+// the rng is used in the same order as the recursive version so seeds give the same mazes
+pub(crate) fn backtrack<R: Rng + ?Sized>(
+    x: usize,
+    y: usize,
+    w: usize,
+    h: usize,
+    grid: &mut [Vec<Cell>],
+    rng: &mut R,
+) {
+    grid[y][x].visited = true;
+    let mut stack = vec![frame(x, y, rng)];
+
+    while let Some(top) = stack.last_mut() {
+        // tried all directions, go back one cell
+        if top.next == 4 {
+            stack.pop();
+            continue;
+        }
+        let (dx, dy, wall, opp_wall) = DIRS[top.dirs[top.next as usize] as usize];
+        top.next += 1;
+        let (x, y) = (top.x as usize, top.y as usize);
 
-    // try all directions
-    for &(dx, dy, wall, opp_wall) in &dirs {
         let nx = x as isize + dx; // neighbor x
         let ny = y as isize + dy; // neighbor y
         // check maze edges
         if nx >= 0 && ny >= 0 && (nx as usize) < w && (ny as usize) < h {
-            let (nx, ny) = (nx as usize, ny as usize); 
+            let (nx, ny) = (nx as usize, ny as usize);
             // if the neighbor cell is not visited
             if !grid[ny][nx].visited {
                 // remove the wall between current and neighbor cell
                 grid[y][x].walls[wall] = false;
 
                 grid[ny][nx].walls[opp_wall] = false;
-                // recursively backtrack from the neighbor cell
-                backtrack(nx, ny, w, h, grid);
+                // continue from the neighbor cell
+                grid[ny][nx].visited = true;
+                stack.push(frame(nx, ny, rng));
             }
         }
     }
 }
 
 // convert the maze to a grid for 3D rendering
-pub fn maze_to_grid(maze: &Vec<Vec<Cell>>) -> Vec<Vec<u8>> {
-    let h = maze.len(); 
-    let w = maze[0].len(); 
+pub fn maze_to_grid(maze: &[Vec<Cell>]) -> Vec<Vec<u8>> {
+    let h = maze.len();
+    let w = maze[0].len();
     // we use *2+1 to leave space for walls so we make the cell 2*2 + 1 for the wall
     let gh = h * 2 + 1;
     let gw = w * 2 + 1;
@@ -69,11 +165,9 @@ pub fn maze_to_grid(maze: &Vec<Vec<Cell>>) -> Vec<Vec<u8>> {
     //every cell in the maze takes a 3x3 area in the grid with walls
     //look at the drawing in t.tldr
 
-    
-
-    for cy in 0..h {
+    for (cy, row) in maze.iter().enumerate() {
         //cell index cy
-        for cx in 0..w {
+        for (cx, &cell) in row.iter().enumerate() {
             //cell index cx
 
             // center of the maze cell * 2 to leave space for walls
@@ -83,7 +177,6 @@ pub fn maze_to_grid(maze: &Vec<Vec<Cell>>) -> Vec<Vec<u8>> {
 
             //
             // walls: [top, right, bottom, left]
-            let cell = maze[cy][cx];
 
             // open passage if there is NO wall
             // reminder : walls are like {true : 0 , false : 1 ...}
@@ -108,43 +201,74 @@ pub fn maze_to_grid(maze: &Vec<Vec<Cell>>) -> Vec<Vec<u8>> {
     grid
 }
 
+// draws into the same kind of framebuffer as cast_and_draw_columns, fb_w pixels per row.
+// scale is the size of one tile in pixels, origin the top left corner, clipped to fb
 pub fn draw_minimap_from_grid(
-    canvas: &mut Canvas<Window>,
-    grid: &[Vec<u8>],
+    fb: &mut [u32],
+    fb_w: usize,
+    map: &TileMap,
     player: &Player,
-    scale_x: i32,
-    scale_y: i32,
-    ox: i32,
-    oy: i32,
-) -> Result<(), String> {
+    actors: Actors,
+    (scale_x, scale_y): (i32, i32),
+    (ox, oy): (i32, i32),
+) {
     // walls
+    for (gx, gy, tile) in map.iter() {
+        let color = match tile {
+            Tile::Wall(_) => (241, 241, 238),
+            Tile::Door => (150, 100, 50),
+            Tile::Window => (150, 200, 230),
+            Tile::Hazard => (120, 30, 30),
+            Tile::Empty | Tile::Spawn => continue,
+        };
+        let x = ox + (gx as i32) * scale_x;
+        let y = oy + (gy as i32) * scale_y;
+        fill_rect(fb, fb_w, (x, y), (scale_x, scale_y), rgb(color));
+    }
 
-
-    
-
-    for (gy, row) in grid.iter().enumerate() {
-        for (gx, &cell) in row.iter().enumerate() {
-            if cell != 0 {
-                let x = ox + (gx as i32) * scale_x;
-                let y = oy + (gy as i32) * scale_y;
-                canvas.set_draw_color(Color::RGB(241, 241, 238));
-                canvas.fill_rect(Rect::new(x, y, scale_x as u32, scale_y as u32))?;
-            }
+    // everyone else, smaller squares in their team color, the dead in grey
+    let look_len = (2 * scale_x).max(2) as f32;
+    for other in actors.others {
+        let ox = ox + (other.x * scale_x as f32) as i32;
+        let oy = oy + (other.y * scale_y as f32) as i32;
+        let color = if other.alive {
+            rgb(other.team.color())
+        } else {
+            rgb((90, 90, 90))
+        };
+        fill_rect(fb, fb_w, (ox - 3, oy - 3), (6, 6), color);
+        if other.alive {
+            let lx = ox as f32 + other.angle.cos() * look_len;
+            let ly = oy as f32 + other.angle.sin() * look_len;
+            draw_line(fb, fb_w, (ox, oy), (lx as i32, ly as i32), color);
         }
     }
 
+    // rockets and grenades, small dots
+    for shot in actors.projectiles {
+        let sx = ox + (shot.x * scale_x as f32) as i32;
+        let sy = oy + (shot.y * scale_y as f32) as i32;
+        let color = match shot.kind {
+            ProjectileKind::Rocket => (255, 150, 30),
+            ProjectileKind::Grenade => (120, 220, 60),
+        };
+        fill_rect(fb, fb_w, (sx - 1, sy - 1), (3, 3), rgb(color));
+    }
+
     // player marker (red square)
     let px = ox + (player.x * scale_x as f32) as i32;
     let py = oy + (player.y * scale_y as f32) as i32;
-    canvas.set_draw_color(Color::RGB(255, 0, 0));
-    canvas.fill_rect(Rect::new(px - 2, py - 2, 8, 8))?;
+    fill_rect(fb, fb_w, (px - 2, py - 2), (8, 8), rgb((255, 0, 0)));
 
     // facing direction (yellow line)
     let look_len = (3 * scale_x).max(3) as f32;
     let lx = px as f32 + player.angle.cos() * look_len;
     let ly = py as f32 + player.angle.sin() * look_len;
-    canvas.set_draw_color(Color::RGB(255, 255, 0));
-    canvas.draw_line((px, py), (lx as i32, ly as i32))?;
-
-    Ok(())
+    draw_line(
+        fb,
+        fb_w,
+        (px, py),
+        (lx as i32, ly as i32),
+        rgb((255, 255, 0)),
+    );
 }
@@ -0,0 +1,331 @@
+// hand authored maps in a plain text file
+//
+//   # comments start with #
+//   name: Corridors
+//   author: someone
+//   version: 3
+//   sky: yes            (optional, open sky instead of a ceiling)
+//   ---
+//   #########
+//   #S..h..S#
+//   #.##2##.#
+//   #########
+//
+// legend below the --- line, every row must be the same width and the border must be walls:
+//   .  floor             S  spawn point (floor)
+//   #  wall (1)          1-9  wall with that texture
+//   D  door              =  window           ~  hazard floor
+//   h  health pickup     a  armor pickup     w  weapon pickup   (all on floor)
+
+use crate::spawn::auto_spawns;
+use crate::tilemap::{Tile, TileMap};
+use std::fmt;
+use std::path::Path;
+
+pub const EXTENSION: &str = "map";
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum PickupKind {
+    Health,
+    Armor,
+    Weapon,
+}
+
+impl PickupKind {
+    fn symbol(self) -> char {
+        match self {
+            PickupKind::Health => 'h',
+            PickupKind::Armor => 'a',
+            PickupKind::Weapon => 'w',
+        }
+    }
+
+    fn from_symbol(c: char) -> Option<Self> {
+        match c {
+            'h' => Some(PickupKind::Health),
+            'a' => Some(PickupKind::Armor),
+            'w' => Some(PickupKind::Weapon),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Pickup {
+    pub x: usize,
+    pub y: usize,
+    pub kind: PickupKind,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct MapFile {
+    pub name: String,
+    pub author: String,
+    // revision of the map, so server and clients can tell two edits apart
+    pub version: u32,
+    pub tiles: TileMap,
+    // Tile::Spawn tiles in file order, the player stands in the middle of the tile
+    pub spawns: Vec<(usize, usize)>,
+    pub pickups: Vec<Pickup>,
+}
+
+// where and why a map file didn't parse, line and column start at 1
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+fn error(line: usize, column: usize, message: impl Into<String>) -> ParseError {
+    ParseError {
+        line,
+        column,
+        message: message.into(),
+    }
+}
+
+impl MapFile {
+    // wraps a generated grid so it can be saved, no spawns or pickups yet
+    pub fn from_grid(name: &str, author: &str, grid: &[Vec<u8>]) -> Self {
+        Self {
+            name: name.to_string(),
+            author: author.to_string(),
+            version: 1,
+            tiles: TileMap::from_grid(grid),
+            spawns: Vec::new(),
+            pickups: Vec::new(),
+        }
+    }
+
+    // marks a floor tile as a spawn point, walls can't be one
+    pub fn add_spawn(&mut self, x: usize, y: usize) {
+        if let Some(tile) = self.tiles.get_mut(x, y)
+            && !tile.is_solid()
+        {
+            *tile = Tile::Spawn;
+            self.spawns.push((x, y));
+        }
+    }
+
+    // a map nobody put spawn points on gets them spread out automatically
+    pub fn add_auto_spawns(&mut self) {
+        if self.spawns.is_empty() {
+            for (x, y) in auto_spawns(&self.tiles) {
+                self.add_spawn(x, y);
+            }
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Self, ParseError> {
+        let mut name = None;
+        let mut author = None;
+        let mut version = None;
+        let mut sky = false;
+        let mut lines = text.lines().enumerate().map(|(i, l)| (i + 1, l));
+
+        // header
+        let mut separator = None;
+        for (n, line) in lines.by_ref() {
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+            if trimmed == "---" {
+                separator = Some(n);
+                break;
+            }
+            let Some((key, value)) = line.split_once(':') else {
+                return Err(error(n, 1, "expected `key: value` or `---`"));
+            };
+            let value = value.trim();
+            // in chars like the grid errors, not bytes
+            let chars = |s: &str| s.chars().count();
+            let value_col = chars(line) - chars(line[key.len() + 1..].trim_start()) + 1;
+            let slot = match key.trim() {
+                "name" => &mut name,
+                "author" => &mut author,
+                "version" => {
+                    let v = value.parse::<u32>().map_err(|_| {
+                        error(
+                            n,
+                            value_col,
+                            format!("version must be a number, got {value:?}"),
+                        )
+                    })?;
+                    version = Some(v);
+                    continue;
+                }
+                "sky" => {
+                    sky = match value {
+                        "yes" | "true" => true,
+                        "no" | "false" => false,
+                        _ => {
+                            return Err(error(
+                                n,
+                                value_col,
+                                format!("sky must be yes or no, got {value:?}"),
+                            ));
+                        }
+                    };
+                    continue;
+                }
+                other => {
+                    let col = chars(line) - chars(line.trim_start()) + 1;
+                    return Err(error(n, col, format!("unknown header key {other:?}")));
+                }
+            };
+            *slot = Some(value.to_string());
+        }
+        let Some(separator) = separator else {
+            let last = text.lines().count().max(1);
+            return Err(error(last, 1, "missing `---` line between header and grid"));
+        };
+        for (key, missing) in [
+            ("name", name.is_none()),
+            ("author", author.is_none()),
+            ("version", version.is_none()),
+        ] {
+            if missing {
+                return Err(error(separator, 1, format!("header is missing `{key}`")));
+            }
+        }
+
+        // grid
+        let mut grid: Vec<Vec<Tile>> = Vec::new();
+        let mut spawns = Vec::new();
+        let mut pickups = Vec::new();
+        let mut rows = Vec::new(); // file line of every grid row, for errors
+        for (n, line) in lines {
+            let line = line.trim_end();
+            if line.is_empty() {
+                continue;
+            }
+            let y = grid.len();
+            let mut row = Vec::with_capacity(line.len());
+            for (x, c) in line.chars().enumerate() {
+                let tile = match c {
+                    '.' => Tile::Empty,
+                    '#' => Tile::Wall(1),
+                    '1'..='9' => Tile::Wall(c as u8 - b'0'),
+                    'D' => Tile::Door,
+                    '=' => Tile::Window,
+                    '~' => Tile::Hazard,
+                    'S' => {
+                        spawns.push((x, y));
+                        Tile::Spawn
+                    }
+                    _ => match PickupKind::from_symbol(c) {
+                        Some(kind) => {
+                            pickups.push(Pickup { x, y, kind });
+                            Tile::Empty
+                        }
+                        None => return Err(error(n, x + 1, format!("unknown tile {c:?}"))),
+                    },
+                };
+                row.push(tile);
+            }
+            if let Some(first) = grid.first()
+                && row.len() != first.len()
+            {
+                let col = row.len().min(first.len()) + 1;
+                return Err(error(
+                    n,
+                    col,
+                    format!(
+                        "row is {} tiles wide, the first row is {}",
+                        row.len(),
+                        first.len()
+                    ),
+                ));
+            }
+            grid.push(row);
+            rows.push(n);
+        }
+        if grid.len() < 3 || grid[0].len() < 3 {
+            return Err(error(separator + 1, 1, "the grid needs at least 3x3 tiles"));
+        }
+
+        // the raycaster and the collision code both assume the map is closed, a window would
+        // let rays out
+        let (h, w) = (grid.len(), grid[0].len());
+        for (y, row) in grid.iter().enumerate() {
+            for (x, &tile) in row.iter().enumerate() {
+                let border = x == 0 || y == 0 || x == w - 1 || y == h - 1;
+                if border && !tile.is_opaque() {
+                    return Err(error(rows[y], x + 1, "the border of the map must be walls"));
+                }
+            }
+        }
+
+        Ok(Self {
+            name: name.unwrap(),
+            author: author.unwrap(),
+            version: version.unwrap(),
+            tiles: {
+                let mut tiles = TileMap::new(w, h, Tile::Empty);
+                tiles.set_open_sky(sky);
+                for (y, row) in grid.into_iter().enumerate() {
+                    for (x, tile) in row.into_iter().enumerate() {
+                        *tiles.get_mut(x, y).unwrap() = tile;
+                    }
+                }
+                tiles
+            },
+            spawns,
+            pickups,
+        })
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut out = String::new();
+        out.push_str(&format!("name: {}\n", self.name));
+        out.push_str(&format!("author: {}\n", self.author));
+        out.push_str(&format!("version: {}\n", self.version));
+        if self.tiles.open_sky() {
+            out.push_str("sky: yes\n");
+        }
+        out.push_str("---\n");
+        for (x, y, tile) in self.tiles.iter() {
+            let c = match tile {
+                Tile::Empty => match self.pickups.iter().find(|p| (p.x, p.y) == (x, y)) {
+                    Some(p) => p.kind.symbol(),
+                    None => '.',
+                },
+                // anything above 9 doesn't fit in one char, keep it a plain wall
+                Tile::Wall(t @ 2..=9) => (b'0' + t) as char,
+                Tile::Wall(_) => '#',
+                Tile::Door => 'D',
+                Tile::Window => '=',
+                Tile::Spawn => 'S',
+                Tile::Hazard => '~',
+            };
+            out.push(c);
+            if x + 1 == self.tiles.width() {
+                out.push('\n');
+            }
+        }
+        out
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("can't read map {}: {e}", path.display()))?;
+        Self::parse(&text).map_err(|e| format!("{}:{e}", path.display()))
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), String> {
+        let path = path.as_ref();
+        std::fs::write(path, self.to_text())
+            .map_err(|e| format!("can't write map {}: {e}", path.display()))
+    }
+}
@@ -0,0 +1,352 @@
+// maze generation algorithms, all of them make a perfect maze (a spanning tree of the cells)
+// in the same Vec<Vec<Cell>> that maze_to_grid consumes. they differ in the shape:
+//   backtracker - long winding corridors, few dead ends (the original generate_maze)
+//   prim        - lots of short dead ends, branches everywhere
+//   kruskal     - like prim but more uniform, short corridors
+//   wilson      - uniform spanning tree, no bias at all
+//   eller       - row by row, horizontal bias
+//   binarytree  - very fast, long open corridor along the top and left edges
+
+use crate::map::{Cell, backtrack, carve, neighbor};
+use rand::seq::SliceRandom;
+use rand::{Rng, RngCore};
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+pub trait MazeGenerator {
+    // w x h cells, every cell starts with all 4 walls
+    fn generate(&self, w: usize, h: usize, rng: &mut dyn RngCore) -> Vec<Vec<Cell>>;
+}
+
+fn blank(w: usize, h: usize) -> Vec<Vec<Cell>> {
+    vec![vec![Cell::new(); w]; h]
+}
+
+pub struct Backtracker;
+
+impl MazeGenerator for Backtracker {
+    fn generate(&self, w: usize, h: usize, rng: &mut dyn RngCore) -> Vec<Vec<Cell>> {
+        let mut maze = blank(w, h);
+        backtrack(0, 0, w, h, &mut maze, rng);
+        maze
+    }
+}
+
+// randomized prim: grow the maze from one cell, each step picks a random frontier cell
+// and connects it to a random neighbor already in the maze
+pub struct Prim;
+
+impl MazeGenerator for Prim {
+    fn generate(&self, w: usize, h: usize, rng: &mut dyn RngCore) -> Vec<Vec<Cell>> {
+        let mut maze = blank(w, h);
+        let mut in_maze = vec![vec![false; w]; h];
+        let mut in_frontier = vec![vec![false; w]; h];
+        let mut frontier = Vec::new();
+
+        let add = |x: usize,
+                   y: usize,
+                   in_maze: &mut Vec<Vec<bool>>,
+                   in_frontier: &mut Vec<Vec<bool>>,
+                   frontier: &mut Vec<(usize, usize)>| {
+            in_maze[y][x] = true;
+            for dir in 0..4 {
+                if let Some((nx, ny)) = neighbor(x, y, dir, w, h)
+                    && !in_maze[ny][nx]
+                    && !in_frontier[ny][nx]
+                {
+                    in_frontier[ny][nx] = true;
+                    frontier.push((nx, ny));
+                }
+            }
+        };
+
+        let (sx, sy) = (rng.random_range(0..w), rng.random_range(0..h));
+        add(sx, sy, &mut in_maze, &mut in_frontier, &mut frontier);
+
+        while !frontier.is_empty() {
+            let (x, y) = frontier.swap_remove(rng.random_range(0..frontier.len()));
+            let into_maze: Vec<usize> = (0..4)
+                .filter(
+                    |&dir| matches!(neighbor(x, y, dir, w, h), Some((nx, ny)) if in_maze[ny][nx]),
+                )
+                .collect();
+            // a frontier cell always touches the maze, that is how it got on the frontier
+            let dir = into_maze[rng.random_range(0..into_maze.len())];
+            carve(&mut maze, x, y, dir);
+            add(x, y, &mut in_maze, &mut in_frontier, &mut frontier);
+        }
+        maze
+    }
+}
+
+// randomized kruskal: every inner wall in random order, knock it down if the two cells
+// are not connected yet (union-find over the cells)
+pub struct Kruskal;
+
+impl MazeGenerator for Kruskal {
+    fn generate(&self, w: usize, h: usize, rng: &mut dyn RngCore) -> Vec<Vec<Cell>> {
+        let mut maze = blank(w, h);
+        // (x, y, dir) with dir 1 = right, 2 = bottom, so every wall shows up once
+        let mut walls = Vec::with_capacity(w * h * 2);
+        for y in 0..h {
+            for x in 0..w {
+                if x + 1 < w {
+                    walls.push((x, y, 1));
+                }
+                if y + 1 < h {
+                    walls.push((x, y, 2));
+                }
+            }
+        }
+        walls.shuffle(rng);
+
+        let mut sets = DisjointSet::new(w * h);
+        for (x, y, dir) in walls {
+            let (nx, ny) = neighbor(x, y, dir, w, h).unwrap();
+            if sets.union(y * w + x, ny * w + nx) {
+                carve(&mut maze, x, y, dir);
+            }
+        }
+        maze
+    }
+}
+
+// wilson: loop-erased random walks from every cell not in the maze until the walk hits
+// the maze, then the walk becomes a corridor. gives a uniform spanning tree
+pub struct Wilson;
+
+impl MazeGenerator for Wilson {
+    fn generate(&self, w: usize, h: usize, rng: &mut dyn RngCore) -> Vec<Vec<Cell>> {
+        let mut maze = blank(w, h);
+        let mut in_maze = vec![vec![false; w]; h];
+        // direction the walk last left each cell in, overwriting it erases the loop
+        let mut walk_dir = vec![vec![0usize; w]; h];
+
+        in_maze[rng.random_range(0..h)][rng.random_range(0..w)] = true;
+
+        let mut starts: Vec<(usize, usize)> =
+            (0..h).flat_map(|y| (0..w).map(move |x| (x, y))).collect();
+        starts.shuffle(rng);
+
+        for (sx, sy) in starts {
+            if in_maze[sy][sx] {
+                continue;
+            }
+            // random walk until we touch the maze
+            let (mut x, mut y) = (sx, sy);
+            while !in_maze[y][x] {
+                let dir = loop {
+                    let dir = rng.random_range(0..4);
+                    if neighbor(x, y, dir, w, h).is_some() {
+                        break dir;
+                    }
+                };
+                walk_dir[y][x] = dir;
+                (x, y) = neighbor(x, y, dir, w, h).unwrap();
+            }
+            // follow the loop-free path again and carve it
+            let (mut x, mut y) = (sx, sy);
+            while !in_maze[y][x] {
+                in_maze[y][x] = true;
+                (x, y) = carve(&mut maze, x, y, walk_dir[y][x]);
+            }
+        }
+        maze
+    }
+}
+
+// eller: one row at a time, only the sets of the current row are kept.
+// randomly join neighbors in different sets, then drop at least one passage down per set.
+// the last row joins everything that is still apart
+pub struct Eller;
+
+impl MazeGenerator for Eller {
+    fn generate(&self, w: usize, h: usize, rng: &mut dyn RngCore) -> Vec<Vec<Cell>> {
+        let mut maze = blank(w, h);
+        let mut sets = DisjointSet::new(0);
+        let mut row: Vec<Option<usize>> = vec![None; w];
+
+        for y in 0..h {
+            let last = y + 1 == h;
+            let ids: Vec<usize> = row
+                .iter()
+                .map(|s| s.unwrap_or_else(|| sets.add()))
+                .collect();
+
+            for x in 0..w.saturating_sub(1) {
+                if sets.find(ids[x]) != sets.find(ids[x + 1]) && (last || rng.random_bool(0.5)) {
+                    sets.union(ids[x], ids[x + 1]);
+                    carve(&mut maze, x, y, 1);
+                }
+            }
+            if last {
+                break;
+            }
+
+            // group the columns by set, in column order so the result only depends on the seed
+            let mut groups: Vec<Vec<usize>> = Vec::new();
+            let mut group_of: HashMap<usize, usize> = HashMap::new();
+            for (x, &id) in ids.iter().enumerate() {
+                let root = sets.find(id);
+                let g = *group_of.entry(root).or_insert_with(|| {
+                    groups.push(Vec::new());
+                    groups.len() - 1
+                });
+                groups[g].push(x);
+            }
+
+            row = vec![None; w];
+            for mut cols in groups {
+                cols.shuffle(rng);
+                let down = 1 + rng.random_range(0..cols.len());
+                for &x in &cols[..down] {
+                    carve(&mut maze, x, y, 2);
+                    row[x] = Some(ids[x]);
+                }
+            }
+        }
+        maze
+    }
+}
+
+// binary tree: every cell opens either up or left, the top row and left column
+// become one long corridor
+pub struct BinaryTree;
+
+impl MazeGenerator for BinaryTree {
+    fn generate(&self, w: usize, h: usize, rng: &mut dyn RngCore) -> Vec<Vec<Cell>> {
+        let mut maze = blank(w, h);
+        for y in 0..h {
+            for x in 0..w {
+                let dir = match (x > 0, y > 0) {
+                    (true, true) => {
+                        if rng.random_bool(0.5) {
+                            0
+                        } else {
+                            3
+                        }
+                    }
+                    (false, true) => 0,
+                    (true, false) => 3,
+                    (false, false) => continue,
+                };
+                carve(&mut maze, x, y, dir);
+            }
+        }
+        maze
+    }
+}
+
+// union-find with path halving, sets are numbered 0..len
+struct DisjointSet {
+    parent: Vec<usize>,
+}
+
+impl DisjointSet {
+    fn new(len: usize) -> Self {
+        Self {
+            parent: (0..len).collect(),
+        }
+    }
+
+    fn add(&mut self) -> usize {
+        self.parent.push(self.parent.len());
+        self.parent.len() - 1
+    }
+
+    fn find(&mut self, mut i: usize) -> usize {
+        while self.parent[i] != i {
+            self.parent[i] = self.parent[self.parent[i]];
+            i = self.parent[i];
+        }
+        i
+    }
+
+    // false if a and b were already in the same set
+    fn union(&mut self, a: usize, b: usize) -> bool {
+        let (ra, rb) = (self.find(a), self.find(b));
+        if ra == rb {
+            return false;
+        }
+        self.parent[ra] = rb;
+        true
+    }
+}
+
+// the algorithm picked on the command line (--maze <name>) or in MapConfig
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum MazeAlgorithm {
+    #[default]
+    Backtracker,
+    Prim,
+    Kruskal,
+    Wilson,
+    Eller,
+    BinaryTree,
+}
+
+impl MazeAlgorithm {
+    pub const ALL: [MazeAlgorithm; 6] = [
+        MazeAlgorithm::Backtracker,
+        MazeAlgorithm::Prim,
+        MazeAlgorithm::Kruskal,
+        MazeAlgorithm::Wilson,
+        MazeAlgorithm::Eller,
+        MazeAlgorithm::BinaryTree,
+    ];
+
+    pub fn generator(self) -> &'static dyn MazeGenerator {
+        match self {
+            MazeAlgorithm::Backtracker => &Backtracker,
+            MazeAlgorithm::Prim => &Prim,
+            MazeAlgorithm::Kruskal => &Kruskal,
+            MazeAlgorithm::Wilson => &Wilson,
+            MazeAlgorithm::Eller => &Eller,
+            MazeAlgorithm::BinaryTree => &BinaryTree,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            MazeAlgorithm::Backtracker => "backtracker",
+            MazeAlgorithm::Prim => "prim",
+            MazeAlgorithm::Kruskal => "kruskal",
+            MazeAlgorithm::Wilson => "wilson",
+            MazeAlgorithm::Eller => "eller",
+            MazeAlgorithm::BinaryTree => "binarytree",
+        }
+    }
+
+    // wire id, see ServerMessage::Welcome
+    pub fn to_u8(self) -> u8 {
+        self as u8
+    }
+
+    pub fn from_u8(id: u8) -> Option<Self> {
+        Self::ALL.get(id as usize).copied()
+    }
+}
+
+impl fmt::Display for MazeAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for MazeAlgorithm {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        Self::ALL
+            .into_iter()
+            .find(|a| a.name() == s)
+            .ok_or_else(|| {
+                let names: Vec<&str> = Self::ALL.iter().map(|a| a.name()).collect();
+                format!(
+                    "unknown maze algorithm {s:?}, pick one of {}",
+                    names.join(", ")
+                )
+            })
+    }
+}
@@ -1,24 +1,51 @@
 // src/mechanics.rs
 use crate::render::Player;
-use sdl2::keyboard::{KeyboardState, Scancode};
+use crate::tilemap::TileMap;
+use std::f32::consts::{PI, TAU};
 
 const MOVE_SPEED: f32 = 1.5; // units / second
 const ROT_SPEED: f32 = 1.0; // radians / second  it's the mouse sensitivity
-const RADIUS: f32 = 0.20; // player collision radius (tile = 1.0)  detect collision with walls 
+pub const RADIUS: f32 = 0.20; // player collision radius (tile = 1.0)  detect collision with walls and bullets
 
-pub fn update_player(player: &mut Player, grid: &[Vec<u8>], kbd: &KeyboardState, mut dt: f32) {
+// one frame of what the player asks for, a user command. the client builds it from sdl in
+// one place (input.rs), moves with it right away and sends the very same thing to the server
+// (ClientMessage::Input), so both, a test, a bot or a replay (see prediction.rs) end up in
+// the same spot
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct PlayerInput {
+    // counts up by one every frame starting at 1, the server acks the last one it used
+    pub tick: u32,
+    // how long this frame lasted in seconds
+    pub dt: f32,
+    // -1.0..=1.0 each: W/S, D/A, Right/Left
+    pub forward: f32,
+    pub strafe: f32,
+    pub turn: f32,
+    // mouse turn in radians since the last frame
+    pub yaw: f32,
+    // held buttons, FIRE and so on
+    pub buttons: u8,
+}
+
+impl PlayerInput {
+    pub const FIRE: u8 = 1;
+
+    pub fn fire(&self) -> bool {
+        self.buttons & Self::FIRE != 0
+    }
+}
+
+// mouse turn first, then the keys, the same on the client and the server
+pub fn update_player(player: &mut Player, map: &TileMap, input: &PlayerInput) {
     // acceleration Speeds are per-second; scale by dt to enxure uniform movement speed
     // acroos different frame rates
-    let move_speed = MOVE_SPEED * dt;
-    let rot_speed = ROT_SPEED * dt;
+    let move_speed = MOVE_SPEED * input.dt;
+    let rot_speed = ROT_SPEED * input.dt;
 
     // Rotate / mouse look
-    if kbd.is_scancode_pressed(Scancode::Left) {
-        player.angle -= rot_speed;
-    }
-    if kbd.is_scancode_pressed(Scancode::Right) {
-        player.angle += rot_speed;
-    }
+    player.angle += input.yaw;
+    player.angle += input.turn.clamp(-1.0, 1.0) * rot_speed;
+    player.angle = wrap_angle(player.angle);
 
     // Direction vectors
     let dir_x = player.angle.cos();
@@ -27,25 +54,10 @@ pub fn update_player(player: &mut Player, grid: &[Vec<u8>], kbd: &KeyboardState,
     let side_y = dir_x;
 
     // Input → desired movement vector
-    let mut mv_x = 0.0;
-    let mut mv_y = 0.0;
-
-    if kbd.is_scancode_pressed(Scancode::W) {
-        mv_x += dir_x * move_speed;
-        mv_y += dir_y * move_speed;
-    }
-    if kbd.is_scancode_pressed(Scancode::S) {
-        mv_x -= dir_x * move_speed;
-        mv_y -= dir_y * move_speed;
-    }
-    if kbd.is_scancode_pressed(Scancode::D) {
-        mv_x += side_x * move_speed;
-        mv_y += side_y * move_speed;
-    }
-    if kbd.is_scancode_pressed(Scancode::A) {
-        mv_x -= side_x * move_speed;
-        mv_y -= side_y * move_speed;
-    }
+    let forward = input.forward.clamp(-1.0, 1.0);
+    let strafe = input.strafe.clamp(-1.0, 1.0);
+    let mv_x = (dir_x * forward + side_x * strafe) * move_speed;
+    let mv_y = (dir_y * forward + side_y * strafe) * move_speed;
 
     // if the player is not moving, skip the skip collision checks which is the rest of the function
     if mv_x == 0.0 && mv_y == 0.0 {
@@ -55,56 +67,60 @@ pub fn update_player(player: &mut Player, grid: &[Vec<u8>], kbd: &KeyboardState,
     let (mut nx, mut ny) = (player.x + mv_x, player.y + mv_y); // newest position if no collision
 
     // ---- Collision with radius + axis separation (allows sliding on walls with no sticking ) ----
-    // helper to test if a circle at (x,y) with radius R intersects any wall cell
-    let can_stand = |x: f32, y: f32, grid: &[Vec<u8>]| -> bool {
-        // check the 3x3 neighborhood around the player
-        let xi = x.floor() as i32;
-        let yi = y.floor() as i32;
-        for gy in (yi - 1)..=(yi + 1) {
-            for gx in (xi - 1)..=(xi + 1) {
-                if gy < 0 || gx < 0 {
-                    continue;
-                }
-                let (gyu, gxu) = (gy as usize, gx as usize);
-                if gyu >= grid.len() || gxu >= grid[0].len() {
-                    continue;
-                }
-                if grid[gyu][gxu] == 0 {
-                    continue;
-                } // not a wall
-                // axis-aligned box of the wall cell
-                let wx0 = gxu as f32;
-                let wy0 = gyu as f32;
-                let wx1 = wx0 + 1.0;
-                let wy1 = wy0 + 1.0;
-
-                // closest point on the wall box to (x,y)
-                let cx = x.clamp(wx0, wx1);
-                let cy = y.clamp(wy0, wy1);
-                let dx = x - cx;
-                let dy = y - cy;
-                if dx * dx + dy * dy < RADIUS * RADIUS {
-                    return false; // overlap
-                }
-            }
-        }
-        true
-    };
-
     // try full move
-    if can_stand(nx, ny, grid) {
+    if can_stand(map, nx, ny, RADIUS) {
         player.x = nx;
         player.y = ny;
         return;
     }
     // try X-only (slide along wall)
     nx = player.x + mv_x;
-    if can_stand(nx, player.y, grid) {
+    if can_stand(map, nx, player.y, RADIUS) {
         player.x = nx;
     }
     // try Y-only
     ny = player.y + mv_y;
-    if can_stand(player.x, ny, grid) {
+    if can_stand(map, player.x, ny, RADIUS) {
         player.y = ny;
     }
 }
+
+// helper to test if a circle at (x,y) with radius r stays clear of every solid tile.
+// players use RADIUS, projectiles their own (see weapons.rs)
+pub fn can_stand(map: &TileMap, x: f32, y: f32, r: f32) -> bool {
+    // check the 3x3 neighborhood around the circle, r must stay under one tile
+    let xi = x.floor() as i32;
+    let yi = y.floor() as i32;
+    for gy in (yi - 1)..=(yi + 1) {
+        for gx in (xi - 1)..=(xi + 1) {
+            // outside the map or something you can walk through
+            if !map.at(gx, gy).is_some_and(|t| t.is_solid()) {
+                continue;
+            }
+            // axis-aligned box of the wall cell
+            let wx0 = gx as f32;
+            let wy0 = gy as f32;
+            let wx1 = wx0 + 1.0;
+            let wy1 = wy0 + 1.0;
+
+            // closest point on the wall box to (x,y)
+            let cx = x.clamp(wx0, wx1);
+            let cy = y.clamp(wy0, wy1);
+            let dx = x - cx;
+            let dy = y - cy;
+            if dx * dx + dy * dy < r * r {
+                return false; // overlap
+            }
+        }
+    }
+    true
+}
+
+// back into -PI..=PI after a turn, so the client's prediction and the server agree
+fn wrap_angle(angle: f32) -> f32 {
+    if (-PI..=PI).contains(&angle) {
+        angle
+    } else {
+        (angle + PI).rem_euclid(TAU) - PI
+    }
+}
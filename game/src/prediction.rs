@@ -0,0 +1,109 @@
+// client side prediction: our own player moves the moment a key goes down instead of a round
+// trip later. every input is applied right away with update_player, numbered and kept until a
+// snapshot says the server has used it (PlayerState::ack). then we start over from where the
+// server put us and replay the inputs it hasn't seen yet. when that still ends up somewhere
+// else than where we were drawn (another player's rocket, the server's move budget) the
+// difference fades out over a few frames instead of snapping
+
+use crate::mechanics::{PlayerInput, update_player};
+use crate::protocol::{ClientMessage, PlayerState};
+use crate::render::Player;
+use crate::server::MAX_INPUT_DT;
+use crate::tilemap::TileMap;
+use std::collections::VecDeque;
+
+// about two seconds at 60 fps, with the server further behind than that the oldest go
+pub const MAX_PENDING: usize = 128;
+// how fast a correction fades, per second
+const CORRECTION_RATE: f32 = 10.0;
+// further off than this is a respawn or a teleport, shown right away
+const SNAP_DISTANCE: f32 = 1.0;
+
+#[derive(Debug, Default)]
+pub struct Prediction {
+    // inputs the server hasn't acknowledged yet, oldest first
+    pending: VecDeque<PlayerInput>,
+    // highest ack seen, older snapshots arriving late are ignored
+    acked: u32,
+    // where we are drawn minus where we are predicted to be, shrinks every frame
+    pub offset: (f32, f32),
+}
+
+impl Prediction {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // moves the player right away and gives back the Input to send for it. ticks have to
+    // count up, InputCollector takes care of that
+    pub fn predict(
+        &mut self,
+        player: &mut Player,
+        map: &TileMap,
+        input: PlayerInput,
+    ) -> ClientMessage {
+        // the same limit the server puts on it
+        let input = PlayerInput {
+            dt: input.dt.clamp(0.0, MAX_INPUT_DT),
+            ..input
+        };
+        step(player, map, &input);
+        if self.pending.len() == MAX_PENDING {
+            self.pending.pop_front();
+        }
+        self.pending.push_back(input);
+        ClientMessage::Input(input)
+    }
+
+    // `state` is us in the snapshot that just came in
+    pub fn reconcile(&mut self, player: &mut Player, map: &TileMap, state: &PlayerState) {
+        if state.ack < self.acked {
+            return;
+        }
+        self.acked = state.ack;
+        let drawn = (player.x + self.offset.0, player.y + self.offset.1);
+        self.pending.retain(|input| input.tick > state.ack);
+        player.x = state.x;
+        player.y = state.y;
+        player.angle = state.angle;
+        player.health = state.health;
+        player.armor = state.armor;
+        for input in &self.pending {
+            step(player, map, input);
+        }
+        let (dx, dy) = (drawn.0 - player.x, drawn.1 - player.y);
+        self.offset = if dx.hypot(dy) > SNAP_DISTANCE {
+            (0.0, 0.0)
+        } else {
+            (dx, dy)
+        };
+    }
+
+    // once a frame, lets the correction fade
+    pub fn smooth(&mut self, dt: f32) {
+        let left = (-CORRECTION_RATE * dt).exp();
+        self.offset.0 *= left;
+        self.offset.1 *= left;
+    }
+
+    // where to draw the player: the prediction plus what is left of the correction
+    pub fn view(&self, player: &Player) -> Player {
+        Player {
+            x: player.x + self.offset.0,
+            y: player.y + self.offset.1,
+            ..*player
+        }
+    }
+
+    // inputs still waiting for the server
+    pub fn pending(&self) -> &VecDeque<PlayerInput> {
+        &self.pending
+    }
+}
+
+// what the server does with one input, the dead don't move
+fn step(player: &mut Player, map: &TileMap, input: &PlayerInput) {
+    if player.health > 0 {
+        update_player(player, map, input);
+    }
+}
@@ -0,0 +1,920 @@
+// messages sent over udp between the client and the server
+// every packet is one message: a kind byte followed by its fields in little endian
+//
+// the handshake is Join { version } -> Welcome or Error { VersionMismatch }.
+// the layout of Join and Error must never change so an old client always gets a readable
+// answer, bump PROTOCOL_VERSION whenever anything else changes
+//
+// snapshots are delta encoded: a client acks every snapshot it decodes with SnapshotAck and
+// the server then only sends what changed since the newest one acked, see Baselines. until
+// there is an ack (or it is too old) a full snapshot goes out instead. positions and angles
+// are quantized on the wire, see quantize_position and quantize_angle
+//
+// what must not get lost (kills, chat, joins and leaves, map changes) goes inside Reliable
+// packets: the payloads are encoded messages of their own, resent until acked and handed
+// out in order, see reliable.rs
+
+use crate::mazegen::MazeAlgorithm;
+use crate::mechanics::PlayerInput;
+use crate::reliable::{Ack, CHANNELS, Channel, ReliableMessage, ReliablePacket};
+use crate::render::{OtherPlayer, Team};
+use crate::rooms::{Carve, RoomConfig};
+use crate::sprites::Animation;
+use crate::weapons::{ProjectileKind, ProjectileState, Weapon};
+use std::collections::VecDeque;
+use std::f32::consts::{PI, TAU};
+use std::fmt;
+
+pub const PROTOCOL_VERSION: u16 = 15;
+
+const JOIN: u8 = 1;
+const INPUT: u8 = 2;
+const LEAVE: u8 = 3;
+const PING: u8 = 4;
+const SET_NAME: u8 = 5;
+const SELECT_WEAPON: u8 = 6;
+const SNAPSHOT_ACK: u8 = 7;
+const CLIENT_RELIABLE: u8 = 8;
+const SAY: u8 = 9;
+const WELCOME: u8 = 10;
+const SNAPSHOT: u8 = 11;
+const PONG: u8 = 12;
+const ERROR: u8 = 13;
+const KILL: u8 = 14;
+const SERVER_RELIABLE: u8 = 15;
+const CHAT: u8 = 16;
+const JOINED: u8 = 17;
+const LEFT: u8 = 18;
+
+// the biggest packet either end sends or reads, under the usual 1500 byte mtu
+pub const MAX_PACKET: usize = 1400;
+// what a Reliable packet takes besides its messages: the type, an ack (seq and bits) per
+// channel and the message count
+pub const RELIABLE_HEADER: usize = 1 + CHANNELS * (2 + 4) + 2;
+// and every message besides its payload: channel, seq and length
+pub const RELIABLE_MESSAGE_HEADER: usize = 1 + 2 + 2;
+
+// longest error text we send, keeps the packet well under the mtu
+const MAX_ERROR_LEN: usize = 512;
+const MAX_MAP_NAME_LEN: usize = 64;
+pub const MAX_PLAYER_NAME_LEN: usize = 16;
+pub const MAX_CHAT_LEN: usize = 128;
+
+// positions go over the wire in 1/POSITION_SCALE of a tile, angles in 1/65536 of a turn
+pub const POSITION_SCALE: f32 = 1024.0;
+const ANGLE_STEPS: f32 = 65536.0;
+// snapshots kept on both ends to delta against, about a second at TICK_RATE. an ack older
+// than that gets a full snapshot
+pub const SNAPSHOT_HISTORY: usize = 32;
+
+// what a player entry in a snapshot carries, anything not set is the same as in the baseline
+const CHANGED_NAME: u8 = 1; // and team
+const CHANGED_X: u8 = 2;
+const CHANGED_Y: u8 = 4;
+const CHANGED_ANGLE: u8 = 8;
+const CHANGED_HEALTH: u8 = 16;
+const CHANGED_ARMOR: u8 = 32;
+const CHANGED_STATUS: u8 = 64; // alive and animation
+const CHANGED_ACK: u8 = 128;
+const CHANGED_ALL: u8 = 255;
+
+// one player inside a snapshot, same fields the renderer uses
+#[derive(Debug, PartialEq, Clone)]
+pub struct PlayerState {
+    pub id: u32,
+    // at most MAX_PLAYER_NAME_LEN bytes
+    pub name: String,
+    pub team: Team,
+    pub x: f32,
+    pub y: f32,
+    pub angle: f32,
+    pub health: u8,
+    pub armor: u8,
+    pub alive: bool,
+    pub animation: Animation,
+    // tick of the last Input the server moved this player with, the owner replays the ones after
+    pub ack: u32,
+}
+
+impl PlayerState {
+    // anim_time starts over, the client keeps it running between snapshots
+    pub fn to_other(&self) -> OtherPlayer {
+        OtherPlayer {
+            id: self.id,
+            name: self.name.clone(),
+            team: self.team,
+            x: self.x,
+            y: self.y,
+            angle: self.angle,
+            health: self.health,
+            alive: self.alive,
+            animation: self.animation,
+            anim_time: 0.0,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ErrorCode {
+    VersionMismatch,
+    ServerFull,
+    // a code from a newer server, still shown to the player
+    Other(u8),
+}
+
+impl ErrorCode {
+    fn to_u8(self) -> u8 {
+        match self {
+            ErrorCode::VersionMismatch => 1,
+            ErrorCode::ServerFull => 2,
+            ErrorCode::Other(c) => c,
+        }
+    }
+
+    fn from_u8(c: u8) -> Self {
+        match c {
+            1 => ErrorCode::VersionMismatch,
+            2 => ErrorCode::ServerFull,
+            c => ErrorCode::Other(c),
+        }
+    }
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ErrorCode::VersionMismatch => write!(f, "protocol version mismatch"),
+            ErrorCode::ServerFull => write!(f, "server full"),
+            ErrorCode::Other(c) => write!(f, "error {c}"),
+        }
+    }
+}
+
+// what the server answers to a Join, Err is the Error message to send back
+pub fn check_version(client_version: u16) -> Result<(), ServerMessage> {
+    if client_version == PROTOCOL_VERSION {
+        return Ok(());
+    }
+    Err(ServerMessage::Error {
+        code: ErrorCode::VersionMismatch,
+        message: format!(
+            "client speaks protocol v{client_version} but this server speaks v{PROTOCOL_VERSION}, update your {}",
+            if client_version < PROTOCOL_VERSION {
+                "client"
+            } else {
+                "server"
+            }
+        ),
+    })
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum ClientMessage {
+    Join { version: u16 },
+    // one frame of what the player does. the server moves with every new tick and skips old
+    // or repeated ones. fire is the button being held, the server decides when a shot goes off
+    Input(PlayerInput),
+    Leave,
+    // echoed back in a Pong to measure the round trip
+    Ping { nonce: u32 },
+    // a snapshot arrived and was decoded, the server may delta the next ones against it
+    SnapshotAck { tick: u32 },
+    // shown over our sprite to everyone else, cut to MAX_PLAYER_NAME_LEN bytes
+    SetName { name: String },
+    // what fire shoots from now on, the cooldown of the old weapon still has to run out
+    SelectWeapon { weapon: Weapon },
+    // a line of chat for everyone, sent on Channel::Chat. cut to MAX_CHAT_LEN bytes
+    Say { text: String },
+    // acks and messages of the reliable channels
+    Reliable(ReliablePacket),
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum ServerMessage {
+    // the client rebuilds the maze from these, see MapConfig::build_grid
+    Welcome {
+        id: u32,
+        seed: u64,
+        width: u16,
+        height: u16,
+        algorithm: MazeAlgorithm,
+        braid: u8,
+        rooms: RoomConfig,
+        // name and version from the --map file, empty name for a generated maze
+        map_name: String,
+        map_version: u32,
+        // open sky over the level, from --sky or the map file
+        sky: bool,
+    },
+    // positions and angles come back quantized. a full snapshot keeps the order it was sent
+    // in, one decoded against a baseline comes back sorted by id like the server sends them
+    Snapshot {
+        tick: u32,
+        players: Vec<PlayerState>,
+        // rockets and grenades in flight
+        projectiles: Vec<ProjectileState>,
+    },
+    Pong {
+        nonce: u32,
+    },
+    Error {
+        code: ErrorCode,
+        message: String,
+    },
+    // for the killfeed, sent once when it happens on Channel::Game. killer == victim for
+    // your own splash
+    Kill {
+        killer: u32,
+        victim: u32,
+        weapon: Weapon,
+    },
+    // someone's Say, on Channel::Chat to everyone including them
+    Chat {
+        from: u32,
+        text: String,
+    },
+    // join and leave notices for everyone else on Channel::Game. a Welcome on Channel::Game
+    // means the server switched maps, the client rebuilds the level from it
+    Joined {
+        id: u32,
+        name: String,
+    },
+    Left {
+        id: u32,
+    },
+    // acks and messages of the reliable channels
+    Reliable(ReliablePacket),
+}
+
+impl ClientMessage {
+    // the Join every client should send
+    pub fn join() -> Self {
+        ClientMessage::Join {
+            version: PROTOCOL_VERSION,
+        }
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        match self {
+            ClientMessage::Join { version } => {
+                out.push(JOIN);
+                out.extend_from_slice(&version.to_le_bytes());
+            }
+            ClientMessage::Input(input) => {
+                out.push(INPUT);
+                out.extend_from_slice(&input.tick.to_le_bytes());
+                for v in [input.dt, input.forward, input.strafe, input.turn, input.yaw] {
+                    out.extend_from_slice(&v.to_le_bytes());
+                }
+                out.push(input.buttons);
+            }
+            ClientMessage::Leave => out.push(LEAVE),
+            ClientMessage::Ping { nonce } => {
+                out.push(PING);
+                out.extend_from_slice(&nonce.to_le_bytes());
+            }
+            ClientMessage::SnapshotAck { tick } => {
+                out.push(SNAPSHOT_ACK);
+                out.extend_from_slice(&tick.to_le_bytes());
+            }
+            ClientMessage::SetName { name } => {
+                out.push(SET_NAME);
+                put_str(&mut out, name, MAX_PLAYER_NAME_LEN);
+            }
+            ClientMessage::SelectWeapon { weapon } => {
+                out.push(SELECT_WEAPON);
+                out.push(weapon.to_u8());
+            }
+            ClientMessage::Say { text } => {
+                out.push(SAY);
+                put_str(&mut out, text, MAX_CHAT_LEN);
+            }
+            ClientMessage::Reliable(packet) => {
+                out.push(CLIENT_RELIABLE);
+                put_reliable(&mut out, packet);
+            }
+        }
+        out
+    }
+
+    pub fn decode(buf: &[u8]) -> Result<Self, String> {
+        let mut r = Reader::new(buf);
+        let msg = match r.u8()? {
+            JOIN => ClientMessage::Join { version: r.u16()? },
+            INPUT => ClientMessage::Input(PlayerInput {
+                tick: r.u32()?,
+                dt: r.f32()?,
+                forward: r.f32()?,
+                strafe: r.f32()?,
+                turn: r.f32()?,
+                yaw: r.f32()?,
+                buttons: r.u8()?,
+            }),
+            LEAVE => ClientMessage::Leave,
+            PING => ClientMessage::Ping { nonce: r.u32()? },
+            SNAPSHOT_ACK => ClientMessage::SnapshotAck { tick: r.u32()? },
+            SET_NAME => ClientMessage::SetName { name: r.string()? },
+            SELECT_WEAPON => ClientMessage::SelectWeapon {
+                weapon: Weapon::from_u8(r.u8()?),
+            },
+            SAY => ClientMessage::Say { text: r.string()? },
+            CLIENT_RELIABLE => ClientMessage::Reliable(r.reliable()?),
+            kind => return Err(format!("unknown client message kind {kind}")),
+        };
+        r.finish()?;
+        Ok(msg)
+    }
+}
+
+impl ServerMessage {
+    // snapshots go out full, see encode_delta
+    pub fn encode(&self) -> Vec<u8> {
+        self.encode_against(None)
+    }
+
+    // a Snapshot only carrying what changed since `baseline`, the snapshot the client acked
+    // at `baseline_tick`. anything else encodes the same as with encode
+    pub fn encode_delta(&self, baseline_tick: u32, baseline: &[PlayerState]) -> Vec<u8> {
+        self.encode_against(Some((baseline_tick, baseline)))
+    }
+
+    fn encode_against(&self, baseline: Option<(u32, &[PlayerState])>) -> Vec<u8> {
+        let mut out = Vec::new();
+        match self {
+            ServerMessage::Welcome {
+                id,
+                seed,
+                width,
+                height,
+                algorithm,
+                braid,
+                rooms,
+                map_name,
+                map_version,
+                sky,
+            } => {
+                out.push(WELCOME);
+                out.extend_from_slice(&id.to_le_bytes());
+                out.extend_from_slice(&seed.to_le_bytes());
+                out.extend_from_slice(&width.to_le_bytes());
+                out.extend_from_slice(&height.to_le_bytes());
+                out.push(algorithm.to_u8());
+                out.push(*braid);
+                for c in [rooms.rooms, rooms.plazas, rooms.wide_corridors] {
+                    out.extend_from_slice(&[c.count, c.min, c.max]);
+                }
+                put_str(&mut out, map_name, MAX_MAP_NAME_LEN);
+                out.extend_from_slice(&map_version.to_le_bytes());
+                out.push(u8::from(*sky));
+            }
+            ServerMessage::Snapshot {
+                tick,
+                players,
+                projectiles,
+            } => {
+                out.push(SNAPSHOT);
+                out.extend_from_slice(&tick.to_le_bytes());
+                // how many ticks back the baseline is, 0 for a full snapshot
+                let age = baseline.map_or(0, |(base, _)| tick.wrapping_sub(base));
+                match baseline {
+                    Some((_, base)) if (1..=u8::MAX as u32).contains(&age) => {
+                        out.push(age as u8);
+                        put_players(&mut out, players, Some(base));
+                    }
+                    _ => {
+                        out.push(0);
+                        put_players(&mut out, players, None);
+                    }
+                }
+                // rockets and grenades move every tick and only live for a few seconds,
+                // a delta wouldn't save anything on them
+                out.extend_from_slice(&(projectiles.len() as u16).to_le_bytes());
+                for p in projectiles {
+                    out.extend_from_slice(&p.id.to_le_bytes());
+                    out.push(p.kind.to_u8());
+                    put_var_i32(&mut out, position_quanta(p.x));
+                    put_var_i32(&mut out, position_quanta(p.y));
+                }
+            }
+            ServerMessage::Pong { nonce } => {
+                out.push(PONG);
+                out.extend_from_slice(&nonce.to_le_bytes());
+            }
+            ServerMessage::Error { code, message } => {
+                out.push(ERROR);
+                out.push(code.to_u8());
+                put_str(&mut out, message, MAX_ERROR_LEN);
+            }
+            ServerMessage::Kill {
+                killer,
+                victim,
+                weapon,
+            } => {
+                out.push(KILL);
+                out.extend_from_slice(&killer.to_le_bytes());
+                out.extend_from_slice(&victim.to_le_bytes());
+                out.push(weapon.to_u8());
+            }
+            ServerMessage::Chat { from, text } => {
+                out.push(CHAT);
+                out.extend_from_slice(&from.to_le_bytes());
+                put_str(&mut out, text, MAX_CHAT_LEN);
+            }
+            ServerMessage::Joined { id, name } => {
+                out.push(JOINED);
+                out.extend_from_slice(&id.to_le_bytes());
+                put_str(&mut out, name, MAX_PLAYER_NAME_LEN);
+            }
+            ServerMessage::Left { id } => {
+                out.push(LEFT);
+                out.extend_from_slice(&id.to_le_bytes());
+            }
+            ServerMessage::Reliable(packet) => {
+                out.push(SERVER_RELIABLE);
+                put_reliable(&mut out, packet);
+            }
+        }
+        out
+    }
+
+    // a snapshot made against a baseline can't be decoded here, see Baselines::decode
+    pub fn decode(buf: &[u8]) -> Result<Self, String> {
+        Self::decode_with(buf, &Baselines::new())
+    }
+
+    fn decode_with(buf: &[u8], baselines: &Baselines) -> Result<Self, String> {
+        let mut r = Reader::new(buf);
+        let msg = match r.u8()? {
+            WELCOME => ServerMessage::Welcome {
+                id: r.u32()?,
+                seed: r.u64()?,
+                width: r.u16()?,
+                height: r.u16()?,
+                algorithm: {
+                    let id = r.u8()?;
+                    MazeAlgorithm::from_u8(id)
+                        .ok_or_else(|| format!("unknown maze algorithm id {id}"))?
+                },
+                braid: r.u8()?,
+                rooms: RoomConfig {
+                    rooms: r.carve()?,
+                    plazas: r.carve()?,
+                    wide_corridors: r.carve()?,
+                },
+                map_name: r.string()?,
+                map_version: r.u32()?,
+                sky: r.u8()? != 0,
+            },
+            SNAPSHOT => {
+                let tick = r.u32()?;
+                let base = match r.u8()? {
+                    0 => None,
+                    age => {
+                        let base = tick.wrapping_sub(age as u32);
+                        let players = baselines.get(base).ok_or_else(|| {
+                            format!("snapshot {tick} is a delta against {base} which we don't have")
+                        })?;
+                        Some(players)
+                    }
+                };
+                let players = read_players(&mut r, base)?;
+                let count = r.u16()? as usize;
+                let mut projectiles = Vec::with_capacity(count);
+                for _ in 0..count {
+                    projectiles.push(ProjectileState {
+                        id: r.u32()?,
+                        kind: ProjectileKind::from_u8(r.u8()?),
+                        x: from_position_quanta(r.var_i32()?),
+                        y: from_position_quanta(r.var_i32()?),
+                    });
+                }
+                ServerMessage::Snapshot {
+                    tick,
+                    players,
+                    projectiles,
+                }
+            }
+            PONG => ServerMessage::Pong { nonce: r.u32()? },
+            ERROR => ServerMessage::Error {
+                code: ErrorCode::from_u8(r.u8()?),
+                message: r.string()?,
+            },
+            KILL => ServerMessage::Kill {
+                killer: r.u32()?,
+                victim: r.u32()?,
+                weapon: Weapon::from_u8(r.u8()?),
+            },
+            CHAT => ServerMessage::Chat {
+                from: r.u32()?,
+                text: r.string()?,
+            },
+            JOINED => ServerMessage::Joined {
+                id: r.u32()?,
+                name: r.string()?,
+            },
+            LEFT => ServerMessage::Left { id: r.u32()? },
+            SERVER_RELIABLE => ServerMessage::Reliable(r.reliable()?),
+            kind => return Err(format!("unknown server message kind {kind}")),
+        };
+        r.finish()?;
+        Ok(msg)
+    }
+}
+
+// u16 length then utf8, cut to max bytes on a char boundary
+fn put_str(out: &mut Vec<u8>, s: &str, max: usize) {
+    let mut end = s.len().min(max);
+    while !s.is_char_boundary(end) {
+        end -= 1;
+    }
+    out.extend_from_slice(&(end as u16).to_le_bytes());
+    out.extend_from_slice(&s.as_bytes()[..end]);
+}
+
+// an ack per channel, then the messages with their channel, sequence and payload
+fn put_reliable(out: &mut Vec<u8>, packet: &ReliablePacket) {
+    for ack in packet.acks {
+        out.extend_from_slice(&ack.seq.to_le_bytes());
+        out.extend_from_slice(&ack.bits.to_le_bytes());
+    }
+    out.extend_from_slice(&(packet.messages.len() as u16).to_le_bytes());
+    for msg in &packet.messages {
+        out.push(msg.channel.to_u8());
+        out.extend_from_slice(&msg.seq.to_le_bytes());
+        out.extend_from_slice(&(msg.payload.len() as u16).to_le_bytes());
+        out.extend_from_slice(&msg.payload);
+    }
+}
+
+// the snapshots a client has decoded, deltas are decoded against them. every snapshot that
+// comes in through decode is kept for SNAPSHOT_HISTORY ticks and should be acked with a
+// SnapshotAck so the server can start sending deltas against it
+#[derive(Debug, Default)]
+pub struct Baselines {
+    // (tick, players) oldest first
+    snapshots: VecDeque<(u32, Vec<PlayerState>)>,
+}
+
+impl Baselines {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // decodes any server packet, a delta against a snapshot we no longer have is an error
+    // and the server falls back to full snapshots once our acks stop coming
+    pub fn decode(&mut self, buf: &[u8]) -> Result<ServerMessage, String> {
+        let msg = ServerMessage::decode_with(buf, self)?;
+        if let ServerMessage::Snapshot { tick, players, .. } = &msg {
+            self.insert(*tick, players.clone());
+        }
+        Ok(msg)
+    }
+
+    pub fn get(&self, tick: u32) -> Option<&[PlayerState]> {
+        self.snapshots
+            .iter()
+            .find(|(t, _)| *t == tick)
+            .map(|(_, players)| players.as_slice())
+    }
+
+    // late ones go where they belong, the oldest are dropped
+    fn insert(&mut self, tick: u32, players: Vec<PlayerState>) {
+        let at = self.snapshots.partition_point(|&(t, _)| t < tick);
+        if self.snapshots.get(at).is_some_and(|&(t, _)| t == tick) {
+            return;
+        }
+        self.snapshots.insert(at, (tick, players));
+        while self.snapshots.len() > SNAPSHOT_HISTORY {
+            self.snapshots.pop_front();
+        }
+    }
+}
+
+// the position a client will see after the round trip, 1/POSITION_SCALE of a tile
+pub fn quantize_position(v: f32) -> f32 {
+    from_position_quanta(position_quanta(v))
+}
+
+// the angle a client will see after the round trip, wrapped into -PI..PI
+pub fn quantize_angle(angle: f32) -> f32 {
+    from_angle_quanta(angle_quanta(angle))
+}
+
+fn position_quanta(v: f32) -> i32 {
+    (v * POSITION_SCALE).round() as i32
+}
+
+fn from_position_quanta(q: i32) -> f32 {
+    q as f32 / POSITION_SCALE
+}
+
+fn angle_quanta(angle: f32) -> u16 {
+    ((angle + PI) / TAU * ANGLE_STEPS)
+        .round()
+        .rem_euclid(ANGLE_STEPS) as u16
+}
+
+fn from_angle_quanta(q: u16) -> f32 {
+    q as f32 / ANGLE_STEPS * TAU - PI
+}
+
+// the players in a snapshot: removed ids, then one entry for everyone new or changed since
+// `base` with a mask of what changed. without a base everyone goes in with everything
+fn put_players(out: &mut Vec<u8>, players: &[PlayerState], base: Option<&[PlayerState]>) {
+    let base = base.unwrap_or_default();
+    let removed: Vec<u32> = base
+        .iter()
+        .filter(|b| !players.iter().any(|p| p.id == b.id))
+        .map(|b| b.id)
+        .collect();
+    out.extend_from_slice(&(removed.len() as u16).to_le_bytes());
+    for id in removed {
+        out.extend_from_slice(&id.to_le_bytes());
+    }
+    let changes: Vec<(&PlayerState, PlayerState, u8)> = players
+        .iter()
+        .filter_map(|p| {
+            let (was, mask) = match base.iter().find(|b| b.id == p.id) {
+                Some(b) => (b.clone(), changes(b, p)),
+                None => (blank(p.id), CHANGED_ALL),
+            };
+            (mask != 0).then_some((p, was, mask))
+        })
+        .collect();
+    out.extend_from_slice(&(changes.len() as u16).to_le_bytes());
+    for (p, was, mask) in changes {
+        out.extend_from_slice(&p.id.to_le_bytes());
+        out.push(mask);
+        if mask & CHANGED_NAME != 0 {
+            put_str(out, &p.name, MAX_PLAYER_NAME_LEN);
+            out.push(p.team.to_u8());
+        }
+        // positions as the distance moved, a few bytes instead of four
+        if mask & CHANGED_X != 0 {
+            put_var_i32(
+                out,
+                position_quanta(p.x).wrapping_sub(position_quanta(was.x)),
+            );
+        }
+        if mask & CHANGED_Y != 0 {
+            put_var_i32(
+                out,
+                position_quanta(p.y).wrapping_sub(position_quanta(was.y)),
+            );
+        }
+        if mask & CHANGED_ANGLE != 0 {
+            out.extend_from_slice(&angle_quanta(p.angle).to_le_bytes());
+        }
+        if mask & CHANGED_HEALTH != 0 {
+            out.push(p.health);
+        }
+        if mask & CHANGED_ARMOR != 0 {
+            out.push(p.armor);
+        }
+        if mask & CHANGED_STATUS != 0 {
+            out.push(u8::from(p.alive));
+            out.push(p.animation.to_u8());
+        }
+        if mask & CHANGED_ACK != 0 {
+            put_var_u32(out, p.ack.wrapping_sub(was.ack));
+        }
+    }
+}
+
+// the other half of put_players, `base` has to be the same snapshot the server used
+fn read_players(r: &mut Reader, base: Option<&[PlayerState]>) -> Result<Vec<PlayerState>, String> {
+    let mut players = base.unwrap_or_default().to_vec();
+    let removed = r.u16()?;
+    for _ in 0..removed {
+        let id = r.u32()?;
+        players.retain(|p| p.id != id);
+    }
+    let count = r.u16()? as usize;
+    players.reserve(count);
+    for _ in 0..count {
+        let id = r.u32()?;
+        let mask = r.u8()?;
+        // without a base every entry is a new player, even with a repeated id
+        let at = match base.and_then(|_| players.iter().position(|p| p.id == id)) {
+            Some(at) => at,
+            None => {
+                players.push(blank(id));
+                players.len() - 1
+            }
+        };
+        let p = &mut players[at];
+        if mask & CHANGED_NAME != 0 {
+            p.name = r.string()?;
+            p.team = Team::from_u8(r.u8()?);
+        }
+        if mask & CHANGED_X != 0 {
+            p.x = from_position_quanta(position_quanta(p.x).wrapping_add(r.var_i32()?));
+        }
+        if mask & CHANGED_Y != 0 {
+            p.y = from_position_quanta(position_quanta(p.y).wrapping_add(r.var_i32()?));
+        }
+        if mask & CHANGED_ANGLE != 0 {
+            p.angle = from_angle_quanta(r.u16()?);
+        }
+        if mask & CHANGED_HEALTH != 0 {
+            p.health = r.u8()?;
+        }
+        if mask & CHANGED_ARMOR != 0 {
+            p.armor = r.u8()?;
+        }
+        if mask & CHANGED_STATUS != 0 {
+            p.alive = r.u8()? != 0;
+            p.animation = Animation::from_u8(r.u8()?);
+        }
+        if mask & CHANGED_ACK != 0 {
+            p.ack = p.ack.wrapping_add(r.var_u32()?);
+        }
+    }
+    if base.is_some() {
+        players.sort_by_key(|p| p.id);
+    }
+    Ok(players)
+}
+
+// what a player entry starts from when the baseline doesn't have them
+fn blank(id: u32) -> PlayerState {
+    PlayerState {
+        id,
+        name: String::new(),
+        team: Team::None,
+        x: 0.0,
+        y: 0.0,
+        angle: from_angle_quanta(0),
+        health: 0,
+        armor: 0,
+        alive: false,
+        animation: Animation::Idle,
+        ack: 0,
+    }
+}
+
+// compared the way they go over the wire, a wobble below the quantization isn't a change
+fn changes(was: &PlayerState, now: &PlayerState) -> u8 {
+    let mut mask = 0;
+    if was.name != now.name || was.team != now.team {
+        mask |= CHANGED_NAME;
+    }
+    if position_quanta(was.x) != position_quanta(now.x) {
+        mask |= CHANGED_X;
+    }
+    if position_quanta(was.y) != position_quanta(now.y) {
+        mask |= CHANGED_Y;
+    }
+    if angle_quanta(was.angle) != angle_quanta(now.angle) {
+        mask |= CHANGED_ANGLE;
+    }
+    if was.health != now.health {
+        mask |= CHANGED_HEALTH;
+    }
+    if was.armor != now.armor {
+        mask |= CHANGED_ARMOR;
+    }
+    if was.alive != now.alive || was.animation != now.animation {
+        mask |= CHANGED_STATUS;
+    }
+    if was.ack != now.ack {
+        mask |= CHANGED_ACK;
+    }
+    mask
+}
+
+// 7 bits a byte, low first, the high bit says another byte follows
+fn put_var_u32(out: &mut Vec<u8>, mut v: u32) {
+    while v >= 0x80 {
+        out.push(v as u8 | 0x80);
+        v >>= 7;
+    }
+    out.push(v as u8);
+}
+
+// zigzag first so small negative numbers stay short too
+fn put_var_i32(out: &mut Vec<u8>, v: i32) {
+    put_var_u32(out, ((v << 1) ^ (v >> 31)) as u32);
+}
+
+// small cursor over a received packet
+struct Reader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(buf: &'a [u8]) -> Self {
+        Self { buf, pos: 0 }
+    }
+
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], String> {
+        let mut bytes = [0u8; N];
+        bytes.copy_from_slice(self.bytes(N)?);
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.take::<1>()?[0])
+    }
+
+    fn carve(&mut self) -> Result<Carve, String> {
+        let [count, min, max] = self.take()?;
+        Ok(Carve { count, min, max })
+    }
+
+    fn u16(&mut self) -> Result<u16, String> {
+        Ok(u16::from_le_bytes(self.take()?))
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        Ok(u32::from_le_bytes(self.take()?))
+    }
+
+    fn u64(&mut self) -> Result<u64, String> {
+        Ok(u64::from_le_bytes(self.take()?))
+    }
+
+    fn f32(&mut self) -> Result<f32, String> {
+        Ok(f32::from_le_bytes(self.take()?))
+    }
+
+    // see put_var_u32
+    fn var_u32(&mut self) -> Result<u32, String> {
+        let mut v = 0u32;
+        for shift in (0..35).step_by(7) {
+            let byte = self.u8()?;
+            if shift == 28 && byte > 0x0f {
+                return Err("varint does not fit in 32 bits".to_string());
+            }
+            v |= ((byte & 0x7f) as u32) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(v);
+            }
+        }
+        unreachable!("the fifth byte either ends the varint or is rejected")
+    }
+
+    // see put_var_i32
+    fn var_i32(&mut self) -> Result<i32, String> {
+        let v = self.var_u32()?;
+        Ok((v >> 1) as i32 ^ -((v & 1) as i32))
+    }
+
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let end = self.pos + len;
+        if end > self.buf.len() {
+            return Err(format!(
+                "packet too short: need {end} bytes, got {}",
+                self.buf.len()
+            ));
+        }
+        let bytes = &self.buf[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    // see put_reliable
+    fn reliable(&mut self) -> Result<ReliablePacket, String> {
+        let mut acks = [Ack { seq: 0, bits: 0 }; CHANNELS];
+        for ack in &mut acks {
+            *ack = Ack {
+                seq: self.u16()?,
+                bits: self.u32()?,
+            };
+        }
+        let count = self.u16()? as usize;
+        let mut messages = Vec::with_capacity(count);
+        for _ in 0..count {
+            let id = self.u8()?;
+            let channel = Channel::from_u8(id).ok_or_else(|| format!("unknown channel {id}"))?;
+            let seq = self.u16()?;
+            let len = self.u16()? as usize;
+            messages.push(ReliableMessage {
+                channel,
+                seq,
+                payload: self.bytes(len)?.to_vec(),
+            });
+        }
+        Ok(ReliablePacket { acks, messages })
+    }
+
+    // u16 length then utf8
+    fn string(&mut self) -> Result<String, String> {
+        let len = self.u16()? as usize;
+        let bytes = self.bytes(len)?;
+        String::from_utf8(bytes.to_vec()).map_err(|e| e.to_string())
+    }
+
+    // trailing bytes mean the sender and receiver disagree on the layout
+    fn finish(&self) -> Result<(), String> {
+        if self.pos != self.buf.len() {
+            return Err(format!(
+                "{} unexpected trailing bytes",
+                self.buf.len() - self.pos
+            ));
+        }
+        Ok(())
+    }
+}
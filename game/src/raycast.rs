@@ -0,0 +1,101 @@
+// grid walking shared by the renderer and the weapons: a ray from (x, y) visits every tile
+// it passes through in order (dda). the raycaster stops at the first opaque tile, a bullet
+// at the first solid one, so the stopping rule is up to the caller
+
+use crate::tilemap::TileMap;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct RayStep {
+    pub x: i32,
+    pub y: i32,
+    // 0 when the ray came in through an x side (a wall running north-south), 1 for a y side
+    pub side: u8,
+    // how far along the ray the tile starts, in lengths of the direction vector. with a unit
+    // direction that's plain distance, with the renderer's camera rays it's the
+    // perpendicular distance the walls are sized by
+    pub dist: f32,
+}
+
+// endless, the caller stops at a hit or once the tile is off the map
+pub struct GridRay {
+    map_x: i32,
+    map_y: i32,
+    step_x: i32,
+    step_y: i32,
+    side_dist_x: f32,
+    side_dist_y: f32,
+    delta_dist_x: f32,
+    delta_dist_y: f32,
+}
+
+impl GridRay {
+    pub fn new(x: f32, y: f32, dir_x: f32, dir_y: f32) -> Self {
+        let map_x = x.floor() as i32;
+        let map_y = y.floor() as i32;
+
+        let delta_dist_x = if dir_x == 0.0 {
+            f32::INFINITY
+        } else {
+            (1.0 / dir_x).abs()
+        };
+        let delta_dist_y = if dir_y == 0.0 {
+            f32::INFINITY
+        } else {
+            (1.0 / dir_y).abs()
+        };
+
+        let (step_x, side_dist_x) = if dir_x < 0.0 {
+            (-1, (x - map_x as f32) * delta_dist_x)
+        } else {
+            (1, ((map_x as f32 + 1.0) - x) * delta_dist_x)
+        };
+        let (step_y, side_dist_y) = if dir_y < 0.0 {
+            (-1, (y - map_y as f32) * delta_dist_y)
+        } else {
+            (1, ((map_y as f32 + 1.0) - y) * delta_dist_y)
+        };
+
+        Self {
+            map_x,
+            map_y,
+            step_x,
+            step_y,
+            side_dist_x,
+            side_dist_y,
+            delta_dist_x,
+            delta_dist_y,
+        }
+    }
+}
+
+impl Iterator for GridRay {
+    type Item = RayStep;
+
+    fn next(&mut self) -> Option<RayStep> {
+        let (side, dist) = if self.side_dist_x < self.side_dist_y {
+            self.map_x += self.step_x;
+            self.side_dist_x += self.delta_dist_x;
+            (0, self.side_dist_x - self.delta_dist_x)
+        } else {
+            self.map_y += self.step_y;
+            self.side_dist_y += self.delta_dist_y;
+            (1, self.side_dist_y - self.delta_dist_y)
+        };
+        Some(RayStep {
+            x: self.map_x,
+            y: self.map_y,
+            side,
+            dist,
+        })
+    }
+}
+
+// distance along a unit direction to the first solid tile (what stops players and bullets),
+// None if the ray leaves the map first or gets further than max_dist
+pub fn solid_distance(map: &TileMap, x: f32, y: f32, angle: f32, max_dist: f32) -> Option<f32> {
+    GridRay::new(x, y, angle.cos(), angle.sin())
+        .take_while(|s| s.dist <= max_dist)
+        .map_while(|s| map.at(s.x, s.y).map(|t| (s.dist, t)))
+        .find(|(_, t)| t.is_solid())
+        .map(|(d, _)| d)
+}
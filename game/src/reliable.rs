@@ -0,0 +1,220 @@
+// reliable ordered delivery on top of the unreliable packets, for the things that must not
+// get lost (kills, chat, joins and leaves, map changes). snapshots and inputs don't go through
+// here, a newer one replaces a lost one anyway.
+//
+// every message gets the next sequence number of its channel and is resent every RESEND_TIME
+// until the other side acks it. an ack is the newest sequence received with nothing missing
+// before it plus a bitfield of the ones after it that came in early. the receiver hands out
+// messages in order, holding back early ones until the gap is filled. channels are
+// independent, a lost kill doesn't hold up the chat.
+// no sockets in here: update gives back the packet to send, receive takes the ones that came in
+
+use crate::protocol::{MAX_PACKET, RELIABLE_HEADER, RELIABLE_MESSAGE_HEADER};
+use std::collections::{HashMap, VecDeque};
+
+// how long an unacked message waits before it goes out again, three ticks
+pub const RESEND_TIME: f32 = 0.1;
+// messages in flight per channel. nothing further ahead of the oldest unacked is sent and
+// the receiver drops anything further ahead than this, so sequences can wrap. one bit each
+// in Ack::bits so everything that came in early gets acked
+pub const WINDOW: u16 = u32::BITS as u16;
+// bytes of messages per packet, headers included, the rest goes with the next update
+const BUDGET: usize = MAX_PACKET - RELIABLE_HEADER;
+// the biggest payload that fits in a packet on its own, send refuses anything bigger
+pub const MAX_MESSAGE: usize = BUDGET - RELIABLE_MESSAGE_HEADER;
+pub const CHANNELS: usize = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Channel {
+    // kills, joins and leaves, map changes
+    Game,
+    Chat,
+}
+
+impl Channel {
+    pub const ALL: [Channel; CHANNELS] = [Channel::Game, Channel::Chat];
+
+    pub fn to_u8(self) -> u8 {
+        self as u8
+    }
+
+    pub fn from_u8(id: u8) -> Option<Self> {
+        Self::ALL.get(id as usize).copied()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ack {
+    // everything up to and including this one came in
+    pub seq: u16,
+    // bit i: seq + 1 + i came in too, ahead of a gap
+    pub bits: u32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReliableMessage {
+    pub channel: Channel,
+    pub seq: u16,
+    // an encoded ClientMessage or ServerMessage
+    pub payload: Vec<u8>,
+}
+
+// what goes over the wire, see ClientMessage::Reliable and ServerMessage::Reliable
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReliablePacket {
+    // one for each channel, in Channel::ALL order
+    pub acks: [Ack; CHANNELS],
+    pub messages: Vec<ReliableMessage>,
+}
+
+#[derive(Debug)]
+struct Outgoing {
+    seq: u16,
+    payload: Vec<u8>,
+    // goes out when this reaches 0, new messages start there
+    resend_in: f32,
+}
+
+#[derive(Debug, Default)]
+struct Sender {
+    next: u16,
+    // unacked, oldest first
+    queue: VecDeque<Outgoing>,
+}
+
+#[derive(Debug, Default)]
+struct Receiver {
+    // the sequence we are waiting for
+    next: u16,
+    // came in ahead of a gap
+    early: HashMap<u16, Vec<u8>>,
+}
+
+// one end of a connection, the server keeps one per client
+#[derive(Debug, Default)]
+pub struct Reliable {
+    senders: [Sender; CHANNELS],
+    receivers: [Receiver; CHANNELS],
+    // a message came in since our last packet, the other side is waiting for the ack
+    ack_due: bool,
+    // in order per channel, waiting for poll
+    delivered: VecDeque<(Channel, Vec<u8>)>,
+}
+
+impl Reliable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // goes out with the next update and then again until it is acked. a payload bigger than
+    // MAX_MESSAGE could never go out at all
+    pub fn send(&mut self, channel: Channel, payload: Vec<u8>) -> Result<(), String> {
+        if payload.len() > MAX_MESSAGE {
+            return Err(format!(
+                "{} byte message, at most {MAX_MESSAGE} fit in a packet",
+                payload.len()
+            ));
+        }
+        let sender = &mut self.senders[channel as usize];
+        sender.queue.push_back(Outgoing {
+            seq: sender.next,
+            payload,
+            resend_in: 0.0,
+        });
+        sender.next = sender.next.wrapping_add(1);
+        Ok(())
+    }
+
+    // once a tick: new messages, the ones due for a resend and our acks. None when there is
+    // nothing to say, an ack alone is only sent when something came in
+    pub fn update(&mut self, dt: f32) -> Option<ReliablePacket> {
+        let mut messages = Vec::new();
+        let mut size = 0;
+        for (sender, channel) in self.senders.iter_mut().zip(Channel::ALL) {
+            for out in sender.queue.iter_mut().take(WINDOW as usize) {
+                out.resend_in -= dt;
+                let cost = RELIABLE_MESSAGE_HEADER + out.payload.len();
+                if out.resend_in > 0.0 || size + cost > BUDGET {
+                    continue;
+                }
+                size += cost;
+                out.resend_in = RESEND_TIME;
+                messages.push(ReliableMessage {
+                    channel,
+                    seq: out.seq,
+                    payload: out.payload.clone(),
+                });
+            }
+        }
+        if messages.is_empty() && !self.ack_due {
+            return None;
+        }
+        self.ack_due = false;
+        Some(ReliablePacket {
+            acks: self.acks(),
+            messages,
+        })
+    }
+
+    // a packet from the other side, in any order and as often as the network likes
+    pub fn receive(&mut self, packet: &ReliablePacket) {
+        for (sender, ack) in self.senders.iter_mut().zip(packet.acks) {
+            // only the first WINDOW have ever gone out. with a long backlog behind them the
+            // sequences further back can look like old ones after the wrap
+            let mut position = 0;
+            sender.queue.retain(|out| {
+                position += 1;
+                position > WINDOW as usize || !acked(out.seq, ack)
+            });
+        }
+        for msg in &packet.messages {
+            self.ack_due = true;
+            let channel = msg.channel;
+            let receiver = &mut self.receivers[channel as usize];
+            let ahead = msg.seq.wrapping_sub(receiver.next);
+            // a repeat of one already handed out, or garbage from far ahead
+            if ahead >= WINDOW {
+                continue;
+            }
+            receiver.early.insert(msg.seq, msg.payload.clone());
+            while let Some(payload) = receiver.early.remove(&receiver.next) {
+                self.delivered.push_back((channel, payload));
+                receiver.next = receiver.next.wrapping_add(1);
+            }
+        }
+    }
+
+    // the next message that came in, in the order it was sent on its channel
+    pub fn poll(&mut self) -> Option<(Channel, Vec<u8>)> {
+        self.delivered.pop_front()
+    }
+
+    // messages sent but not acked yet, on all channels
+    pub fn in_flight(&self) -> usize {
+        self.senders.iter().map(|s| s.queue.len()).sum()
+    }
+
+    // the same for one channel
+    pub fn in_flight_on(&self, channel: Channel) -> usize {
+        self.senders[channel as usize].queue.len()
+    }
+
+    fn acks(&self) -> [Ack; CHANNELS] {
+        self.receivers.each_ref().map(|r| {
+            let seq = r.next.wrapping_sub(1);
+            let bits = (0..WINDOW)
+                .filter(|&i| r.early.contains_key(&seq.wrapping_add(1 + i)))
+                .fold(0, |bits, i| bits | 1 << i);
+            Ack { seq, bits }
+        })
+    }
+}
+
+// at or before ack.seq (with the wrap), or one of the early ones after it
+fn acked(seq: u16, ack: Ack) -> bool {
+    if ack.seq.wrapping_sub(seq) < u16::MAX / 2 {
+        return true;
+    }
+    let after = seq.wrapping_sub(ack.seq).wrapping_sub(1);
+    after < WINDOW && ack.bits & (1 << after) != 0
+}
@@ -1,3 +1,16 @@
+// the raycaster draws into a plain framebuffer (one u32 per pixel, 0xRRGGBBAA like
+// textures.rs) so it runs and can be tested without a window. the client uploads the
+// finished frame to one streaming texture with present_frame
+
+use crate::health::{MAX_HEALTH, SPAWN_ARMOR};
+use crate::map::draw_minimap_from_grid;
+use crate::raycast::GridRay;
+use crate::sprites::{Animation, PLAYER_SPRITE, Sprite, rotation};
+use crate::textures::{Texture, TextureSet, texture_x};
+use crate::tilemap::{Tile, TileMap};
+use crate::weapons::ProjectileState;
+use std::f32::consts::{FRAC_PI_3, TAU};
+
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub struct Player {
     pub x: f32,
@@ -6,13 +19,141 @@ pub struct Player {
     pub fov: f32,
     pub move_speed: f32,
     pub rot_speed: f32,
+    // 0 is dead, see health.rs
+    pub health: u8,
+    // takes part of every hit while it lasts
+    pub armor: u8,
+}
+
+impl Player {
+    // just spawned at (x, y), full health and spawn armor
+    pub fn at(x: f32, y: f32, angle: f32) -> Self {
+        Self {
+            x,
+            y,
+            angle,
+            fov: FRAC_PI_3,
+            move_speed: 3.0,
+            rot_speed: 2.5,
+            health: MAX_HEALTH,
+            armor: SPAWN_ARMOR,
+        }
+    }
 }
-#[derive(Debug, Clone, Copy)]
+
+// everyone but us, as the client knows them from the server's snapshots
+#[derive(Debug, PartialEq, Clone, Default)]
 pub struct OtherPlayer {
+    // server id, stays the same as long as they are connected
+    pub id: u32,
+    pub name: String,
+    pub team: Team,
     pub x: f32,
     pub y: f32,
+    // where they look, picks the sprite rotation
+    pub angle: f32,
+    // 0..=100
+    pub health: u8,
+    pub alive: bool,
+    pub animation: Animation,
+    // seconds since the animation started
+    pub anim_time: f32,
 }
 
+impl OtherPlayer {
+    // nameless, healthy and standing still, for the render tool and tests
+    pub fn at(x: f32, y: f32) -> Self {
+        Self {
+            x,
+            y,
+            health: 100,
+            alive: true,
+            ..Self::default()
+        }
+    }
+
+    pub fn sprite(&self) -> Sprite {
+        let animation = if self.alive {
+            self.animation
+        } else {
+            Animation::Dead
+        };
+        Sprite {
+            x: self.x,
+            y: self.y,
+            angle: self.angle,
+            sheet: PLAYER_SPRITE,
+            frame: animation.frame(self.anim_time),
+        }
+    }
+}
+
+// Team::None is everyone for themselves
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum Team {
+    #[default]
+    None,
+    Red,
+    Blue,
+}
+
+impl Team {
+    // minimap marker and health bar
+    pub fn color(self) -> (u8, u8, u8) {
+        match self {
+            Team::None => (0, 200, 0),
+            Team::Red => (220, 60, 60),
+            Team::Blue => (70, 130, 255),
+        }
+    }
+
+    pub fn to_u8(self) -> u8 {
+        match self {
+            Team::None => 0,
+            Team::Red => 1,
+            Team::Blue => 2,
+        }
+    }
+
+    pub fn from_u8(v: u8) -> Self {
+        match v {
+            1 => Team::Red,
+            2 => Team::Blue,
+            _ => Team::None,
+        }
+    }
+}
+
+// everything in the level that moves, besides us. drawn as sprites and minimap markers
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub struct Actors<'a> {
+    pub others: &'a [OtherPlayer],
+    pub projectiles: &'a [ProjectileState],
+}
+
+impl<'a> Actors<'a> {
+    // just players, nothing in flight
+    pub fn players(others: &'a [OtherPlayer]) -> Self {
+        Self {
+            others,
+            projectiles: &[],
+        }
+    }
+}
+
+// where the client writes a player's name over their sprite, it has the font.
+// x is the middle of the text, y its bottom, in framebuffer pixels
+#[derive(Debug, PartialEq, Clone)]
+pub struct NameTag {
+    pub x: i32,
+    pub y: i32,
+    pub name: String,
+    pub team: Team,
+}
+
+// names and health bars only show this close, in tiles
+pub const NAME_TAG_RANGE: f32 = 8.0;
+
 // the serrver will send the Vec of players struct to the client with their positions and angles
 
 // random players for testing and
@@ -33,61 +174,48 @@ fn lerp_rgb(n: (u8, u8, u8), f: (u8, u8, u8), t: f32) -> (u8, u8, u8) {
     )
 }
 
-/// Nonlinear remap so the gradient stays darker near you and brightens smoothly.
-/// k ~ 1.5..3.0 looks nice. Adjust to taste.
+// packs a color the way the framebuffer and the textures store it
+#[inline]
+pub fn rgb((r, g, b): (u8, u8, u8)) -> u32 {
+    u32::from_be_bytes([r, g, b, 255])
+}
+
+#[inline]
+fn unpack(px: u32) -> (u8, u8, u8) {
+    let [r, g, b, _] = px.to_be_bytes();
+    (r, g, b)
+}
+
+// a over b, alpha 0..=255
 #[inline]
-fn depth_curve(t: f32, k: f32) -> f32 {
-    // Option A: simple power curve
-    t.clamp(0.0, 1.0).powf(k)
-    // Option B: 1/(1 + a/t) type can also be used; stick to one curve for consistency.
+fn blend(under: u32, (r, g, b): (u8, u8, u8), alpha: u8) -> u32 {
+    let t = alpha as f32 / 255.0;
+    rgb(lerp_rgb(unpack(under), (r, g, b), t))
 }
 
+// fb is screen_w * view_h pixels, row major. only the 3d view goes in here,
+// the hud and the minimap are drawn on top by the caller. gives back the name tags
+// of the players in sight for the caller to write. depth is scratch for draw_sprites, kept
+// by the caller like fb so it isn't allocated every frame
+#[allow(clippy::too_many_arguments)]
 pub fn cast_and_draw_columns(
-    canvas: &mut sdl2::render::Canvas<sdl2::video::Window>,
-    grid: &Vec<Vec<u8>>,    // 0 = empty, 1 = wall
-    p: &Player,             // needs x, y, angle, fov
-    others: &[OtherPlayer], // NEW: slice of other players with (x, y)
-    screen_w: i32,
-    screen_h: i32, // full window height
-    reserved: i32, // reserved pixels at the bottom (e.g. for minimap)
-) -> Result<(), String> {
-    use sdl2::pixels::Color;
+    fb: &mut [u32],
+    depth: &mut Vec<f32>,
+    screen_w: usize,
+    view_h: usize,
+    map: &TileMap,
+    textures: &TextureSet,
+    p: &Player, // needs x, y, angle, fov
+    actors: Actors,
+) -> Vec<NameTag> {
+    assert!(fb.len() >= screen_w * view_h, "framebuffer too small");
+    let fb = &mut fb[..screen_w * view_h];
+    let view_h_i = view_h as i32;
 
     // z-buffer for sprite rendering: distance to wall for each vertical stripe
-    let mut zbuffer = vec![f32::INFINITY; screen_w as usize];
-
-    // visible area = total height minus reserved area
-    let view_h = screen_h - reserved;
-    let half = view_h / 2;
-
-    // --- draw ceiling & floor gradients (unchanged) ---
-    let (ceil_near, ceil_far, floor_near, floor_far) = (
-        (18, 18, 20), // ceiling close
-        (8, 9, 12),   // ceiling horizon
-        (50, 50, 54), // floor close
-        (20, 20, 24), // floor far
-    );
+    let mut zbuffer = vec![f32::INFINITY; screen_w];
 
-    for y in 0..half {
-        let t = 1.0 - (y as f32 / half as f32);
-        let t = depth_curve(t, 1.6);
-        let (r, g, b) = lerp_rgb(ceil_far, ceil_near, t);
-        canvas.set_draw_color(Color::RGB(r, g, b));
-        canvas.draw_line((0, y), (screen_w - 1, y))?;
-    }
-
-    for y in half..view_h {
-        let raw = (y - half) as f32 / half as f32;
-        let t = depth_curve(raw, 2.4);
-        let (r0, g0, b0) = lerp_rgb(floor_far, floor_near, t);
-        let dark_mul = 0.85;
-        let r = (r0 as f32 * dark_mul) as u8;
-        let g = (g0 as f32 * dark_mul) as u8;
-        let b = (b0 as f32 * dark_mul) as u8;
-
-        canvas.set_draw_color(Color::RGB(r, g, b));
-        canvas.draw_line((0, y), (screen_w - 1, y))?;
-    }
+    let half = view_h_i / 2;
 
     // --- camera setup ---
     let dir_x = p.angle.cos();
@@ -96,156 +224,382 @@ pub fn cast_and_draw_columns(
     let plane_x = -dir_y * plane_scale;
     let plane_y = dir_x * plane_scale;
 
+    let fog_k = 0.50_f32;
+    let min_brightness = 0.30_f32;
+
+    // --- floor & ceiling, a row at a time, the walls get drawn over them ---
+    // every row is a straight line across the floor between where the leftmost and the
+    // rightmost ray touch it, so the texture coords just step along it
+    let (ray0_x, ray0_y) = (dir_x - plane_x, dir_y - plane_y);
+    let (ray1_x, ray1_y) = (dir_x + plane_x, dir_y + plane_y);
+    // open sky: the panorama is one full turn wide and turns with the view,
+    // it's infinitely far away so walking doesn't move it
+    let sky = map.open_sky().then(|| {
+        let sky = textures.sky();
+        let cols: Vec<usize> = (0..screen_w)
+            .map(|x| {
+                let camera_x = 2.0 * (x as f32) / (screen_w as f32) - 1.0;
+                let a = (p.angle + (camera_x * plane_scale).atan()).rem_euclid(TAU);
+                (a / TAU * sky.width as f32) as usize
+            })
+            .collect();
+        (sky, cols)
+    });
+    // neighbouring pixels are nearly always on the same tile, skip the lookups then
+    let flats = |t: Tile| {
+        (
+            t,
+            textures.flat(t.floor_texture()),
+            textures.flat(t.ceiling_texture()),
+        )
+    };
+    let mut cached = flats(Tile::Empty);
+
+    for (y, row) in fb.chunks_mut(screen_w).enumerate() {
+        let y = y as i32;
+        let floor = y >= half;
+        if !floor && let Some((sky, cols)) = &sky {
+            let ty = y as usize * sky.height / half as usize;
+            for (px, &tx) in row.iter_mut().zip(cols) {
+                *px = sky.texel(tx, ty);
+            }
+            continue;
+        }
+
+        // same math as the wall height: a wall d away ends view_h / (2 d) rows below the
+        // horizon, so that row shows the floor d away. ceiling rows mirror the floor ones
+        let row_dist = 0.5 * view_h as f32 / ((y - half) as f32 + 0.5).abs();
+        let step_x = row_dist * (ray1_x - ray0_x) / screen_w as f32;
+        let step_y = row_dist * (ray1_y - ray0_y) / screen_w as f32;
+        let mut fx = p.x + row_dist * ray0_x;
+        let mut fy = p.y + row_dist * ray0_y;
+        // a bit darker than the walls so the room keeps its shape
+        let mul = (1.0 / (1.0 + fog_k * row_dist)).clamp(min_brightness, 1.0)
+            * if floor { 0.85 } else { 0.7 };
+
+        for px in row.iter_mut() {
+            let (cx, cy) = (fx.floor(), fy.floor());
+            let tile = map.at(cx as i32, cy as i32).unwrap_or_default();
+            if tile != cached.0 {
+                cached = flats(tile);
+            }
+            let tex = if floor { cached.1 } else { cached.2 };
+            let tx = ((fx - cx) * tex.width as f32) as usize;
+            let ty = ((fy - cy) * tex.height as f32) as usize;
+            *px = rgb(shade_rgb(unpack(tex.texel(tx, ty)), mul));
+            fx += step_x;
+            fy += step_y;
+        }
+    }
+
     // --- WALLS + fill zbuffer ---
     for x in 0..screen_w {
         let camera_x = 2.0 * (x as f32) / (screen_w as f32) - 1.0;
         let ray_dir_x = dir_x + plane_x * camera_x;
         let ray_dir_y = dir_y + plane_y * camera_x;
 
-        let mut map_x = p.x.floor() as i32;
-        let mut map_y = p.y.floor() as i32;
-
-        let delta_dist_x = if ray_dir_x == 0.0 {
-            f32::INFINITY
-        } else {
-            (1.0 / ray_dir_x).abs()
-        };
-        let delta_dist_y = if ray_dir_y == 0.0 {
-            f32::INFINITY
-        } else {
-            (1.0 / ray_dir_y).abs()
-        };
-
-        let (step_x, mut side_dist_x) = if ray_dir_x < 0.0 {
-            (-1, (p.x - map_x as f32) * delta_dist_x)
-        } else {
-            (1, ((map_x as f32 + 1.0) - p.x) * delta_dist_x)
-        };
-        let (step_y, mut side_dist_y) = if ray_dir_y < 0.0 {
-            (-1, (p.y - map_y as f32) * delta_dist_y)
-        } else {
-            (1, ((map_y as f32 + 1.0) - p.y) * delta_dist_y)
-        };
-
-        let mut hit = false;
-        let mut side = 0;
-        while !hit {
-            if side_dist_x < side_dist_y {
-                side_dist_x += delta_dist_x;
-                map_x += step_x;
-                side = 0;
-            } else {
-                side_dist_y += delta_dist_y;
-                map_y += step_y;
-                side = 1;
-            }
-            if map_y < 0
-                || map_y as usize >= grid.len()
-                || map_x < 0
-                || map_x as usize >= grid[0].len()
-            {
+        let mut hit = None;
+        let (mut side, mut perp_dist) = (0, f32::INFINITY);
+        // distance to the first window the ray went through, drawn over the wall behind it
+        let mut glass = None;
+        for step in GridRay::new(p.x, p.y, ray_dir_x, ray_dir_y) {
+            (side, perp_dist) = (step.side, step.dist);
+            let Some(tile) = map.at(step.x, step.y) else {
                 break;
+            };
+            if tile.is_opaque() {
+                hit = Some(tile);
+                break;
+            } else if tile == Tile::Window && glass.is_none() {
+                glass = Some(step.dist);
             }
-            if grid[map_y as usize][map_x as usize] != 0 {
-                hit = true;
-            }
-        }
-
-        let perp_dist = if side == 0 {
-            (map_x as f32 - p.x + (1 - step_x) as f32 * 0.5) / ray_dir_x
-        } else {
-            (map_y as f32 - p.y + (1 - step_y) as f32 * 0.5) / ray_dir_y
         }
-        .abs();
 
-        // FILL zbuffer for this column (NEW: 1 line)
-        zbuffer[x as usize] = perp_dist;
+        zbuffer[x] = perp_dist;
 
-        let base = if side == 1 {
-            (68, 68, 68)
-        } else {
-            (43, 43, 43)
-        };
-        let fog_k = 0.50_f32;
-        let min_brightness = 0.30_f32;
+        // x sides stay darker than y sides so corners still read with textures on
+        let side_mul = if side == 1 { 1.0 } else { 0.67 };
         let dist_mul = (1.0 / (1.0 + fog_k * perp_dist)).clamp(min_brightness, 1.0);
-        let (r, g, b) = shade_rgb(base, dist_mul);
 
-        if perp_dist.is_finite() && perp_dist > 0.0001 {
+        if let Some(tile) = hit
+            && perp_dist.is_finite()
+            && perp_dist > 0.0001
+        {
             let line_h = (view_h as f32 / perp_dist) as i32;
-            let draw_start = (view_h - line_h) / 2;
-            let draw_end = (view_h + line_h) / 2;
-
-            canvas.set_draw_color(Color::RGB(r, g, b));
+            let draw_start = (view_h_i - line_h) / 2;
 
+            // where along the wall face the ray landed
+            let wall_x = if side == 0 {
+                p.y + perp_dist * ray_dir_y
+            } else {
+                p.x + perp_dist * ray_dir_x
+            };
+            let flip = (side == 0 && ray_dir_x > 0.0) || (side == 1 && ray_dir_y < 0.0);
+
+            let tex = textures.wall(tile.wall_texture().unwrap_or(1));
+            let tx = texture_x(wall_x, tex.width, flip);
+            // texture rows per screen row, the column can be taller than the view up close
+            let step = tex.height as f32 / line_h.max(1) as f32;
             let ys = draw_start.max(0);
-            let ye = draw_end.min(view_h - 1);
-            if ys <= ye {
-                canvas.draw_line((x, ys), (x, ye))?;
+            let ye = (draw_start + line_h).min(view_h_i);
+            for y in ys..ye {
+                let ty = ((y - draw_start) as f32 * step) as usize;
+                let texel = unpack(tex.texel(tx, ty.min(tex.height - 1)));
+                fb[y as usize * screen_w + x] = rgb(shade_rgb(texel, dist_mul * side_mul));
+            }
+        }
+
+        // see-through pane in front of the wall, same height math as the wall
+        if let Some(d) = glass
+            && d > 0.0001
+        {
+            let line_h = (view_h as f32 / d) as i32;
+            let ys = ((view_h_i - line_h) / 2).max(0);
+            let ye = ((view_h_i + line_h) / 2).min(view_h_i);
+            for y in ys..ye {
+                let px = &mut fb[y as usize * screen_w + x];
+                *px = blend(*px, (150, 200, 230), 70);
             }
         }
     }
 
-    // --- OTHER PLAYERS (render only when visible) ---
-    // Camera-space transform constants
+    // --- SPRITES, other players and projectiles ---
+    let sprites: Vec<Sprite> = (actors.others.iter().map(OtherPlayer::sprite))
+        .chain(actors.projectiles.iter().map(ProjectileState::sprite))
+        .collect();
+    draw_sprites(fb, depth, screen_w, view_h, &zbuffer, textures, p, &sprites);
+
+    draw_health_bars(fb, screen_w, view_h, &zbuffer, p, actors.others)
+}
+
+// (sideways, depth) of a world position as the camera sees it, depth is along the view
+// direction like the wall distances in the zbuffer
+fn to_camera(p: &Player, x: f32, y: f32) -> (f32, f32) {
+    let dir_x = p.angle.cos();
+    let dir_y = p.angle.sin();
+    let plane_scale = (p.fov * 0.5).tan();
+    let plane_x = -dir_y * plane_scale;
+    let plane_y = dir_x * plane_scale;
     let inv_det = {
         let det = plane_x * dir_y - dir_x * plane_y;
         if det.abs() < 1e-6 { 1e6 } else { 1.0 / det }
     };
+    let (dx, dy) = (x - p.x, y - p.y);
+    (
+        inv_det * (dir_y * dx - dir_x * dy),
+        inv_det * (-plane_y * dx + plane_x * dy),
+    )
+}
 
-    for other in others {
-        // relative position
-        let dx = other.x - p.x;
-        let dy = other.y - p.y;
+// a health bar in the team color over every living player whose head we can see,
+// and the name tag to go above it
+fn draw_health_bars(
+    fb: &mut [u32],
+    screen_w: usize,
+    view_h: usize,
+    zbuffer: &[f32],
+    p: &Player,
+    others: &[OtherPlayer],
+) -> Vec<NameTag> {
+    let mut tags = Vec::new();
+    for other in others.iter().filter(|o| o.alive) {
+        let (transform_x, transform_y) = to_camera(p, other.x, other.y);
+        if transform_y <= 0.0001 || transform_y > NAME_TAG_RANGE {
+            continue;
+        }
+        let x = ((screen_w as f32 / 2.0) * (1.0 + transform_x / transform_y)) as i32;
+        // hidden behind a wall (or off screen), no tag either
+        if x < 0 || x >= screen_w as i32 || transform_y >= zbuffer[x as usize] {
+            continue;
+        }
+        let sprite_h = (view_h as f32 / transform_y) as i32;
+        let top = (view_h as i32 - sprite_h) / 2;
+        let (bar_w, bar_h) = ((sprite_h / 3).max(4), (sprite_h / 24).max(2));
+        let bar = (x - bar_w / 2, top - bar_h - 1);
+        let filled = bar_w * other.health.min(100) as i32 / 100;
+        fill_rect(fb, screen_w, bar, (bar_w, bar_h), rgb((40, 40, 40)));
+        fill_rect(fb, screen_w, bar, (filled, bar_h), rgb(other.team.color()));
+        tags.push(NameTag {
+            x,
+            y: bar.1 - 1,
+            name: other.name.clone(),
+            team: other.team,
+        });
+    }
+    tags
+}
 
-        // camera space
-        let transform_x = inv_det * (dir_y * dx - dir_x * dy);
-        let transform_y = inv_det * (-plane_y * dx + plane_x * dy);
+// billboards over a finished 3d view. zbuffer is the wall distance of every column, a sprite
+// column only shows where it's nearer than the wall. sprites are drawn back to front and
+// every sprite pixel keeps its depth, so a nearer sprite always wins where two overlap
+// whatever order they come in. depth is scratch space, grown to screen_w * view_h when
+// there is something to draw
+#[allow(clippy::too_many_arguments)]
+pub fn draw_sprites(
+    fb: &mut [u32],
+    depth: &mut Vec<f32>,
+    screen_w: usize,
+    view_h: usize,
+    zbuffer: &[f32],
+    textures: &TextureSet,
+    p: &Player,
+    sprites: &[Sprite],
+) {
+    assert!(fb.len() >= screen_w * view_h, "framebuffer too small");
+    let (screen_w_i, view_h_i) = (screen_w as i32, view_h as i32);
+
+    // camera space (transform_x, transform_y, sprite) of everything in front of the camera
+    let mut visible: Vec<(f32, f32, &Sprite)> = sprites
+        .iter()
+        .filter_map(|s| {
+            let (transform_x, transform_y) = to_camera(p, s.x, s.y);
+            (transform_y > 0.0001).then_some((transform_x, transform_y, s))
+        })
+        .collect();
+    // far to near by depth along the view, not straight line distance: a sprite off to the
+    // side can be further away and still be in front of one straight ahead.
+    // ties go by position so the picture doesn't depend on the slice order
+    visible.sort_by(|a, b| {
+        (b.1.total_cmp(&a.1))
+            .then(a.2.x.total_cmp(&b.2.x))
+            .then(a.2.y.total_cmp(&b.2.y))
+    });
+    // depth of what each pixel shows, starts out as the wall depth of its column
+    if !visible.is_empty() {
+        depth.clear();
+        for _ in 0..view_h {
+            depth.extend_from_slice(&zbuffer[..screen_w]);
+        }
+    }
 
-        // behind camera? skip
-        if transform_y <= 0.0 {
+    for (transform_x, transform_y, sprite) in visible {
+        let Some(sheet) = textures.sprite(sprite.sheet) else {
             continue;
-        }
+        };
 
         // screen x
         let sprite_screen_x = ((screen_w as f32 / 2.0) * (1.0 + transform_x / transform_y)) as i32;
 
-        // size by distance
+        // size by distance, as tall as a wall and standing on the floor
         let sprite_h = (view_h as f32 / transform_y) as i32;
         let sprite_w = sprite_h;
-
-        // vertical span (clamped to view)
-        let mut draw_start_y = (view_h / 2) - (sprite_h / 2);
-        let mut draw_end_y = draw_start_y + sprite_h;
-        if draw_start_y < 0 {
-            draw_start_y = 0;
-        }
-        if draw_end_y > view_h {
-            draw_end_y = view_h;
+        if sprite_h <= 0 {
+            continue;
         }
+        let top = (view_h_i - sprite_h) / 2;
+        let left = sprite_screen_x - sprite_w / 2;
 
-        // horizontal span (clamped to screen)
-        let mut draw_start_x = sprite_screen_x - (sprite_w / 2);
-        let mut draw_end_x = draw_start_x + sprite_w;
-        if draw_start_x < 0 {
-            draw_start_x = 0;
-        }
-        if draw_end_x > screen_w {
-            draw_end_x = screen_w;
-        }
+        let (draw_start_y, draw_end_y) = (top.max(0), (top + sprite_h).min(view_h_i));
+        let (draw_start_x, draw_end_x) = (left.max(0), (left + sprite_w).min(screen_w_i));
+
+        let rot = rotation(sprite, p.x, p.y, sheet.rotations);
+        let size = sheet.frame_size;
+        // same fog as the walls
+        let mul = (1.0 / (1.0 + 0.5 * transform_y)).clamp(0.3, 1.0);
 
         // column-by-column with depth test against walls
-        canvas.set_draw_color(Color::RGB(0, 200, 0)); // simple green box/player
         for stripe in draw_start_x..draw_end_x {
-            // sprite depth (transform_y) must be < wall depth at this column
-            if transform_y < zbuffer[stripe as usize] {
-                let h = (draw_end_y - draw_start_y).max(1) as u32;
-                let _ = canvas.fill_rect(sdl2::rect::Rect::new(stripe, draw_start_y, 1, h));
+            if transform_y >= zbuffer[stripe as usize] {
+                continue;
+            }
+            let u = ((stripe - left) as usize * size / sprite_w as usize).min(size - 1);
+            for y in draw_start_y..draw_end_y {
+                let v = ((y - top) as usize * size / sprite_h as usize).min(size - 1);
+                let texel = sheet.texel(sprite.frame, rot, u, v);
+                let i = y as usize * screen_w + stripe as usize;
+                // alpha under half is a hole, and an equally near sprite keeps its pixel
+                if texel & 0xff < 0x80 || transform_y >= depth[i] {
+                    continue;
+                }
+                fb[i] = rgb(shade_rgb(unpack(texel), mul));
+                depth[i] = transform_y;
             }
         }
     }
+}
+
+// the whole client screen: the 3d view on top and a black band with the minimap below,
+// the same picture the window shows minus the text (the name tags it gives back included)
+#[allow(clippy::too_many_arguments)]
+pub fn render_frame(
+    fb: &mut [u32],
+    depth: &mut Vec<f32>,
+    width: usize,
+    height: usize,
+    map: &TileMap,
+    textures: &TextureSet,
+    p: &Player,
+    actors: Actors,
+) -> Vec<NameTag> {
+    let view_h = height * 4 / 5;
+    let tags = cast_and_draw_columns(fb, depth, width, view_h, map, textures, p, actors);
+    fb[width * view_h..width * height].fill(rgb((0, 0, 0)));
+    // tiles are a bit wider than tall, like the old 8x6 minimap on the 1200x1000 window
+    let sy = ((height - view_h) / map.height().max(1)).max(1) as i32;
+    let sx = sy * 4 / 3;
+    let ox = (width as i32 - map.width() as i32 * sx) / 2;
+    draw_minimap_from_grid(fb, width, map, p, actors, (sx, sy), (ox, view_h as i32));
+    tags
+}
+
+// render_frame into a fresh image, for screenshots and the golden image tests
+pub fn render_image(
+    width: usize,
+    height: usize,
+    map: &TileMap,
+    textures: &TextureSet,
+    p: &Player,
+    actors: Actors,
+) -> Texture {
+    let mut pixels = vec![0; width * height];
+    render_frame(
+        &mut pixels,
+        &mut Vec::new(),
+        width,
+        height,
+        map,
+        textures,
+        p,
+        actors,
+    );
+    Texture {
+        width,
+        height,
+        pixels,
+    }
+}
+
+// framebuffer rect, anything outside fb is skipped
+pub fn fill_rect(fb: &mut [u32], fb_w: usize, (x, y): (i32, i32), (w, h): (i32, i32), color: u32) {
+    let fb_h = (fb.len() / fb_w) as i32;
+    let (x0, x1) = (x.max(0), (x + w).min(fb_w as i32));
+    let (y0, y1) = (y.max(0), (y + h).min(fb_h));
+    if x0 >= x1 {
+        return;
+    }
+    for row in y0..y1 {
+        let start = row as usize * fb_w;
+        fb[start + x0 as usize..start + x1 as usize].fill(color);
+    }
+}
 
-    Ok(())
+// simple dda line, clipped per pixel
+pub fn draw_line(
+    fb: &mut [u32],
+    fb_w: usize,
+    (x0, y0): (i32, i32),
+    (x1, y1): (i32, i32),
+    color: u32,
+) {
+    let fb_h = (fb.len() / fb_w) as i32;
+    let steps = (x1 - x0).abs().max((y1 - y0).abs()).max(1);
+    for i in 0..=steps {
+        let x = x0 + (x1 - x0) * i / steps;
+        let y = y0 + (y1 - y0) * i / steps;
+        if x >= 0 && y >= 0 && x < fb_w as i32 && y < fb_h {
+            fb[y as usize * fb_w + x as usize] = color;
+        }
+    }
 }
 
 // Simple shading function to darken a color by a multiplier (0.0 to 1.0)
@@ -257,3 +611,19 @@ fn shade_rgb((r, g, b): (u8, u8, u8), mul: f32) -> (u8, u8, u8) {
         (b as f32 * m).round().clamp(0.0, 255.0) as u8,
     )
 }
+
+// copies a finished framebuffer into a streaming RGBA8888 texture of the same size
+#[cfg(feature = "client")]
+pub fn present_frame(
+    texture: &mut sdl2::render::Texture,
+    fb: &[u32],
+    width: usize,
+) -> Result<(), String> {
+    texture.with_lock(None, |buf: &mut [u8], pitch: usize| {
+        for (src, dst) in fb.chunks(width).zip(buf.chunks_mut(pitch)) {
+            for (px, out) in src.iter().zip(dst.chunks_exact_mut(4)) {
+                out.copy_from_slice(&px.to_ne_bytes());
+            }
+        }
+    })
+}
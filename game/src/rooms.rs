@@ -0,0 +1,196 @@
+// open spaces carved into the maze_to_grid output so there is room for firefights:
+//   rooms          - empty rectangles
+//   plazas         - bigger rectangles that keep the maze corner posts as pillars for cover
+//   wide corridors - 2 tile wide straight runs
+// carving only turns walls into floor (plaza pillars were walls already), and at the end
+// a flood fill makes sure every open tile can still be reached
+
+use rand::{Rng, RngCore};
+use std::collections::VecDeque;
+
+// count and side length range in grid tiles for one kind of open space
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Carve {
+    pub count: u8,
+    pub min: u8,
+    pub max: u8,
+}
+
+impl Carve {
+    pub const fn none(min: u8, max: u8) -> Self {
+        Self { count: 0, min, max }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct RoomConfig {
+    pub rooms: Carve,
+    pub plazas: Carve,
+    // min / max is the length, the width is always 2
+    pub wide_corridors: Carve,
+}
+
+impl Default for RoomConfig {
+    fn default() -> Self {
+        Self {
+            rooms: Carve::none(3, 5),
+            plazas: Carve::none(7, 9),
+            wide_corridors: Carve::none(6, 12),
+        }
+    }
+}
+
+impl RoomConfig {
+    pub fn is_empty(&self) -> bool {
+        self.rooms.count == 0 && self.plazas.count == 0 && self.wide_corridors.count == 0
+    }
+}
+
+pub fn carve_rooms(grid: &mut [Vec<u8>], cfg: &RoomConfig, rng: &mut dyn RngCore) {
+    let gh = grid.len();
+    let gw = grid.first().map_or(0, |row| row.len());
+    // need at least one tile of inside next to the outer wall
+    if gw < 3 || gh < 3 {
+        return;
+    }
+
+    for _ in 0..cfg.rooms.count {
+        let (w, h) = (pick(rng, cfg.rooms), pick(rng, cfg.rooms));
+        let (x, y, w, h) = place(rng, gw, gh, w, h);
+        open_rect(grid, x, y, w, h, false);
+    }
+    for _ in 0..cfg.plazas.count {
+        let (w, h) = (pick(rng, cfg.plazas), pick(rng, cfg.plazas));
+        let (x, y, w, h) = place(rng, gw, gh, w, h);
+        open_rect(grid, x, y, w, h, true);
+    }
+    for _ in 0..cfg.wide_corridors.count {
+        let len = pick(rng, cfg.wide_corridors);
+        let (w, h) = if rng.random_bool(0.5) {
+            (len, 2)
+        } else {
+            (2, len)
+        };
+        let (x, y, w, h) = place(rng, gw, gh, w, h);
+        open_rect(grid, x, y, w, h, false);
+    }
+
+    connect_regions(grid);
+}
+
+fn pick(rng: &mut dyn RngCore, c: Carve) -> usize {
+    let (lo, hi) = (c.min.min(c.max).max(1), c.max.max(c.min).max(1));
+    rng.random_range(lo..=hi) as usize
+}
+
+// random top-left corner so the rect stays inside the outer wall, shrunk if the map is small
+fn place(
+    rng: &mut dyn RngCore,
+    gw: usize,
+    gh: usize,
+    w: usize,
+    h: usize,
+) -> (usize, usize, usize, usize) {
+    let w = w.min(gw - 2);
+    let h = h.min(gh - 2);
+    let x = rng.random_range(1..=gw - 1 - w);
+    let y = rng.random_range(1..=gh - 1 - h);
+    (x, y, w, h)
+}
+
+fn open_rect(grid: &mut [Vec<u8>], x: usize, y: usize, w: usize, h: usize, pillars: bool) {
+    for row in grid.iter_mut().skip(y).take(h) {
+        row[x..x + w].fill(0);
+    }
+    if pillars {
+        // the maze corner posts sit on even x and even y, they were walls before we carved
+        for gy in (y..y + h).filter(|gy| gy % 2 == 0) {
+            for gx in (x..x + w).filter(|gx| gx % 2 == 0) {
+                grid[gy][gx] = 1;
+            }
+        }
+    }
+}
+
+// breadth first flood fill over open tiles (0), true for every tile reachable from start
+pub fn flood_fill(grid: &[Vec<u8>], start: (usize, usize)) -> Vec<Vec<bool>> {
+    let mut seen = vec![vec![false; grid.first().map_or(0, |r| r.len())]; grid.len()];
+    let (sx, sy) = start;
+    if grid.get(sy).and_then(|row| row.get(sx)) != Some(&0) {
+        return seen;
+    }
+    seen[sy][sx] = true;
+    let mut queue = VecDeque::from([start]);
+    while let Some((x, y)) = queue.pop_front() {
+        for (nx, ny) in neighbors4(grid, x, y) {
+            if grid[ny][nx] == 0 && !seen[ny][nx] {
+                seen[ny][nx] = true;
+                queue.push_back((nx, ny));
+            }
+        }
+    }
+    seen
+}
+
+// true when every open tile can reach every other one
+pub fn is_connected(grid: &[Vec<u8>]) -> bool {
+    let Some(start) = first_open(grid) else {
+        return true;
+    };
+    let seen = flood_fill(grid, start);
+    grid.iter()
+        .zip(&seen)
+        .all(|(row, seen_row)| row.iter().zip(seen_row).all(|(&t, &s)| t != 0 || s))
+}
+
+// digs a straight L shaped tunnel from every unreachable area to the main one
+pub fn connect_regions(grid: &mut [Vec<u8>]) {
+    let Some(start) = first_open(grid) else {
+        return;
+    };
+    loop {
+        let seen = flood_fill(grid, start);
+        let lost = grid.iter().enumerate().find_map(|(y, row)| {
+            row.iter()
+                .enumerate()
+                .find(|&(x, &t)| t == 0 && !seen[y][x])
+                .map(|(x, _)| (x, y))
+        });
+        let Some((lx, ly)) = lost else {
+            return;
+        };
+        // closest reachable tile, manhattan distance is what the tunnel costs
+        let (tx, ty) = seen
+            .iter()
+            .enumerate()
+            .flat_map(|(y, row)| {
+                row.iter()
+                    .enumerate()
+                    .filter(|&(_, &s)| s)
+                    .map(move |(x, _)| (x, y))
+            })
+            .min_by_key(|&(x, y)| x.abs_diff(lx) + y.abs_diff(ly))
+            .unwrap();
+        grid[ly][lx.min(tx)..=lx.max(tx)].fill(0);
+        for row in &mut grid[ly.min(ty)..=ly.max(ty)] {
+            row[tx] = 0;
+        }
+    }
+}
+
+fn first_open(grid: &[Vec<u8>]) -> Option<(usize, usize)> {
+    grid.iter()
+        .enumerate()
+        .find_map(|(y, row)| row.iter().position(|&t| t == 0).map(|x| (x, y)))
+}
+
+fn neighbors4(grid: &[Vec<u8>], x: usize, y: usize) -> impl Iterator<Item = (usize, usize)> {
+    let (h, w) = (grid.len(), grid.first().map_or(0, |r| r.len()));
+    [(0isize, -1isize), (1, 0), (0, 1), (-1, 0)]
+        .into_iter()
+        .filter_map(move |(dx, dy)| {
+            let nx = x.checked_add_signed(dx)?;
+            let ny = y.checked_add_signed(dy)?;
+            (nx < w && ny < h).then_some((nx, ny))
+        })
+}
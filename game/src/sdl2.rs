@@ -1,10 +1,6 @@
-use sdl2::event::Event;
-use sdl2::keyboard::Keycode;
-use sdl2::pixels::Color;
 use sdl2::render::Canvas;
 use sdl2::video::Window;
-use std::time::{Duration, Instant};
-// 
+//
 pub fn sdl2_win(
     title: &str,
     width: u32,
@@ -23,6 +19,6 @@ pub fn sdl2_win(
     let event_pump = sdl_context.event_pump()?;
 
     sdl_context.mouse().set_relative_mouse_mode(true);
-    
+
     Ok((canvas, event_pump))
 }
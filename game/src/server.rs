@@ -0,0 +1,682 @@
+// authoritative game server: owns the map, moves every player with update_player as their
+// inputs come in and broadcasts a snapshot of the world every tick. no sdl2 in here so it runs headless.
+// snapshots are deltas against the newest one each client acked, full until it acks one.
+// kills, chat, joins and leaves and map changes go out on the reliable channels instead
+// all damage is applied here, the dead wait RESPAWN_DELAY and come back at the spawn point
+// furthest from their enemies
+
+use crate::config::MapConfig;
+use crate::health::{Kill, RESPAWN_DELAY, apply_damage, respawn};
+use crate::mapfile::MapFile;
+use crate::mechanics::{PlayerInput, update_player};
+use crate::protocol::{
+    ClientMessage, ErrorCode, MAX_CHAT_LEN, MAX_PACKET, MAX_PLAYER_NAME_LEN, PlayerState,
+    SNAPSHOT_HISTORY, ServerMessage, check_version, quantize_angle, quantize_position,
+};
+use crate::reliable::{Channel, Reliable, WINDOW};
+use crate::render::{Player, Team};
+use crate::spawn::choose_spawn;
+use crate::sprites::Animation;
+use crate::tilemap::TileMap;
+use crate::weapons::{Projectile, ProjectileState, Trigger, fire, update_projectiles};
+use std::collections::{HashMap, VecDeque};
+use std::io;
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+use std::time::{Duration, Instant};
+
+pub const TICK_RATE: u32 = 30; // snapshots per second
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(5); // drop clients we stop hearing from
+pub const MAX_PLAYERS: usize = 16;
+// longest step a single input can ask for, a client stalling for longer loses the rest
+pub const MAX_INPUT_DT: f32 = 0.1;
+// movement time a client can save up while its inputs are late, more than that is dropped
+// so extra inputs can't make anyone faster than real time allows
+const MAX_MOVE_BACKLOG: f32 = 0.25;
+// chat lines a client can say in one go, after that it gets CHAT_RATE more a second
+pub const CHAT_BURST: f32 = 5.0;
+const CHAT_RATE: f32 = 1.0;
+
+struct Client {
+    id: u32,
+    name: String,
+    team: Team,
+    player: Player,
+    // seconds left until a dead player respawns
+    respawn_in: f32,
+    // seq of the last input we moved with, sent back in the snapshot
+    ack: u32,
+    // seconds of movement the client may still use, see MAX_MOVE_BACKLOG
+    move_time: f32,
+    // chat lines the client may still say, see CHAT_BURST
+    chat_allowance: f32,
+    // what the latest input held, for the animation and the trigger
+    walking: bool,
+    firing: bool,
+    trigger: Trigger,
+    last_seen: Instant,
+    // newest snapshot tick the client acked, snapshots are sent as deltas against it
+    baseline: Option<u32>,
+    bandwidth: Bandwidth,
+    reliable: Reliable,
+}
+
+// what the server sent one client since it joined
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Bandwidth {
+    pub packets: u64,
+    pub bytes: u64,
+    pub snapshots: u64,
+    // snapshots sent against a baseline, the rest went out full
+    pub deltas: u64,
+    pub snapshot_bytes: u64,
+    // what the same snapshots would have taken sent in full
+    pub full_bytes: u64,
+}
+
+impl Bandwidth {
+    fn sent(&mut self, packet: &[u8]) {
+        self.packets += 1;
+        self.bytes += packet.len() as u64;
+    }
+
+    // what went out after `earlier`, an earlier reading of the same client
+    pub fn since(&self, earlier: &Bandwidth) -> Bandwidth {
+        Bandwidth {
+            packets: self.packets - earlier.packets,
+            bytes: self.bytes - earlier.bytes,
+            snapshots: self.snapshots - earlier.snapshots,
+            deltas: self.deltas - earlier.deltas,
+            snapshot_bytes: self.snapshot_bytes - earlier.snapshot_bytes,
+            full_bytes: self.full_bytes - earlier.full_bytes,
+        }
+    }
+}
+
+pub struct Server {
+    socket: UdpSocket,
+    map: MapConfig,
+    tiles: TileMap,
+    // empty for a generated maze, see ServerMessage::Welcome
+    map_name: String,
+    map_version: u32,
+    spawns: Vec<(usize, usize)>,
+    clients: HashMap<SocketAddr, Client>,
+    next_id: u32,
+    projectiles: Vec<Projectile>,
+    next_projectile_id: u32,
+    tick: u32,
+    // the players of the last SNAPSHOT_HISTORY snapshots as sent, oldest first
+    history: VecDeque<(u32, Vec<PlayerState>)>,
+    // seconds on each map before moving on to the next seed, see rotate_maps
+    rotation: Option<f32>,
+    rotate_in: f32,
+}
+
+impl Server {
+    pub fn bind<A: ToSocketAddrs>(addr: A, map: MapConfig) -> io::Result<Self> {
+        let level = map.build_level().map_err(io::Error::other)?;
+        Self::with_level(addr, map, level)
+    }
+
+    // for a grid that didn't come from map.build_grid(), e.g. a hand made test level
+    pub fn with_grid<A: ToSocketAddrs>(
+        addr: A,
+        map: MapConfig,
+        grid: Vec<Vec<u8>>,
+    ) -> io::Result<Self> {
+        let mut level = MapFile::from_grid("", "", &grid);
+        level.add_auto_spawns();
+        Self::with_level(addr, map, level)
+    }
+
+    pub fn with_level<A: ToSocketAddrs>(
+        addr: A,
+        map: MapConfig,
+        level: MapFile,
+    ) -> io::Result<Self> {
+        let socket = UdpSocket::bind(addr)?;
+        socket.set_nonblocking(true)?;
+        let (map_name, map_version) = level_name(&map, &level);
+        Ok(Self {
+            socket,
+            map,
+            tiles: level.tiles,
+            map_name,
+            map_version,
+            spawns: level.spawns,
+            clients: HashMap::new(),
+            next_id: 1,
+            projectiles: Vec::new(),
+            next_projectile_id: 1,
+            tick: 0,
+            history: VecDeque::new(),
+            rotation: None,
+            rotate_in: 0.0,
+        })
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.socket.local_addr()
+    }
+
+    pub fn map(&self) -> &MapConfig {
+        &self.map
+    }
+
+    pub fn player_count(&self) -> usize {
+        self.clients.len()
+    }
+
+    // what has been sent to each client so far, by player id
+    pub fn bandwidth(&self) -> Vec<(u32, Bandwidth)> {
+        let mut all: Vec<(u32, Bandwidth)> =
+            self.clients.values().map(|c| (c.id, c.bandwidth)).collect();
+        all.sort_by_key(|&(id, _)| id);
+        all
+    }
+
+    // switches everyone over to another level: they all start over spread across its spawn
+    // points and get a Welcome for it on Channel::Game. rotate_maps calls it on a timer
+    pub fn change_map(&mut self, map: MapConfig, level: MapFile) {
+        (self.map_name, self.map_version) = level_name(&map, &level);
+        self.map = map;
+        self.tiles = level.tiles;
+        self.spawns = level.spawns;
+        self.projectiles.clear();
+        let mut ids: Vec<u32> = self.clients.values().map(|c| c.id).collect();
+        ids.sort();
+        // one after the other so nobody lands on someone placed before them
+        let mut placed = Vec::new();
+        for id in ids {
+            let at = choose_spawn(&self.tiles, &self.spawns, &placed, &placed);
+            placed.push(at);
+            let welcome = self.welcome(id);
+            if let Some(c) = self.clients.values_mut().find(|c| c.id == id) {
+                respawn(&mut c.player, at);
+                c.respawn_in = 0.0;
+                c.trigger.cooldown = 0.0;
+                // tiny next to MAX_MESSAGE, the map name is cut short
+                let _ = c.reliable.send(Channel::Game, welcome.encode());
+            }
+        }
+    }
+
+    // every `seconds` the server moves on to the maze of the next seed with the same options,
+    // through change_map. a hand made map has nothing to move on to
+    pub fn rotate_maps(&mut self, seconds: f32) -> Result<(), String> {
+        if self.map.file.is_some() {
+            return Err("only generated mazes can rotate, not a --map file".to_string());
+        }
+        self.rotation = Some(seconds);
+        self.rotate_in = seconds;
+        Ok(())
+    }
+
+    // runs forever at TICK_RATE, `after_tick` gets a look at the server after every tick
+    pub fn run(&mut self, mut after_tick: impl FnMut(&Server)) -> io::Result<()> {
+        let step = Duration::from_secs_f32(1.0 / TICK_RATE as f32);
+        let mut next = Instant::now();
+        loop {
+            self.tick(step.as_secs_f32())?;
+            after_tick(self);
+            next += step;
+            let now = Instant::now();
+            if next > now {
+                std::thread::sleep(next - now);
+            } else {
+                // we fell behind, don't try to catch up with a burst of ticks
+                next = now;
+            }
+        }
+    }
+
+    // one server step: read everything pending (moving players as their inputs arrive),
+    // resolve shots, send what is due on the reliable channels, send the snapshot
+    pub fn tick(&mut self, dt: f32) -> io::Result<()> {
+        for c in self.clients.values_mut() {
+            c.move_time = (c.move_time + dt).min(MAX_MOVE_BACKLOG);
+            c.chat_allowance = (c.chat_allowance + CHAT_RATE * dt).min(CHAT_BURST);
+        }
+        self.receive()?;
+
+        let now = Instant::now();
+        let mut gone = Vec::new();
+        self.clients.retain(|_, c| {
+            let alive = now.duration_since(c.last_seen) < CLIENT_TIMEOUT;
+            if !alive {
+                gone.push(c.id);
+            }
+            alive
+        });
+        for id in gone {
+            self.broadcast_reliable(Channel::Game, &ServerMessage::Left { id });
+        }
+
+        for kill in self.resolve_shots(dt) {
+            let msg = ServerMessage::Kill {
+                killer: kill.killer,
+                victim: kill.victim,
+                weapon: kill.weapon,
+            };
+            self.broadcast_reliable(Channel::Game, &msg);
+        }
+        self.respawn_dead(dt);
+        if let Some(seconds) = self.rotation {
+            self.rotate_in -= dt;
+            if self.rotate_in <= 0.0 {
+                self.rotate_in = seconds;
+                let map = MapConfig {
+                    seed: self.map.seed.wrapping_add(1),
+                    ..self.map.clone()
+                };
+                let level = map.build_level().map_err(io::Error::other)?;
+                self.change_map(map, level);
+            }
+        }
+
+        self.tick = self.tick.wrapping_add(1);
+        for (&addr, c) in &mut self.clients {
+            if let Some(packet) = c.reliable.update(dt) {
+                let packet = ServerMessage::Reliable(packet).encode();
+                c.bandwidth.sent(&packet);
+                send(&self.socket, &packet, addr)?;
+            }
+        }
+        self.broadcast_snapshot()
+    }
+
+    fn receive(&mut self) -> io::Result<()> {
+        let mut buf = [0u8; MAX_PACKET];
+        loop {
+            let (len, from) = match self.socket.recv_from(&mut buf) {
+                Ok(r) => r,
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => return Ok(()),
+                // on windows a previous send to a closed port shows up here, not fatal
+                Err(e) if e.kind() == io::ErrorKind::ConnectionReset => continue,
+                Err(e) => return Err(e),
+            };
+            // bad packets are dropped, a client can't take the server down
+            let Ok(msg) = ClientMessage::decode(&buf[..len]) else {
+                continue;
+            };
+            self.handle(from, msg)?;
+        }
+    }
+
+    fn handle(&mut self, from: SocketAddr, msg: ClientMessage) -> io::Result<()> {
+        match msg {
+            ClientMessage::Join { version } => {
+                if let Err(reply) = check_version(version) {
+                    return send(&self.socket, &reply.encode(), from);
+                }
+                if !self.clients.contains_key(&from) && self.clients.len() >= MAX_PLAYERS {
+                    let reply = ServerMessage::Error {
+                        code: ErrorCode::ServerFull,
+                        message: format!("server is full ({MAX_PLAYERS} players)"),
+                    };
+                    return send(&self.socket, &reply.encode(), from);
+                }
+                let id = match self.clients.get_mut(&from) {
+                    // join resent because the welcome got lost
+                    Some(c) => {
+                        c.last_seen = Instant::now();
+                        c.id
+                    }
+                    None => {
+                        let id = self.next_id;
+                        self.next_id += 1;
+                        // no team picked yet, everyone alive is an enemy and in the way
+                        let enemies = self.enemies_of(id, Team::None);
+                        let at = choose_spawn(&self.tiles, &self.spawns, &enemies, &enemies);
+                        let client = new_client(id, at);
+                        let joined = ServerMessage::Joined {
+                            id,
+                            name: client.name.clone(),
+                        };
+                        // before they are in so the newcomer isn't told about themselves
+                        self.broadcast_reliable(Channel::Game, &joined);
+                        self.clients.insert(from, client);
+                        id
+                    }
+                };
+                let packet = self.welcome(id).encode();
+                if let Some(c) = self.clients.get_mut(&from) {
+                    c.bandwidth.sent(&packet);
+                }
+                send(&self.socket, &packet, from)?;
+            }
+            ClientMessage::Input(input) => {
+                let Some(c) = self.clients.get_mut(&from) else {
+                    return Ok(());
+                };
+                c.last_seen = Instant::now();
+                // late or duplicated packet, a newer input already moved us
+                if input.tick <= c.ack {
+                    return Ok(());
+                }
+                let dt = finite_or_zero(input.dt)
+                    .clamp(0.0, MAX_INPUT_DT)
+                    .min(c.move_time);
+                c.move_time -= dt;
+                let input = PlayerInput {
+                    dt,
+                    forward: finite_or_zero(input.forward),
+                    strafe: finite_or_zero(input.strafe),
+                    turn: finite_or_zero(input.turn),
+                    yaw: finite_or_zero(input.yaw),
+                    ..input
+                };
+                c.ack = input.tick;
+                c.walking = input.forward != 0.0 || input.strafe != 0.0;
+                c.firing = input.fire();
+                // the dead don't move, their camera stays where they fell
+                if c.player.health == 0 {
+                    return Ok(());
+                }
+                update_player(&mut c.player, &self.tiles, &input);
+            }
+            ClientMessage::SnapshotAck { tick } => {
+                let Some(c) = self.clients.get_mut(&from) else {
+                    return Ok(());
+                };
+                c.last_seen = Instant::now();
+                // acks come in out of order too, the newest is the smallest delta. one for a
+                // tick we never sent can't be a baseline
+                if tick <= self.tick && c.baseline.is_none_or(|b| tick > b) {
+                    c.baseline = Some(tick);
+                }
+            }
+            ClientMessage::Leave => {
+                if let Some(c) = self.clients.remove(&from) {
+                    let left = ServerMessage::Left { id: c.id };
+                    self.broadcast_reliable(Channel::Game, &left);
+                }
+            }
+            ClientMessage::SelectWeapon { weapon } => {
+                if let Some(c) = self.clients.get_mut(&from) {
+                    c.trigger.weapon = weapon;
+                    c.last_seen = Instant::now();
+                }
+            }
+            ClientMessage::SetName { name } => {
+                if let Some(c) = self.clients.get_mut(&from) {
+                    c.name = clean_text(&name, MAX_PLAYER_NAME_LEN)
+                        .unwrap_or_else(|| default_name(c.id));
+                    c.last_seen = Instant::now();
+                }
+            }
+            ClientMessage::Say { text } => {
+                let Some(c) = self.clients.get_mut(&from) else {
+                    return Ok(());
+                };
+                c.last_seen = Instant::now();
+                if c.chat_allowance < 1.0 {
+                    return Ok(());
+                }
+                c.chat_allowance -= 1.0;
+                if let Some(text) = clean_text(&text, MAX_CHAT_LEN) {
+                    let chat = ServerMessage::Chat { from: c.id, text };
+                    self.broadcast_reliable(Channel::Chat, &chat);
+                }
+            }
+            ClientMessage::Reliable(packet) => {
+                let Some(c) = self.clients.get_mut(&from) else {
+                    return Ok(());
+                };
+                c.last_seen = Instant::now();
+                c.reliable.receive(&packet);
+                let mut delivered = Vec::new();
+                while let Some((_, payload)) = c.reliable.poll() {
+                    delivered.push(payload);
+                }
+                for payload in delivered {
+                    match ClientMessage::decode(&payload) {
+                        // handled like any other message, just one that can't get lost.
+                        // no packets inside packets
+                        Ok(ClientMessage::Reliable(_)) | Err(_) => {}
+                        Ok(msg) => self.handle(from, msg)?,
+                    }
+                }
+            }
+            // answered even before joining so a server browser can show the ping
+            ClientMessage::Ping { nonce } => {
+                send(&self.socket, &ServerMessage::Pong { nonce }.encode(), from)?;
+            }
+        }
+        Ok(())
+    }
+
+    // everyone shoots from where they stand after this tick's movement, all shots of a tick
+    // see the same positions so who fired first in the hashmap doesn't matter.
+    // projectiles already in flight move after that, new ones get their first step right away
+    // gives back who died
+    fn resolve_shots(&mut self, dt: f32) -> Vec<Kill> {
+        let targets: Vec<(u32, f32, f32)> = self
+            .clients
+            .values()
+            .filter(|c| c.player.health > 0)
+            .map(|c| (c.id, c.player.x, c.player.y))
+            .collect();
+        let mut hits = Vec::new();
+        for c in self.clients.values_mut() {
+            if !c.trigger.update(c.firing && c.player.health > 0, dt) {
+                continue;
+            }
+            let shooter = (c.id, c.player.x, c.player.y, c.player.angle);
+            hits.extend(fire(
+                c.trigger.weapon,
+                &self.tiles,
+                shooter,
+                &targets,
+                &mut self.projectiles,
+                &mut self.next_projectile_id,
+            ));
+        }
+        hits.extend(update_projectiles(
+            &mut self.projectiles,
+            &self.tiles,
+            &targets,
+            dt,
+        ));
+        let mut kills = Vec::new();
+        for hit in hits {
+            let Some(c) = self.clients.values_mut().find(|c| c.id == hit.target) else {
+                continue;
+            };
+            if apply_damage(&mut c.player, hit.damage) {
+                c.respawn_in = RESPAWN_DELAY;
+                kills.push(Kill {
+                    killer: hit.attacker,
+                    victim: hit.target,
+                    weapon: hit.weapon,
+                });
+            }
+        }
+        kills
+    }
+
+    fn respawn_dead(&mut self, dt: f32) {
+        let mut due = Vec::new();
+        for c in self.clients.values_mut() {
+            if c.player.health == 0 {
+                c.respawn_in -= dt;
+                if c.respawn_in <= 0.0 {
+                    due.push((c.id, c.team));
+                }
+            }
+        }
+        // one after the other so two players coming back in the same tick spread out too
+        for (id, team) in due {
+            let enemies = self.enemies_of(id, team);
+            // with no team everyone alive is an enemy, so this is every body in the way
+            let players = self.enemies_of(id, Team::None);
+            let at = choose_spawn(&self.tiles, &self.spawns, &enemies, &players);
+            if let Some(c) = self.clients.values_mut().find(|c| c.id == id) {
+                respawn(&mut c.player, at);
+                c.trigger.cooldown = 0.0;
+            }
+        }
+    }
+
+    // where everyone alive who could shoot player `id` stands, Team::None fights everybody
+    fn enemies_of(&self, id: u32, team: Team) -> Vec<(f32, f32)> {
+        self.clients
+            .values()
+            .filter(|c| c.id != id && c.player.health > 0)
+            .filter(|c| team == Team::None || c.team != team)
+            .map(|c| (c.player.x, c.player.y))
+            .collect()
+    }
+
+    // everyone gets the same snapshot, as a delta against what they acked last if we still
+    // have that one. the players are kept the way they went out for the deltas after
+    fn broadcast_snapshot(&mut self) -> io::Result<()> {
+        let mut players: Vec<PlayerState> = self
+            .clients
+            .values()
+            .map(|c| PlayerState {
+                id: c.id,
+                name: c.name.clone(),
+                team: c.team,
+                x: quantize_position(c.player.x),
+                y: quantize_position(c.player.y),
+                angle: quantize_angle(c.player.angle),
+                health: c.player.health,
+                armor: c.player.armor,
+                alive: c.player.health > 0,
+                animation: if c.player.health == 0 {
+                    Animation::Dead
+                } else if c.walking {
+                    Animation::Walk
+                } else {
+                    Animation::Idle
+                },
+                ack: c.ack,
+            })
+            .collect();
+        players.sort_by_key(|p| p.id);
+        let projectiles = self
+            .projectiles
+            .iter()
+            .map(|p| ProjectileState {
+                x: quantize_position(p.x),
+                y: quantize_position(p.y),
+                ..p.state()
+            })
+            .collect();
+        let snapshot = ServerMessage::Snapshot {
+            tick: self.tick,
+            players: players.clone(),
+            projectiles,
+        };
+        let full = snapshot.encode();
+        for (&addr, c) in &mut self.clients {
+            let baseline = c
+                .baseline
+                .and_then(|tick| self.history.iter().find(|(t, _)| *t == tick));
+            let packet = match baseline {
+                Some((tick, players)) => snapshot.encode_delta(*tick, players),
+                None => full.clone(),
+            };
+            c.bandwidth.sent(&packet);
+            c.bandwidth.snapshots += 1;
+            c.bandwidth.deltas += u64::from(baseline.is_some());
+            c.bandwidth.snapshot_bytes += packet.len() as u64;
+            c.bandwidth.full_bytes += full.len() as u64;
+            send(&self.socket, &packet, addr)?;
+        }
+        self.history.push_back((self.tick, players));
+        while self.history.len() > SNAPSHOT_HISTORY {
+            self.history.pop_front();
+        }
+        Ok(())
+    }
+
+    // queued for everyone, goes out with the next tick and again until acked. everything sent
+    // this way is far smaller than MAX_MESSAGE, names and chat lines are cut short
+    fn broadcast_reliable(&mut self, channel: Channel, msg: &ServerMessage) {
+        let payload = msg.encode();
+        for c in self.clients.values_mut() {
+            // chat a client isn't keeping up with is dropped rather than queued without end,
+            // everything else has to get there
+            if channel == Channel::Chat && c.reliable.in_flight_on(channel) >= WINDOW as usize {
+                continue;
+            }
+            let _ = c.reliable.send(channel, payload.clone());
+        }
+    }
+
+    fn welcome(&self, id: u32) -> ServerMessage {
+        ServerMessage::Welcome {
+            id,
+            seed: self.map.seed,
+            width: self.map.width as u16,
+            height: self.map.height as u16,
+            algorithm: self.map.algorithm,
+            braid: self.map.braid,
+            rooms: self.map.rooms,
+            map_name: self.map_name.clone(),
+            map_version: self.map_version,
+            sky: self.tiles.open_sky(),
+        }
+    }
+}
+
+fn send(socket: &UdpSocket, packet: &[u8], to: SocketAddr) -> io::Result<()> {
+    match socket.send_to(packet, to) {
+        Ok(_) => Ok(()),
+        // socket buffer full, the client just misses this packet
+        Err(e) if e.kind() == io::ErrorKind::WouldBlock => Ok(()),
+        Err(e) => Err(e),
+    }
+}
+
+fn new_client(id: u32, (x, y): (f32, f32)) -> Client {
+    Client {
+        id,
+        name: default_name(id),
+        team: Team::None,
+        player: Player::at(x, y, 0.0),
+        respawn_in: 0.0,
+        ack: 0,
+        move_time: 0.0,
+        chat_allowance: CHAT_BURST,
+        walking: false,
+        firing: false,
+        trigger: Trigger::default(),
+        last_seen: Instant::now(),
+        baseline: None,
+        bandwidth: Bandwidth::default(),
+        reliable: Reliable::new(),
+    }
+}
+
+fn default_name(id: u32) -> String {
+    format!("player {id}")
+}
+
+// only a loaded file has a name worth showing, generated mazes are described by the seed
+fn level_name(map: &MapConfig, level: &MapFile) -> (String, u32) {
+    if map.file.is_some() {
+        (level.name.clone(), level.version)
+    } else {
+        (String::new(), 0)
+    }
+}
+
+// printable and at most `max` bytes, None if nothing is left
+fn clean_text(text: &str, max: usize) -> Option<String> {
+    let mut out = String::new();
+    for c in text.trim().chars().filter(|c| !c.is_control()) {
+        if out.len() + c.len_utf8() > max {
+            break;
+        }
+        out.push(c);
+    }
+    (!out.is_empty()).then_some(out)
+}
+
+// NaN from a broken client would poison the player position forever
+fn finite_or_zero(v: f32) -> f32 {
+    if v.is_finite() { v } else { 0.0 }
+}
@@ -0,0 +1,161 @@
+// where players appear when they join and after they die. a spawn point only counts when
+// it's open floor nobody is standing on, and of those the one furthest from the closest
+// enemy wins so nobody comes back in front of a gun. maps without authored spawn points
+// (every generated maze) get theirs from auto_spawns
+
+use crate::mechanics::RADIUS;
+use crate::tilemap::TileMap;
+use std::collections::VecDeque;
+
+// one spawn point for about this many open cells
+pub const CELLS_PER_SPAWN: usize = 24;
+// a generated map never gets fewer than two or more than this
+pub const MAX_SPAWNS: usize = 16;
+
+// floor a player can stand on without getting hurt
+pub fn is_open(map: &TileMap, x: usize, y: usize) -> bool {
+    map.get(x, y)
+        .is_some_and(|t| !t.is_solid() && !t.is_hazard())
+}
+
+// steps over open cells from the closest of `sources`, None where they can't be reached
+pub fn walk_distances(map: &TileMap, sources: &[(usize, usize)]) -> Vec<Vec<Option<u32>>> {
+    let (w, h) = (map.width(), map.height());
+    let mut dist = vec![vec![None; w]; h];
+    let mut queue = VecDeque::new();
+    for &(x, y) in sources {
+        if is_open(map, x, y) && dist[y][x].is_none() {
+            dist[y][x] = Some(0);
+            queue.push_back((x, y));
+        }
+    }
+    while let Some((x, y)) = queue.pop_front() {
+        let d = dist[y][x].unwrap_or(0) + 1;
+        let around = [
+            (x.wrapping_sub(1), y),
+            (x + 1, y),
+            (x, y.wrapping_sub(1)),
+            (x, y + 1),
+        ];
+        for (nx, ny) in around {
+            if is_open(map, nx, ny) && dist[ny][nx].is_none() {
+                dist[ny][nx] = Some(d);
+                queue.push_back((nx, ny));
+            }
+        }
+    }
+    dist
+}
+
+// up to `count` spawn points spread out over the biggest stretch of connected floor, so a
+// walled off pocket never gets them all: its first open cell first, then every next one as
+// many steps away from the ones already picked as possible (ties go to the first in reading
+// order)
+pub fn pick_spawns(map: &TileMap, count: usize) -> Vec<(usize, usize)> {
+    let Some(first) = largest_region(map) else {
+        return Vec::new();
+    };
+    let mut spawns = vec![first];
+    while spawns.len() < count {
+        let dist = walk_distances(map, &spawns);
+        let mut best: Option<((usize, usize), u32)> = None;
+        for (y, row) in dist.iter().enumerate() {
+            for (x, &d) in row.iter().enumerate() {
+                if let Some(d) = d
+                    && best.is_none_or(|(_, b)| d > b)
+                {
+                    best = Some(((x, y), d));
+                }
+            }
+        }
+        // every reachable cell already is a spawn point
+        match best {
+            Some((cell, d)) if d > 0 => spawns.push(cell),
+            _ => break,
+        }
+    }
+    spawns
+}
+
+// first open cell in reading order of the biggest connected stretch of floor, the first
+// such stretch on a tie
+fn largest_region(map: &TileMap) -> Option<(usize, usize)> {
+    let mut counted = vec![vec![false; map.width()]; map.height()];
+    let mut best: Option<((usize, usize), usize)> = None;
+    for (x, y, _) in map.iter() {
+        if !is_open(map, x, y) || counted[y][x] {
+            continue;
+        }
+        let mut size = 0;
+        for (row, dist) in counted.iter_mut().zip(walk_distances(map, &[(x, y)])) {
+            for (cell, d) in row.iter_mut().zip(dist) {
+                if d.is_some() {
+                    *cell = true;
+                    size += 1;
+                }
+            }
+        }
+        if best.is_none_or(|(_, b)| size > b) {
+            best = Some(((x, y), size));
+        }
+    }
+    best.map(|(cell, _)| cell)
+}
+
+// spawn points for a map that came without any, more of them on bigger maps. in reading
+// order like a map file lists them, so a saved and loaded map picks the same ones
+pub fn auto_spawns(map: &TileMap) -> Vec<(usize, usize)> {
+    let open = map.iter().filter(|&(x, y, _)| is_open(map, x, y)).count();
+    let mut spawns = pick_spawns(map, (open / CELLS_PER_SPAWN).clamp(2, MAX_SPAWNS));
+    spawns.sort_unstable_by_key(|&(x, y)| (y, x));
+    spawns
+}
+
+// center of the chosen cell. enemies are where everyone alive who isn't on our team stands,
+// players everyone alive at all (teammates too), nobody spawns inside one of them.
+// spawn points that aren't open or are taken are skipped, with none left the safest free
+// open cell of the map is used (and (1, 1) for a map without any floor)
+pub fn choose_spawn(
+    map: &TileMap,
+    spawns: &[(usize, usize)],
+    enemies: &[(f32, f32)],
+    players: &[(f32, f32)],
+) -> (f32, f32) {
+    let center = |x: usize, y: usize| (x as f32 + 0.5, y as f32 + 0.5);
+    // squared distance to the closest enemy, infinite with nobody around
+    let safety = |x: usize, y: usize| {
+        let (cx, cy) = center(x, y);
+        enemies
+            .iter()
+            .map(|&(ex, ey)| (ex - cx).powi(2) + (ey - cy).powi(2))
+            .fold(f32::INFINITY, f32::min)
+    };
+    // two bodies closer than this overlap
+    let free = |x: usize, y: usize| {
+        let (cx, cy) = center(x, y);
+        is_open(map, x, y)
+            && players
+                .iter()
+                .all(|&(px, py)| (px - cx).powi(2) + (py - cy).powi(2) >= (2.0 * RADIUS).powi(2))
+    };
+    let safest = |cells: &mut dyn Iterator<Item = (usize, usize)>| {
+        let mut best: Option<((usize, usize), f32)> = None;
+        for (x, y) in cells.filter(|&(x, y)| free(x, y)) {
+            let s = safety(x, y);
+            // the first of equally safe cells wins
+            if best.is_none_or(|(_, b)| s > b) {
+                best = Some(((x, y), s));
+            }
+        }
+        best.map(|(cell, _)| cell)
+    };
+    let (x, y) = safest(&mut spawns.iter().copied())
+        .or_else(|| safest(&mut map.iter().map(|(x, y, _)| (x, y))))
+        .or_else(|| {
+            map.iter()
+                .find(|&(x, y, _)| is_open(map, x, y))
+                .map(|(x, y, _)| (x, y))
+        })
+        .unwrap_or((1, 1));
+    center(x, y)
+}
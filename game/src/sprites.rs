@@ -0,0 +1,143 @@
+// billboard sprites: everything in the level that isn't a wall (other players for now) is a
+// flat picture that always faces the camera. a sprite sheet is one png, a column per
+// direction the thing can be seen from and a row per animation frame. pixels with alpha
+// under half are holes, the level behind shows through them
+
+use crate::textures::Texture;
+use std::f32::consts::TAU;
+
+// columns of a full rotation set, one every 45 degrees
+pub const ROTATIONS: usize = 8;
+
+pub const PLAYER_SPRITE: u8 = 1;
+pub const ROCKET_SPRITE: u8 = 2;
+pub const GRENADE_SPRITE: u8 = 3;
+// rows of player.png, see Animation::frame
+const WALK_FRAMES: usize = 4;
+const DEAD_FRAME: usize = 4;
+const WALK_FPS: f32 = 8.0;
+
+// sprite id -> file in the textures folder and how many rotation columns it has
+// (1 for things that look the same from every side)
+pub const SPRITE_FILES: [(u8, &str, usize); 3] = [
+    (PLAYER_SPRITE, "player.png", ROTATIONS),
+    (ROCKET_SPRITE, "rocket.png", 1),
+    (GRENADE_SPRITE, "grenade.png", 1),
+];
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct SpriteSheet {
+    pub texture: Texture,
+    // frames are square, frame_w = texture.width / rotations
+    pub frame_size: usize,
+    pub rotations: usize,
+    pub frames: usize,
+}
+
+impl SpriteSheet {
+    pub fn new(texture: Texture, rotations: usize) -> Result<Self, String> {
+        if rotations == 0 || !texture.width.is_multiple_of(rotations) {
+            return Err(format!(
+                "sprite sheet is {} wide, can't split it into {rotations} rotations",
+                texture.width
+            ));
+        }
+        let frame_size = texture.width / rotations;
+        if frame_size == 0 || !texture.height.is_multiple_of(frame_size) {
+            return Err(format!(
+                "sprite sheet is {} high, not a whole number of {frame_size}px frames",
+                texture.height
+            ));
+        }
+        Ok(Self {
+            frames: texture.height / frame_size,
+            texture,
+            frame_size,
+            rotations,
+        })
+    }
+
+    pub fn load(path: impl AsRef<std::path::Path>, rotations: usize) -> Result<Self, String> {
+        let path = path.as_ref();
+        Self::new(Texture::load(path)?, rotations).map_err(|e| format!("{}: {e}", path.display()))
+    }
+
+    // pixel (u, v) of one frame, u and v in 0..frame_size. frame and rotation wrap around
+    pub fn texel(&self, frame: usize, rotation: usize, u: usize, v: usize) -> u32 {
+        let (col, row) = (rotation % self.rotations, frame % self.frames);
+        self.texture
+            .texel(col * self.frame_size + u, row * self.frame_size + v)
+    }
+}
+
+// one thing to draw
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Sprite {
+    pub x: f32,
+    pub y: f32,
+    // where it faces, radians like Player::angle
+    pub angle: f32,
+    // SPRITE_FILES id
+    pub sheet: u8,
+    // animation frame, row of the sheet
+    pub frame: usize,
+}
+
+// which column of the sheet the viewer sees. column 0 is the front, the next ones go
+// around the sprite the way angles grow (clockwise, y goes down on the map), so
+// column 2 is seen from its right side. sprites with a single column always get 0
+pub fn rotation(sprite: &Sprite, viewer_x: f32, viewer_y: f32, rotations: usize) -> usize {
+    if rotations <= 1 {
+        return 0;
+    }
+    let to_viewer = (viewer_y - sprite.y).atan2(viewer_x - sprite.x);
+    let rel = (to_viewer - sprite.angle).rem_euclid(TAU);
+    let slice = TAU / rotations as f32;
+    ((rel / slice).round() as usize) % rotations
+}
+
+// looping animation: the frame to show t seconds into an animation running at fps
+pub fn animation_frame(t: f32, fps: f32, frames: usize) -> usize {
+    if frames == 0 || t <= 0.0 {
+        return 0;
+    }
+    (t * fps) as usize % frames
+}
+
+// what a player sprite is doing
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum Animation {
+    #[default]
+    Idle,
+    Walk,
+    Dead,
+}
+
+impl Animation {
+    // row of the player sheet t seconds into the animation
+    pub fn frame(self, t: f32) -> usize {
+        match self {
+            // first step of the walk cycle doubles as standing still
+            Animation::Idle => 0,
+            Animation::Walk => animation_frame(t, WALK_FPS, WALK_FRAMES),
+            Animation::Dead => DEAD_FRAME,
+        }
+    }
+
+    pub fn to_u8(self) -> u8 {
+        match self {
+            Animation::Idle => 0,
+            Animation::Walk => 1,
+            Animation::Dead => 2,
+        }
+    }
+
+    // unknown ids from a newer server just stand there
+    pub fn from_u8(v: u8) -> Self {
+        match v {
+            1 => Animation::Walk,
+            2 => Animation::Dead,
+            _ => Animation::Idle,
+        }
+    }
+}
@@ -0,0 +1,68 @@
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use sdl2::render::TextureQuery;
+use sdl2::render::{Canvas, TextureCreator};
+use sdl2::ttf::{Font, Sdl2TtfContext};
+use sdl2::video::Window;
+
+// a font for text placed in the world rather than the hud, like the name tags over players
+pub struct Text<'a> {
+    font: Font<'a, 'a>,
+}
+
+impl<'a> Text<'a> {
+    pub fn new(
+        ttf_context: &'a Sdl2TtfContext,
+        font_path: &str,
+        size: u16,
+    ) -> Result<Self, String> {
+        Ok(Self {
+            font: ttf_context.load_font(font_path, size)?,
+        })
+    }
+
+    // text centered on x with its bottom at y, in the given color. for the name tags
+    // render_frame gives back
+    pub fn draw_tag(
+        &self,
+        canvas: &mut Canvas<Window>,
+        texture_creator: &TextureCreator<sdl2::video::WindowContext>,
+        text: &str,
+        (x, y): (i32, i32),
+        color: (u8, u8, u8),
+    ) -> Result<(), String> {
+        draw_text(
+            &self.font,
+            canvas,
+            texture_creator,
+            (text, color),
+            |w, h| (x - w as i32 / 2, y - h as i32),
+        )
+    }
+}
+
+// renders one line and copies it onto the canvas, `place` gets its size and gives back the
+// top left corner. nothing to draw for an empty line
+pub fn draw_text(
+    font: &Font,
+    canvas: &mut Canvas<Window>,
+    texture_creator: &TextureCreator<sdl2::video::WindowContext>,
+    (text, (r, g, b)): (&str, (u8, u8, u8)),
+    place: impl FnOnce(u32, u32) -> (i32, i32),
+) -> Result<(), String> {
+    if text.is_empty() {
+        return Ok(());
+    }
+    let surface = font
+        .render(text)
+        .blended(Color::RGB(r, g, b))
+        .map_err(|e| e.to_string())?;
+    let texture = texture_creator
+        .create_texture_from_surface(&surface)
+        .map_err(|e| e.to_string())?;
+
+    let TextureQuery { width, height, .. } = texture.query();
+    let (x, y) = place(width, height);
+    canvas.copy(&texture, None, Some(Rect::new(x, y, width, height)))?;
+    Ok(())
+}
@@ -0,0 +1,170 @@
+// wall, floor, sky and sprite textures, decoded once from the png files in textures/ into plain pixels.
+// no sdl2 in here, the raycaster samples them straight into its framebuffer.
+// a pixel is 0xRRGGBBAA, the same layout as sdl's RGBA8888
+
+use crate::sprites::{SPRITE_FILES, SpriteSheet};
+use crate::tilemap::{
+    CEILING_TEXTURE, DOOR_TEXTURE, FLOOR_TEXTURE, HAZARD_CEILING_TEXTURE, HAZARD_FLOOR_TEXTURE,
+    SPAWN_FLOOR_TEXTURE,
+};
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::path::Path;
+
+// Tile::wall_texture id -> file in the textures folder, ids without a file use id 1
+pub const WALL_FILES: [(u8, &str); 6] = [
+    (1, "stone.png"),
+    (2, "brick.png"),
+    (3, "moss.png"),
+    (4, "bluestone.png"),
+    (5, "sandstone.png"),
+    (DOOR_TEXTURE, "door.png"),
+];
+
+// Tile::floor_texture / ceiling_texture id -> file, ids without a file use the plain floor
+pub const FLAT_FILES: [(u8, &str); 5] = [
+    (FLOOR_TEXTURE, "floor.png"),
+    (CEILING_TEXTURE, "ceiling.png"),
+    (HAZARD_FLOOR_TEXTURE, "hazard.png"),
+    (SPAWN_FLOOR_TEXTURE, "spawn.png"),
+    (HAZARD_CEILING_TEXTURE, "hazard_ceiling.png"),
+];
+
+// panorama drawn instead of the ceiling on open sky maps, its width is one full turn
+pub const SKY_FILE: &str = "sky.png";
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Texture {
+    pub width: usize,
+    pub height: usize,
+    // row major
+    pub pixels: Vec<u32>,
+}
+
+impl Texture {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref();
+        let bytes = std::fs::read(path)
+            .map_err(|e| format!("can't read texture {}: {e}", path.display()))?;
+        Self::decode(&bytes).map_err(|e| format!("{}: {e}", path.display()))
+    }
+
+    // any png, palettes, grayscale and 16 bit get expanded to 8 bit rgba
+    pub fn decode(png_bytes: &[u8]) -> Result<Self, String> {
+        let mut decoder = png::Decoder::new(Cursor::new(png_bytes));
+        decoder.set_transformations(png::Transformations::normalize_to_color8());
+        let mut reader = decoder.read_info().map_err(|e| e.to_string())?;
+        let mut buf = vec![0; reader.output_buffer_size().ok_or("png is too big")?];
+        let info = reader.next_frame(&mut buf).map_err(|e| e.to_string())?;
+
+        let (width, height) = (info.width as usize, info.height as usize);
+        let channels = info.color_type.samples();
+        let mut pixels = Vec::with_capacity(width * height);
+        for row in buf.chunks(info.line_size).take(height) {
+            for px in row[..width * channels].chunks(channels) {
+                let [r, g, b, a] = match *px {
+                    [l] => [l, l, l, 255],
+                    [l, a] => [l, l, l, a],
+                    [r, g, b] => [r, g, b, 255],
+                    [r, g, b, a] => [r, g, b, a],
+                    _ => return Err(format!("unsupported png color type {:?}", info.color_type)),
+                };
+                pixels.push(u32::from_be_bytes([r, g, b, a]));
+            }
+        }
+        Ok(Self {
+            width,
+            height,
+            pixels,
+        })
+    }
+
+    // 8 bit rgba png, decode gives back the same pixels
+    pub fn encode_png(&self) -> Result<Vec<u8>, String> {
+        let mut out = Vec::new();
+        let mut encoder = png::Encoder::new(&mut out, self.width as u32, self.height as u32);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header().map_err(|e| e.to_string())?;
+        let bytes: Vec<u8> = self.pixels.iter().flat_map(|p| p.to_be_bytes()).collect();
+        writer.write_image_data(&bytes).map_err(|e| e.to_string())?;
+        writer.finish().map_err(|e| e.to_string())?;
+        Ok(out)
+    }
+
+    pub fn save_png(&self, path: impl AsRef<Path>) -> Result<(), String> {
+        let path = path.as_ref();
+        std::fs::write(path, self.encode_png()?)
+            .map_err(|e| format!("can't write {}: {e}", path.display()))
+    }
+
+    // wraps around, so u and v can come straight from the texture math
+    pub fn texel(&self, u: usize, v: usize) -> u32 {
+        self.pixels[(v % self.height) * self.width + u % self.width]
+    }
+}
+
+pub struct TextureSet {
+    walls: HashMap<u8, Texture>,
+    flats: HashMap<u8, Texture>,
+    sky: Texture,
+    sprites: HashMap<u8, SpriteSheet>,
+}
+
+impl TextureSet {
+    // every file of WALL_FILES, FLAT_FILES, SKY_FILE and SPRITE_FILES must be in dir
+    pub fn load_dir(dir: impl AsRef<Path>) -> Result<Self, String> {
+        let dir = dir.as_ref();
+        let load_all = |files: &[(u8, &str)]| -> Result<HashMap<u8, Texture>, String> {
+            files
+                .iter()
+                .map(|&(id, file)| Ok((id, Texture::load(dir.join(file))?)))
+                .collect()
+        };
+        Ok(Self {
+            walls: load_all(&WALL_FILES)?,
+            flats: load_all(&FLAT_FILES)?,
+            sky: Texture::load(dir.join(SKY_FILE))?,
+            sprites: SPRITE_FILES
+                .iter()
+                .map(|&(id, file, rotations)| {
+                    Ok((id, SpriteSheet::load(dir.join(file), rotations)?))
+                })
+                .collect::<Result<_, String>>()?,
+        })
+    }
+
+    // the texture for Tile::wall_texture, unknown ids get the plain stone wall
+    pub fn wall(&self, id: u8) -> &Texture {
+        self.walls.get(&id).unwrap_or_else(|| &self.walls[&1])
+    }
+
+    // the texture for Tile::floor_texture / ceiling_texture
+    pub fn flat(&self, id: u8) -> &Texture {
+        self.flats
+            .get(&id)
+            .unwrap_or_else(|| &self.flats[&FLOOR_TEXTURE])
+    }
+
+    pub fn sky(&self) -> &Texture {
+        &self.sky
+    }
+
+    // None for ids without a sheet, those sprites don't get drawn
+    pub fn sprite(&self, id: u8) -> Option<&SpriteSheet> {
+        self.sprites.get(&id)
+    }
+
+    pub fn walls(&self) -> impl Iterator<Item = (u8, &Texture)> {
+        self.walls.iter().map(|(&id, t)| (id, t))
+    }
+}
+
+// which texture column a ray hit. wall_x is where on the wall face it landed, 0.0..1.0.
+// flip is for the faces seen from the other side (ray going +x on an x side, -y on a y side),
+// without it those textures come out mirrored
+pub fn texture_x(wall_x: f32, tex_w: usize, flip: bool) -> usize {
+    let frac = wall_x - wall_x.floor();
+    let tx = ((frac * tex_w as f32) as usize).min(tex_w - 1);
+    if flip { tex_w - 1 - tx } else { tx }
+}
@@ -0,0 +1,164 @@
+// the level as the game sees it: a grid of typed tiles.
+// the maze generators and carve_rooms still work on plain u8 grids (0 = empty, n = wall n),
+// TileMap::from_grid turns those into tiles once the level is done.
+// everything that walks, shoots or looks through the level asks the tile (is_solid,
+// is_opaque, wall_texture) instead of comparing numbers, so a new kind of tile only
+// needs a new arm in the matches below
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum Tile {
+    #[default]
+    Empty,
+    // wall with a texture / color id, 1 is the plain maze wall
+    Wall(u8),
+    // closed for now, blocks movement and sight like a wall
+    Door,
+    // blocks movement but you can see through it
+    Window,
+    // floor where players appear, see MapFile::spawns
+    Spawn,
+    // floor that hurts
+    Hazard,
+}
+
+// texture id of doors, past anything a wall digit in a map file can name
+pub const DOOR_TEXTURE: u8 = 10;
+// floor and ceiling texture ids, same numbering as the walls
+pub const FLOOR_TEXTURE: u8 = 11;
+pub const CEILING_TEXTURE: u8 = 12;
+pub const HAZARD_FLOOR_TEXTURE: u8 = 13;
+pub const SPAWN_FLOOR_TEXTURE: u8 = 14;
+pub const HAZARD_CEILING_TEXTURE: u8 = 15;
+
+impl Tile {
+    // players and projectiles can't pass
+    pub fn is_solid(self) -> bool {
+        matches!(self, Tile::Wall(_) | Tile::Door | Tile::Window)
+    }
+
+    // stops a ray, the raycaster draws the first opaque tile it hits
+    pub fn is_opaque(self) -> bool {
+        matches!(self, Tile::Wall(_) | Tile::Door)
+    }
+
+    // what the raycaster paints on an opaque tile
+    pub fn wall_texture(self) -> Option<u8> {
+        match self {
+            Tile::Wall(t) => Some(t),
+            Tile::Door => Some(DOOR_TEXTURE),
+            _ => None,
+        }
+    }
+
+    // what the floor caster paints under this tile
+    pub fn floor_texture(self) -> u8 {
+        match self {
+            Tile::Hazard => HAZARD_FLOOR_TEXTURE,
+            Tile::Spawn => SPAWN_FLOOR_TEXTURE,
+            _ => FLOOR_TEXTURE,
+        }
+    }
+
+    // and above it, unless the map has an open sky. lava lights up the ceiling over it
+    pub fn ceiling_texture(self) -> u8 {
+        match self {
+            Tile::Hazard => HAZARD_CEILING_TEXTURE,
+            _ => CEILING_TEXTURE,
+        }
+    }
+
+    pub fn is_hazard(self) -> bool {
+        self == Tile::Hazard
+    }
+
+    // generator grids only know empty and walls
+    pub fn from_u8(v: u8) -> Self {
+        match v {
+            0 => Tile::Empty,
+            n => Tile::Wall(n),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct TileMap {
+    width: usize,
+    height: usize,
+    // row major, y * width + x
+    tiles: Vec<Tile>,
+    // no ceiling, the renderer draws the sky panorama instead
+    sky: bool,
+}
+
+impl TileMap {
+    pub fn new(width: usize, height: usize, fill: Tile) -> Self {
+        Self {
+            width,
+            height,
+            tiles: vec![fill; width * height],
+            sky: false,
+        }
+    }
+
+    // rows must all have the width of the first one
+    pub fn from_grid(grid: &[Vec<u8>]) -> Self {
+        let width = grid.first().map_or(0, |row| row.len());
+        let mut tiles = Vec::with_capacity(width * grid.len());
+        for row in grid {
+            assert_eq!(row.len(), width, "ragged grid");
+            tiles.extend(row.iter().map(|&v| Tile::from_u8(v)));
+        }
+        Self {
+            width,
+            height: grid.len(),
+            tiles,
+            sky: false,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn open_sky(&self) -> bool {
+        self.sky
+    }
+
+    pub fn set_open_sky(&mut self, sky: bool) {
+        self.sky = sky;
+    }
+
+    // None outside the map
+    pub fn get(&self, x: usize, y: usize) -> Option<Tile> {
+        (x < self.width && y < self.height).then(|| self.tiles[y * self.width + x])
+    }
+
+    pub fn get_mut(&mut self, x: usize, y: usize) -> Option<&mut Tile> {
+        (x < self.width && y < self.height).then(|| &mut self.tiles[y * self.width + x])
+    }
+
+    // same as get for the signed tile coordinates the raycaster and collision code step through
+    pub fn at(&self, x: i32, y: i32) -> Option<Tile> {
+        if x < 0 || y < 0 {
+            return None;
+        }
+        self.get(x as usize, y as usize)
+    }
+
+    // tile under a world position, tiles are 1.0 wide
+    pub fn at_point(&self, x: f32, y: f32) -> Option<Tile> {
+        self.at(x.floor() as i32, y.floor() as i32)
+    }
+
+    // (x, y, tile) for every tile, row by row
+    pub fn iter(&self) -> impl Iterator<Item = (usize, usize, Tile)> + '_ {
+        self.tiles
+            .iter()
+            .enumerate()
+            .map(|(i, &t)| (i % self.width, i / self.width, t))
+    }
+}
@@ -0,0 +1,480 @@
+// weapons. the server resolves every shot in Server::tick, a client only says its fire
+// button is down and sees what happened in the health of the next snapshots, so nobody
+// gets to decide their own kills.
+// hitscan weapons hit instantly: a ray from the shooter stops at the first solid tile
+// (same grid walk as the renderer) or the first player circle it crosses.
+// rockets and grenades are projectiles instead, they fly a little every tick, collide with
+// walls like a (small) player does and blow up with splash damage
+
+use crate::mechanics::{RADIUS, can_stand};
+use crate::raycast::solid_distance;
+use crate::sprites::{GRENADE_SPRITE, ROCKET_SPRITE, Sprite};
+use crate::tilemap::TileMap;
+
+// most projectiles in flight at once, a snapshot with all of them still fits in a packet
+pub const MAX_PROJECTILES: usize = 40;
+// how much speed a grenade keeps when it bounces off a wall
+const BOUNCE: f32 = 0.6;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum Weapon {
+    #[default]
+    Pistol,
+    Rifle,
+    Rocket,
+    Grenade,
+}
+
+impl Weapon {
+    pub const ALL: [Weapon; 4] = [
+        Weapon::Pistol,
+        Weapon::Rifle,
+        Weapon::Rocket,
+        Weapon::Grenade,
+    ];
+
+    // what pulling the trigger does
+    pub fn attack(self) -> Attack {
+        match self {
+            Weapon::Pistol => Attack::Hitscan { range: 30.0 },
+            Weapon::Rifle => Attack::Hitscan { range: 50.0 },
+            Weapon::Rocket => Attack::Projectile(ProjectileKind::Rocket),
+            Weapon::Grenade => Attack::Projectile(ProjectileKind::Grenade),
+        }
+    }
+
+    // per hit, for projectiles what a player right at the explosion takes
+    pub fn damage(self) -> u8 {
+        match self {
+            Weapon::Pistol => 25,
+            Weapon::Rifle => 12,
+            Weapon::Rocket => ProjectileKind::Rocket.damage(),
+            Weapon::Grenade => ProjectileKind::Grenade.damage(),
+        }
+    }
+
+    // seconds between shots
+    pub fn cooldown(self) -> f32 {
+        match self {
+            Weapon::Pistol => 0.4,
+            Weapon::Rifle => 0.1,
+            Weapon::Rocket => 0.8,
+            Weapon::Grenade => 0.6,
+        }
+    }
+
+    // for the killfeed
+    pub fn name(self) -> &'static str {
+        match self {
+            Weapon::Pistol => "pistol",
+            Weapon::Rifle => "rifle",
+            Weapon::Rocket => "rocket",
+            Weapon::Grenade => "grenade",
+        }
+    }
+
+    pub fn to_u8(self) -> u8 {
+        match self {
+            Weapon::Pistol => 0,
+            Weapon::Rifle => 1,
+            Weapon::Rocket => 2,
+            Weapon::Grenade => 3,
+        }
+    }
+
+    // unknown weapons from a newer client fall back to the pistol
+    pub fn from_u8(v: u8) -> Self {
+        match v {
+            1 => Weapon::Rifle,
+            2 => Weapon::Rocket,
+            3 => Weapon::Grenade,
+            _ => Weapon::Pistol,
+        }
+    }
+}
+
+// what a weapon does when it goes off
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Attack {
+    // the bullet hits right away, nothing further than `range` tiles
+    Hitscan { range: f32 },
+    // flies until it hits something or its fuse runs out
+    Projectile(ProjectileKind),
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum ProjectileKind {
+    // flies straight and goes off at the first wall or player
+    #[default]
+    Rocket,
+    // bounces off walls, rolls to a stop and goes off when the fuse runs out or it hits a player
+    Grenade,
+}
+
+impl ProjectileKind {
+    // what fired it
+    pub fn weapon(self) -> Weapon {
+        match self {
+            ProjectileKind::Rocket => Weapon::Rocket,
+            ProjectileKind::Grenade => Weapon::Grenade,
+        }
+    }
+
+    // tiles per second
+    pub fn speed(self) -> f32 {
+        match self {
+            ProjectileKind::Rocket => 8.0,
+            ProjectileKind::Grenade => 6.0,
+        }
+    }
+
+    // collision radius, walls are tested with can_stand like in update_player and players
+    // as circles of RADIUS
+    pub fn radius(self) -> f32 {
+        match self {
+            ProjectileKind::Rocket => 0.1,
+            ProjectileKind::Grenade => 0.08,
+        }
+    }
+
+    // seconds until it goes off by itself
+    pub fn fuse(self) -> f32 {
+        match self {
+            ProjectileKind::Rocket => 10.0,
+            ProjectileKind::Grenade => 2.0,
+        }
+    }
+
+    pub fn bounces(self) -> bool {
+        self == ProjectileKind::Grenade
+    }
+
+    // share of the speed lost per second while flying
+    fn drag(self) -> f32 {
+        match self {
+            ProjectileKind::Rocket => 0.0,
+            ProjectileKind::Grenade => 0.8,
+        }
+    }
+
+    // explosion reach in tiles, damage falls off to nothing at the edge
+    pub fn splash_radius(self) -> f32 {
+        match self {
+            ProjectileKind::Rocket => 2.0,
+            ProjectileKind::Grenade => 2.5,
+        }
+    }
+
+    pub fn damage(self) -> u8 {
+        match self {
+            ProjectileKind::Rocket => 90,
+            ProjectileKind::Grenade => 80,
+        }
+    }
+
+    pub fn to_u8(self) -> u8 {
+        match self {
+            ProjectileKind::Rocket => 0,
+            ProjectileKind::Grenade => 1,
+        }
+    }
+
+    // unknown kinds from a newer server are drawn as rockets
+    pub fn from_u8(v: u8) -> Self {
+        match v {
+            1 => ProjectileKind::Grenade,
+            _ => ProjectileKind::Rocket,
+        }
+    }
+}
+
+// what the clients see of a projectile, the server keeps the rest
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub struct ProjectileState {
+    pub id: u32,
+    pub kind: ProjectileKind,
+    pub x: f32,
+    pub y: f32,
+}
+
+impl ProjectileState {
+    // both sheets look the same from every side, the angle doesn't matter
+    pub fn sprite(&self) -> Sprite {
+        Sprite {
+            x: self.x,
+            y: self.y,
+            angle: 0.0,
+            sheet: match self.kind {
+                ProjectileKind::Rocket => ROCKET_SPRITE,
+                ProjectileKind::Grenade => GRENADE_SPRITE,
+            },
+            frame: 0,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Projectile {
+    pub id: u32,
+    // never hit by its own projectile directly, the splash still hurts
+    pub owner: u32,
+    pub kind: ProjectileKind,
+    pub x: f32,
+    pub y: f32,
+    pub vx: f32,
+    pub vy: f32,
+    // seconds left until it goes off by itself
+    pub fuse: f32,
+}
+
+impl Projectile {
+    // launched from the middle of the shooter, it can't be inside a wall there
+    pub fn new(id: u32, owner: u32, kind: ProjectileKind, (x, y): (f32, f32), angle: f32) -> Self {
+        Self {
+            id,
+            owner,
+            kind,
+            x,
+            y,
+            vx: angle.cos() * kind.speed(),
+            vy: angle.sin() * kind.speed(),
+            fuse: kind.fuse(),
+        }
+    }
+
+    pub fn state(&self) -> ProjectileState {
+        ProjectileState {
+            id: self.id,
+            kind: self.kind,
+            x: self.x,
+            y: self.y,
+        }
+    }
+
+    // one tick of flight, Some(where it exploded) when it went off.
+    // targets are (id, x, y) of every player that can be hit
+    pub fn step(
+        &mut self,
+        map: &TileMap,
+        targets: &[(u32, f32, f32)],
+        dt: f32,
+    ) -> Option<(f32, f32)> {
+        let r = self.kind.radius();
+        // moves in steps no longer than its radius so a rocket can't skip over a wall corner
+        // or a player between two ticks
+        let dist = (self.vx * self.vx + self.vy * self.vy).sqrt() * dt;
+        let steps = ((dist / r).ceil() as usize).max(1);
+        let h = dt / steps as f32;
+        let touch = (RADIUS + r) * (RADIUS + r);
+        for _ in 0..steps {
+            let (nx, ny) = (self.x + self.vx * h, self.y + self.vy * h);
+            if can_stand(map, nx, ny, r) {
+                (self.x, self.y) = (nx, ny);
+            } else if self.kind.bounces() {
+                // flip whichever way is blocked, both of them in a corner
+                let x_free = can_stand(map, nx, self.y, r);
+                let y_free = can_stand(map, self.x, ny, r);
+                if !x_free || x_free == y_free {
+                    self.vx = -self.vx * BOUNCE;
+                }
+                if !y_free || x_free == y_free {
+                    self.vy = -self.vy * BOUNCE;
+                }
+            } else {
+                return Some((self.x, self.y));
+            }
+            let hit = targets.iter().any(|&(id, tx, ty)| {
+                id != self.owner && (tx - self.x).powi(2) + (ty - self.y).powi(2) < touch
+            });
+            if hit {
+                return Some((self.x, self.y));
+            }
+        }
+
+        self.fuse -= dt;
+        if self.fuse <= 0.0 {
+            return Some((self.x, self.y));
+        }
+        let keep = (1.0 - self.kind.drag() * dt).max(0.0);
+        self.vx *= keep;
+        self.vy *= keep;
+        None
+    }
+}
+
+// damage an explosion at (x, y) does to every target that can see it, full damage up to a
+// player's edge fading to nothing at splash_radius. walls and windows shield players
+pub fn splash(
+    map: &TileMap,
+    (x, y): (f32, f32),
+    kind: ProjectileKind,
+    targets: &[(u32, f32, f32)],
+) -> Vec<(u32, u8)> {
+    let reach = kind.splash_radius();
+    let mut out = Vec::new();
+    for &(id, tx, ty) in targets {
+        let (dx, dy) = (tx - x, ty - y);
+        let dist = (dx * dx + dy * dy).sqrt();
+        if dist >= reach + RADIUS {
+            continue;
+        }
+        if solid_distance(map, x, y, dy.atan2(dx), dist).is_some() {
+            continue;
+        }
+        let falloff = 1.0 - ((dist - RADIUS).max(0.0) / reach);
+        let damage = (kind.damage() as f32 * falloff).round() as u8;
+        if damage > 0 {
+            out.push((id, damage));
+        }
+    }
+    out
+}
+
+// one tick for everything in flight: moves them, drops the ones that went off and returns
+// the damage they did, a player caught by two explosions gets two hits
+pub fn update_projectiles(
+    projectiles: &mut Vec<Projectile>,
+    map: &TileMap,
+    targets: &[(u32, f32, f32)],
+    dt: f32,
+) -> Vec<Hit> {
+    let mut hits = Vec::new();
+    projectiles.retain_mut(|p| match p.step(map, targets, dt) {
+        Some(at) => {
+            let splashed = splash(map, at, p.kind, targets);
+            hits.extend(splashed.into_iter().map(|(target, damage)| Hit {
+                attacker: p.owner,
+                target,
+                damage,
+                weapon: p.kind.weapon(),
+            }));
+            false
+        }
+        None => true,
+    });
+    hits
+}
+
+// a shot that went off: hitscan weapons return who they hit right away, the others launch a
+// projectile (unless MAX_PROJECTILES are already flying) that hurts in a later tick
+pub fn fire(
+    weapon: Weapon,
+    map: &TileMap,
+    (owner, x, y, angle): (u32, f32, f32, f32),
+    targets: &[(u32, f32, f32)],
+    projectiles: &mut Vec<Projectile>,
+    next_id: &mut u32,
+) -> Option<Hit> {
+    match weapon.attack() {
+        Attack::Hitscan { range } => {
+            let others: Vec<_> = targets.iter().filter(|t| t.0 != owner).copied().collect();
+            let shot = hitscan(map, (x, y), angle, range, &others);
+            shot.hit.map(|target| Hit {
+                attacker: owner,
+                target,
+                damage: weapon.damage(),
+                weapon,
+            })
+        }
+        Attack::Projectile(kind) => {
+            if projectiles.len() < MAX_PROJECTILES {
+                projectiles.push(Projectile::new(*next_id, owner, kind, (x, y), angle));
+                *next_id = next_id.wrapping_add(1);
+            }
+            None
+        }
+    }
+}
+
+// the weapon someone holds and how long until it can fire again
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub struct Trigger {
+    pub weapon: Weapon,
+    pub cooldown: f32,
+}
+
+impl Trigger {
+    // call every tick, true when a shot goes off. holding the button fires at exactly the
+    // weapon's rate, leftover time carries over to the next shot
+    pub fn update(&mut self, firing: bool, dt: f32) -> bool {
+        self.cooldown -= dt;
+        if firing && self.cooldown <= 0.0 {
+            self.cooldown += self.weapon.cooldown();
+            return true;
+        }
+        self.cooldown = self.cooldown.max(0.0);
+        false
+    }
+}
+
+// damage someone took, who gets the kill if it was too much
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Hit {
+    pub attacker: u32,
+    pub target: u32,
+    pub damage: u8,
+    pub weapon: Weapon,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Shot {
+    // id of the player hit, None when it ended in a wall or ran out of range
+    pub hit: Option<u32>,
+    // where the bullet stopped
+    pub x: f32,
+    pub y: f32,
+}
+
+// a shot from (x, y) along angle. targets are (id, x, y) of everyone who can be hit,
+// leave the shooter out
+pub fn hitscan(
+    map: &TileMap,
+    (x, y): (f32, f32),
+    angle: f32,
+    range: f32,
+    targets: &[(u32, f32, f32)],
+) -> Shot {
+    let wall = solid_distance(map, x, y, angle, range).unwrap_or(range);
+    let (dir_x, dir_y) = (angle.cos(), angle.sin());
+    let mut best: Option<(f32, u32)> = None;
+    for &(id, tx, ty) in targets {
+        let Some(t) = ray_circle((x, y), (dir_x, dir_y), (tx, ty), RADIUS) else {
+            continue;
+        };
+        // ties go to the lower id so the result doesn't depend on the order of targets
+        if t <= wall && best.is_none_or(|(bt, bid)| t < bt || (t == bt && id < bid)) {
+            best = Some((t, id));
+        }
+    }
+    let dist = best.map_or(wall, |(t, _)| t);
+    Shot {
+        hit: best.map(|(_, id)| id),
+        x: x + dir_x * dist,
+        y: y + dir_y * dist,
+    }
+}
+
+// distance along a unit direction to where the ray enters the circle, None if it misses or
+// the circle is behind. starting inside the circle is a hit at 0
+pub fn ray_circle(
+    (x, y): (f32, f32),
+    (dir_x, dir_y): (f32, f32),
+    (cx, cy): (f32, f32),
+    radius: f32,
+) -> Option<f32> {
+    let (ox, oy) = (cx - x, cy - y);
+    // closest approach along the ray and how far the center is off it there
+    let along = ox * dir_x + oy * dir_y;
+    let off2 = ox * ox + oy * oy - along * along;
+    if off2 > radius * radius {
+        return None;
+    }
+    let half = (radius * radius - off2).sqrt();
+    if along - half >= 0.0 {
+        Some(along - half)
+    } else if along + half >= 0.0 {
+        Some(0.0)
+    } else {
+        None
+    }
+}
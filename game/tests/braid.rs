@@ -0,0 +1,91 @@
+use game::braid::{MazeMetrics, braid};
+use game::config::MapConfig;
+use game::map::generate_maze;
+use game::mazegen::MazeAlgorithm;
+use game::rooms::RoomConfig;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+#[test]
+fn perfect_maze_has_no_loops() {
+    let m = MazeMetrics::measure(&generate_maze(20, 15, 5));
+    assert_eq!(m.cells, 300);
+    assert_eq!(m.loops, 0);
+    assert!(m.dead_ends > 0);
+    assert!(m.avg_corridor_len >= 1.0);
+}
+
+#[test]
+fn straight_corridor_metrics() {
+    // a 3x1 maze is one corridor from end to end
+    let m = MazeMetrics::measure(&generate_maze(3, 1, 0));
+    assert_eq!(m.dead_ends, 2);
+    assert_eq!(m.loops, 0);
+    assert_eq!(m.corridors, 1);
+    assert_eq!(m.avg_corridor_len, 2.0);
+}
+
+#[test]
+fn every_removed_wall_adds_a_loop() {
+    for percent in [0, 10, 50, 100] {
+        let mut maze = generate_maze(30, 20, 8);
+        let before = MazeMetrics::measure(&maze);
+        let removed = braid(&mut maze, percent, &mut ChaCha8Rng::seed_from_u64(1));
+        let after = MazeMetrics::measure(&maze);
+        assert_eq!(after.loops, removed, "braid {percent}%");
+        assert!(after.dead_ends <= before.dead_ends);
+        if percent == 0 {
+            assert_eq!(after, before);
+        }
+    }
+}
+
+#[test]
+fn full_braid_removes_every_dead_end() {
+    for algo in MazeAlgorithm::ALL {
+        let cfg = MapConfig {
+            seed: 3,
+            width: 15,
+            height: 12,
+            algorithm: algo,
+            braid: 100,
+            rooms: RoomConfig::default(),
+            file: None,
+            sky: false,
+        };
+        let m = MazeMetrics::measure(&cfg.build_maze());
+        assert_eq!(m.dead_ends, 0, "{algo} still has dead ends");
+        assert!(m.loops > 0);
+    }
+}
+
+#[test]
+fn partial_braid_removes_about_that_share() {
+    let mut maze = generate_maze(40, 40, 21);
+    let before = MazeMetrics::measure(&maze).dead_ends;
+    braid(&mut maze, 50, &mut ChaCha8Rng::seed_from_u64(2));
+    let after = MazeMetrics::measure(&maze).dead_ends;
+    // pairs of dead ends can go with one wall so it may overshoot a little
+    assert!(after <= before / 2 + 1, "{before} -> {after}");
+    assert!(after >= before / 2 - 2, "{before} -> {after}");
+}
+
+#[test]
+fn braided_config_is_reproducible() {
+    let cfg = MapConfig {
+        seed: 99,
+        width: 20,
+        height: 15,
+        algorithm: MazeAlgorithm::Prim,
+        braid: 40,
+        rooms: RoomConfig::default(),
+        file: None,
+        sky: false,
+    };
+    assert_eq!(cfg.build_grid(), cfg.clone().build_grid());
+    let perfect = MapConfig {
+        braid: 0,
+        ..cfg.clone()
+    };
+    assert_ne!(cfg.build_grid(), perfect.build_grid());
+}
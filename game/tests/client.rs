@@ -0,0 +1,215 @@
+// a Connection against a real server over 127.0.0.1, the server ticked by hand
+
+mod common;
+
+use common::corridor_server;
+use game::client::{Connection, welcome_level};
+use game::config::MapConfig;
+use game::mechanics::PlayerInput;
+use game::protocol::ServerMessage;
+use game::server::Server;
+use std::thread;
+use std::time::Duration;
+
+const DT: f32 = 1.0 / 30.0;
+
+fn maze_server() -> Server {
+    let map = MapConfig {
+        seed: 11,
+        width: 6,
+        height: 5,
+        ..MapConfig::default()
+    };
+    Server::bind("127.0.0.1:0", map).unwrap()
+}
+
+// connect waits for the welcome, so it runs on its own thread while this one ticks
+fn join(server: &mut Server) -> Connection {
+    let addr = server.local_addr().unwrap();
+    let joining = thread::spawn(move || Connection::connect(addr, &MapConfig::default()));
+    while !joining.is_finished() {
+        server.tick(DT).unwrap();
+        thread::sleep(Duration::from_millis(5));
+    }
+    joining.join().unwrap().unwrap()
+}
+
+// one client frame and one server tick
+fn frame(server: &mut Server, conn: &mut Connection, input: PlayerInput) {
+    conn.update(input).unwrap();
+    server.tick(DT).unwrap();
+}
+
+fn still(tick: u32) -> PlayerInput {
+    PlayerInput {
+        tick,
+        dt: DT,
+        ..PlayerInput::default()
+    }
+}
+
+fn walk(tick: u32) -> PlayerInput {
+    PlayerInput {
+        forward: 1.0,
+        ..still(tick)
+    }
+}
+
+#[test]
+fn the_level_comes_from_the_welcome() {
+    let mut server = maze_server();
+    let conn = join(&mut server);
+    assert_eq!(conn.map.seed, 11);
+    assert_eq!(conn.level.tiles, server.map().build_level().unwrap().tiles);
+    assert_eq!(server.player_count(), 1);
+    drop(conn);
+    server.tick(DT).unwrap();
+    assert_eq!(server.player_count(), 0, "leaving on drop");
+}
+
+#[test]
+fn the_server_has_the_last_word() {
+    let mut server = corridor_server();
+    let mut conn = join(&mut server);
+    frame(&mut server, &mut conn, still(1));
+    frame(&mut server, &mut conn, still(2));
+    assert_eq!((conn.player.x, conn.player.y), (1.5, 1.5));
+
+    // three frames for every server tick: each moves us at once, but the server only lets
+    // us go as fast as real time allows and every snapshot pulls us back
+    let mut tick = 2;
+    for _ in 0..4 {
+        for _ in 0..3 {
+            tick += 1;
+            conn.update(walk(tick)).unwrap();
+        }
+        server.tick(DT).unwrap();
+    }
+    frame(&mut server, &mut conn, still(tick + 1));
+    // 12 frames of walking would be 1.2 tiles
+    assert!(conn.player.x < 2.0, "{}", conn.player.x);
+    // drawn sliding back rather than jumping
+    assert!(conn.view().x > conn.player.x);
+    for tick in tick + 2..tick + 60 {
+        frame(&mut server, &mut conn, still(tick));
+    }
+    assert!((conn.view().x - conn.player.x).abs() < 1e-3);
+}
+
+#[test]
+fn everyone_else_is_drawn_a_little_in_the_past() {
+    let mut server = corridor_server();
+    let mut watcher = join(&mut server);
+    let mut walker = join(&mut server);
+    for tick in 1..=5 {
+        walker.update(still(tick)).unwrap();
+        frame(&mut server, &mut watcher, still(tick));
+    }
+    let seen = watcher.others();
+    assert_eq!(seen.len(), 1);
+    assert_eq!(seen[0].id, walker.id);
+    let start = walker.player.x;
+    assert!((seen[0].x - start).abs() < 1e-3);
+
+    // spawned at the far end facing the wall, so backwards along the corridor
+    for tick in 6..=15 {
+        walker
+            .update(PlayerInput {
+                forward: -1.0,
+                ..still(tick)
+            })
+            .unwrap();
+        frame(&mut server, &mut watcher, still(tick));
+    }
+    walker.update(still(16)).unwrap();
+    let seen = watcher.others()[0].x;
+    // a few ticks of walking behind, and moving on between snapshots
+    let behind = seen - walker.player.x;
+    assert!(behind > 0.1 && behind < 0.5, "{behind}");
+    frame(&mut server, &mut watcher, still(16));
+    let later = watcher.others()[0].x;
+    assert!(later < seen && later > walker.player.x, "{later}");
+}
+
+#[test]
+fn acked_snapshots_come_back_as_deltas() {
+    let mut server = maze_server();
+    let mut conn = join(&mut server);
+    for tick in 1..=5 {
+        frame(&mut server, &mut conn, still(tick));
+    }
+    let before = server.bandwidth()[0].1;
+    for tick in 6..=35 {
+        frame(&mut server, &mut conn, still(tick));
+    }
+    let sent = server.bandwidth()[0].1.since(&before);
+    // every one of them against a snapshot the client still had
+    assert_eq!((sent.snapshots, sent.deltas), (30, 30));
+    assert!(sent.snapshot_bytes * 2 < sent.full_bytes, "{sent:?}");
+}
+
+#[test]
+fn names_chat_and_joins_go_over_the_reliable_channels() {
+    let mut server = corridor_server();
+    let mut ann = join(&mut server);
+    let mut bob = join(&mut server);
+    ann.set_name("ann").unwrap();
+    ann.say("hello").unwrap();
+    let (mut heard_by_ann, mut heard_by_bob) = (Vec::new(), Vec::new());
+    for tick in 1..=10 {
+        heard_by_bob.extend(bob.update(still(tick)).unwrap());
+        heard_by_ann.extend(ann.update(still(tick)).unwrap());
+        server.tick(DT).unwrap();
+    }
+    let chat = ServerMessage::Chat {
+        from: ann.id,
+        text: "hello".to_string(),
+    };
+    assert_eq!(heard_by_bob, vec![chat.clone()]);
+    let joined = ServerMessage::Joined {
+        id: bob.id,
+        name: format!("player {}", bob.id),
+    };
+    assert_eq!(heard_by_ann, vec![joined, chat]);
+    assert_eq!(bob.others()[0].name, "ann");
+}
+
+#[test]
+fn a_map_change_rebuilds_the_level() {
+    let mut server = maze_server();
+    let mut conn = join(&mut server);
+    let next = MapConfig {
+        seed: 12,
+        ..server.map().clone()
+    };
+    server.change_map(next.clone(), next.build_level().unwrap());
+    let mut heard = Vec::new();
+    for tick in 1..=3 {
+        server.tick(DT).unwrap();
+        heard.extend(conn.update(still(tick)).unwrap());
+    }
+    assert!(matches!(
+        heard[..],
+        [ServerMessage::Welcome { seed: 12, .. }]
+    ));
+    assert_eq!(conn.map.seed, 12);
+    assert_eq!(conn.level.tiles, next.build_level().unwrap().tiles);
+}
+
+#[test]
+fn a_hand_made_map_needs_the_same_file() {
+    let welcome = ServerMessage::Welcome {
+        id: 1,
+        seed: 1,
+        width: 3,
+        height: 3,
+        algorithm: Default::default(),
+        braid: 0,
+        rooms: Default::default(),
+        map_name: "Corridors".to_string(),
+        map_version: 3,
+        sky: false,
+    };
+    let err = welcome_level(&welcome, &MapConfig::default()).unwrap_err();
+    assert!(err.contains("\"Corridors\" v3"), "{err}");
+}
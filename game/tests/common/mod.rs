@@ -0,0 +1,38 @@
+// fixtures more than one test file needs, not every file uses all of them
+#![allow(dead_code)]
+
+use game::config::MapConfig;
+use game::server::Server;
+use game::tilemap::TileMap;
+
+// open 10x10 room, walls all around
+pub fn room() -> TileMap {
+    let grid: Vec<Vec<u8>> = (0..10)
+        .map(|y| {
+            (0..10)
+                .map(|x| u8::from(x == 0 || y == 0 || x == 9 || y == 9))
+                .collect()
+        })
+        .collect();
+    TileMap::from_grid(&grid)
+}
+
+// straight corridor along x, the player spawns at (1.5, 1.5) facing +x. it is the grid a
+// 3x1 maze comes out as, so a client rebuilds the same one from the welcome
+pub fn corridor() -> Vec<Vec<u8>> {
+    vec![
+        vec![1, 1, 1, 1, 1, 1, 1, 1],
+        vec![1, 0, 0, 0, 0, 0, 0, 1],
+        vec![1, 1, 1, 1, 1, 1, 1, 1],
+    ]
+}
+
+pub fn corridor_server() -> Server {
+    let map = MapConfig {
+        seed: 7,
+        width: 3,
+        height: 1,
+        ..MapConfig::default()
+    };
+    Server::with_grid("127.0.0.1:0", map, corridor()).unwrap()
+}
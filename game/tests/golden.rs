@@ -0,0 +1,241 @@
+// golden image tests: fixed scenes rendered with render_image and compared against the
+// pngs in tests/golden. a few pixels may drift (float rounding on another cpu), a broken
+// raycaster changes far more than that.
+//
+// after an intended change to the picture regenerate them with
+//   UPDATE_GOLDEN=1 cargo test --no-default-features --test golden
+// and look at the new pngs before committing them
+
+use game::config::MapConfig;
+use game::mapfile::MapFile;
+use game::render::{Actors, OtherPlayer, Player, Team, render_image};
+use game::sprites::Animation;
+use game::textures::{Texture, TextureSet};
+use game::tilemap::TileMap;
+use game::weapons::{ProjectileKind, ProjectileState};
+use std::path::{Path, PathBuf};
+
+const W: usize = 240;
+const H: usize = 180;
+// a channel off by more than this counts as a different pixel
+const CHANNEL_TOLERANCE: u8 = 16;
+// share of pixels allowed to differ
+const MAX_DIFFERENT: f32 = 0.002;
+
+const ARENA: &str = "\
+name: golden arena
+author: tests
+version: 1
+---
+###########
+#S..D.....#
+#.2...3...#
+#...=.....#
+#.~~..4.5.#
+###########
+";
+
+fn manifest() -> &'static Path {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+}
+
+fn pose(x: f32, y: f32, degrees: f32) -> Player {
+    Player::at(x, y, degrees.to_radians())
+}
+
+fn seeded_maze() -> TileMap {
+    let cfg = MapConfig {
+        seed: 42,
+        width: 8,
+        height: 6,
+        ..MapConfig::default()
+    };
+    cfg.build_level().unwrap().tiles
+}
+
+fn arena() -> TileMap {
+    MapFile::parse(ARENA).unwrap().tiles
+}
+
+fn differs(a: u32, b: u32) -> bool {
+    a.to_be_bytes()
+        .iter()
+        .zip(b.to_be_bytes())
+        .any(|(&x, y)| x.abs_diff(y) > CHANNEL_TOLERANCE)
+}
+
+fn count_different(a: &Texture, b: &Texture) -> usize {
+    a.pixels
+        .iter()
+        .zip(&b.pixels)
+        .filter(|&(&x, &y)| differs(x, y))
+        .count()
+}
+
+fn check(name: &str, map: &TileMap, p: Player, actors: Actors) {
+    let textures = TextureSet::load_dir(manifest().join("textures")).unwrap();
+    let actual = render_image(W, H, map, &textures, &p, actors);
+    let golden = manifest().join("tests/golden").join(format!("{name}.png"));
+
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        actual.save_png(&golden).unwrap();
+        return;
+    }
+    let expected = Texture::load(&golden)
+        .unwrap_or_else(|e| panic!("{e}, run with UPDATE_GOLDEN=1 to create it"));
+    assert_eq!(
+        (expected.width, expected.height),
+        (W, H),
+        "{name}: golden image has a different size"
+    );
+
+    // differing pixels in magenta over a darkened copy of the frame
+    let mut diff = actual.clone();
+    for (px, &e) in diff.pixels.iter_mut().zip(&expected.pixels) {
+        *px = if differs(*px, e) {
+            0xff00ffff
+        } else {
+            *px & 0x3f3f3fff
+        };
+    }
+    let different = count_different(&actual, &expected);
+    let share = different as f32 / (W * H) as f32;
+    if share > MAX_DIFFERENT {
+        let out = PathBuf::from(env!("CARGO_TARGET_TMPDIR"));
+        let (a, d) = (
+            out.join(format!("{name}.actual.png")),
+            out.join(format!("{name}.diff.png")),
+        );
+        actual.save_png(&a).unwrap();
+        diff.save_png(&d).unwrap();
+        panic!(
+            "{name}: {different} pixels ({:.2}%) differ from {}, see {} and {}",
+            share * 100.0,
+            golden.display(),
+            a.display(),
+            d.display()
+        );
+    }
+}
+
+#[test]
+fn maze_corridor() {
+    check(
+        "maze_corridor",
+        &seeded_maze(),
+        pose(1.5, 1.5, 90.0),
+        Actors::default(),
+    );
+}
+
+#[test]
+fn maze_corridor_with_player() {
+    let others = [OtherPlayer::at(1.5, 3.5)];
+    check(
+        "maze_corridor_with_player",
+        &seeded_maze(),
+        pose(1.5, 1.5, 90.0),
+        Actors::players(&others),
+    );
+}
+
+#[test]
+fn arena_door_window_and_textures() {
+    // looking east through the window at (4, 3), brick and moss pillars on the left
+    check("arena", &arena(), pose(1.5, 3.5, 0.0), Actors::default());
+}
+
+#[test]
+fn arena_players_in_front_and_behind_the_door() {
+    // one player in the open, one hidden behind the door at (4, 1)
+    let others = [OtherPlayer::at(2.5, 1.85), OtherPlayer::at(6.5, 1.5)];
+    check(
+        "arena_players",
+        &arena(),
+        pose(1.5, 1.5, 0.0),
+        Actors::players(&others),
+    );
+}
+
+#[test]
+fn arena_teams_facing_and_the_dead() {
+    let others = [
+        // red looking at us, blue walking away to the east, one dead on the lava
+        OtherPlayer {
+            team: Team::Red,
+            angle: std::f32::consts::PI,
+            health: 60,
+            ..OtherPlayer::at(3.0, 3.2)
+        },
+        OtherPlayer {
+            team: Team::Blue,
+            animation: Animation::Walk,
+            anim_time: 0.2,
+            ..OtherPlayer::at(8.5, 3.5)
+        },
+        OtherPlayer {
+            alive: false,
+            ..OtherPlayer::at(3.5, 4.5)
+        },
+    ];
+    check(
+        "arena_teams",
+        &arena(),
+        pose(1.5, 3.5, 0.0),
+        Actors::players(&others),
+    );
+}
+
+#[test]
+fn arena_rocket_and_grenade_in_flight() {
+    let projectiles = [
+        ProjectileState {
+            id: 1,
+            kind: ProjectileKind::Rocket,
+            x: 2.8,
+            y: 3.3,
+        },
+        ProjectileState {
+            id: 2,
+            kind: ProjectileKind::Grenade,
+            x: 2.5,
+            y: 3.8,
+        },
+    ];
+    let actors = Actors {
+        others: &[],
+        projectiles: &projectiles,
+    };
+    check("arena_projectiles", &arena(), pose(1.5, 3.5, 0.0), actors);
+}
+
+#[test]
+fn arena_under_open_sky() {
+    let mut map = arena();
+    map.set_open_sky(true);
+    check("arena_sky", &map, pose(1.5, 3.5, 0.0), Actors::default());
+}
+
+#[test]
+fn a_broken_render_is_caught() {
+    let textures = TextureSet::load_dir(manifest().join("textures")).unwrap();
+    let good = render_image(
+        W,
+        H,
+        &arena(),
+        &textures,
+        &pose(1.5, 3.5, 0.0),
+        Actors::default(),
+    );
+    // turning a few degrees moves every wall edge, the tolerance must not hide that
+    let off = render_image(
+        W,
+        H,
+        &arena(),
+        &textures,
+        &pose(1.5, 3.5, 5.0),
+        Actors::default(),
+    );
+    let different = count_different(&good, &off);
+    assert!(different as f32 / (W * H) as f32 > MAX_DIFFERENT);
+}
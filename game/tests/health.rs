@@ -0,0 +1,93 @@
+use game::health::{
+    Kill, MAX_HEALTH, SPAWN_ARMOR, apply_damage, death_camera, kill_text, next_spectate, respawn,
+};
+use game::render::{OtherPlayer, Player};
+use game::weapons::Weapon;
+
+fn player(health: u8, armor: u8) -> Player {
+    Player {
+        health,
+        armor,
+        ..Player::at(2.5, 3.5, 1.0)
+    }
+}
+
+#[test]
+fn armor_takes_two_thirds_while_it_lasts() {
+    let mut p = player(100, 50);
+    assert!(!apply_damage(&mut p, 30));
+    assert_eq!((p.health, p.armor), (90, 30));
+    // only 10 armor left for the 20 it would take
+    let mut p = player(100, 10);
+    assert!(!apply_damage(&mut p, 30));
+    assert_eq!((p.health, p.armor), (80, 0));
+    let mut p = player(100, 0);
+    apply_damage(&mut p, 30);
+    assert_eq!(p.health, 70);
+}
+
+#[test]
+fn only_the_killing_hit_counts() {
+    let mut p = player(20, 0);
+    assert!(apply_damage(&mut p, 200));
+    assert_eq!(p.health, 0);
+    // more damage on the body is not another kill
+    assert!(!apply_damage(&mut p, 50));
+    assert_eq!(p.health, 0);
+}
+
+#[test]
+fn respawn_heals_and_moves() {
+    let mut p = player(0, 0);
+    respawn(&mut p, (7.5, 1.5));
+    assert_eq!((p.x, p.y), (7.5, 1.5));
+    assert_eq!((p.health, p.armor), (MAX_HEALTH, SPAWN_ARMOR));
+}
+
+#[test]
+fn killfeed_lines() {
+    let kill = Kill {
+        killer: 1,
+        victim: 2,
+        weapon: Weapon::Rocket,
+    };
+    assert_eq!(kill_text("alice", "bob", &kill), "alice [rocket] bob");
+    let own = Kill { victim: 1, ..kill };
+    assert_eq!(kill_text("alice", "alice", &own), "alice [rocket]");
+}
+
+fn other(id: u32, x: f32, alive: bool) -> OtherPlayer {
+    OtherPlayer {
+        id,
+        alive,
+        angle: 2.0,
+        ..OtherPlayer::at(x, 1.5)
+    }
+}
+
+#[test]
+fn spectating_cycles_through_the_living() {
+    let others = [
+        other(5, 1.5, true),
+        other(2, 2.5, false),
+        other(3, 3.5, true),
+    ];
+    assert_eq!(next_spectate(&others, None), Some(3));
+    assert_eq!(next_spectate(&others, Some(3)), Some(5));
+    assert_eq!(next_spectate(&others, Some(5)), Some(3));
+    // the dead one is skipped even when it was being watched
+    assert_eq!(next_spectate(&others, Some(2)), Some(3));
+    assert_eq!(next_spectate(&[other(2, 2.5, false)], Some(2)), None);
+}
+
+#[test]
+fn the_death_camera_stays_or_follows() {
+    let dead = player(0, 0);
+    let others = [other(3, 6.5, true), other(4, 4.5, false)];
+    assert_eq!(death_camera(&dead, &others, None), dead);
+    let cam = death_camera(&dead, &others, Some(3));
+    assert_eq!((cam.x, cam.y, cam.angle), (6.5, 1.5, 2.0));
+    assert_eq!(cam.fov, dead.fov);
+    // watching someone who died too goes back to our own body
+    assert_eq!(death_camera(&dead, &others, Some(4)), dead);
+}
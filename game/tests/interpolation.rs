@@ -0,0 +1,175 @@
+use game::interpolation::{INTERP_DELAY, Interpolator, MAX_EXTRAPOLATION, lerp_angle};
+use game::protocol::PlayerState;
+use game::render::Team;
+use game::server::TICK_RATE;
+use game::sprites::Animation;
+use std::f32::consts::PI;
+
+const TICK: f32 = 1.0 / TICK_RATE as f32;
+
+fn state(id: u32, x: f32, angle: f32) -> PlayerState {
+    PlayerState {
+        id,
+        name: format!("player {id}"),
+        team: Team::None,
+        x,
+        y: 1.5,
+        angle,
+        health: 100,
+        armor: 0,
+        alive: true,
+        animation: Animation::Walk,
+        ack: 0,
+    }
+}
+
+fn close(a: f32, b: f32) -> bool {
+    (a - b).abs() < 1e-3
+}
+
+// the next snapshot a tick after the last one, the way they come in from a server
+fn feed(interp: &mut Interpolator, tick: u32, players: Vec<PlayerState>) {
+    if interp.clock().is_some() {
+        interp.advance(TICK);
+    }
+    interp.push(tick, players);
+}
+
+// player 2 walking along x, one tile per second, ticks 0..=n
+fn walking(n: u32) -> Interpolator {
+    let mut interp = Interpolator::new();
+    for tick in 0..=n {
+        let players = vec![state(1, 0.0, 0.0), state(2, tick as f32 * TICK, 0.0)];
+        feed(&mut interp, tick, players);
+    }
+    interp
+}
+
+fn wrap(angle: f32) -> f32 {
+    (angle + PI).rem_euclid(2.0 * PI) - PI
+}
+
+#[test]
+fn angles_take_the_short_way_round() {
+    assert!(close(lerp_angle(0.0, 1.0, 0.5), 0.5));
+    // across the wrap the middle is at PI, not at 0
+    let mid = lerp_angle(3.0, -3.0, 0.5);
+    assert!(close(mid.abs(), PI), "{mid}");
+    let quarter = lerp_angle(3.0, -3.0, 0.25);
+    assert!(close(quarter, 3.0 + (2.0 * PI - 6.0) * 0.25), "{quarter}");
+    // and the other way
+    let back = lerp_angle(-3.0, 3.0, 0.25);
+    assert!(close(back, -3.0 - (2.0 * PI - 6.0) * 0.25), "{back}");
+    // always back in -PI..PI
+    for s in [0.0, 0.3, 0.7, 1.0, 1.5] {
+        let a = lerp_angle(3.1, -3.1, s);
+        assert!((-PI..=PI).contains(&a), "{a}");
+    }
+}
+
+#[test]
+fn others_are_drawn_in_the_past_between_snapshots() {
+    let mut interp = walking(10);
+    let clock = interp.clock().unwrap();
+    assert!((clock - (10.0 * TICK as f64 - INTERP_DELAY)).abs() < 1e-4);
+    let others = interp.players(1);
+    // we are not in the list
+    assert_eq!(others.len(), 1);
+    assert_eq!((others[0].id, others[0].name.as_str()), (2, "player 2"));
+    assert!(close(others[0].x, clock as f32), "{}", others[0].x);
+
+    // between two snapshots lands between their positions
+    interp.advance(TICK / 2.0);
+    let clock = interp.clock().unwrap() as f32;
+    let x = interp.players(1)[0].x;
+    assert!(close(x, clock), "{x}");
+    assert!(x < 10.0 * TICK - 0.05);
+}
+
+#[test]
+fn facing_is_interpolated_across_the_wrap() {
+    // turning left through PI, 0.1 radians a tick
+    let mut interp = Interpolator::new();
+    for tick in 0..=5 {
+        let angle = wrap(3.0 + 0.1 * tick as f32);
+        feed(&mut interp, tick, vec![state(2, 1.0, angle)]);
+    }
+    for _ in 0..4 {
+        interp.advance(TICK / 4.0);
+        let at = interp.clock().unwrap() as f32 / TICK;
+        let angle = interp.players(1)[0].angle;
+        assert!(close(angle, wrap(3.0 + 0.1 * at)), "{angle} at tick {at}");
+        // never anywhere near 0 on the way
+        assert!(angle.abs() > 2.9);
+    }
+}
+
+#[test]
+fn late_and_repeated_snapshots_fit_in() {
+    let mut interp = Interpolator::new();
+    interp.push(0, vec![state(2, 0.0, 0.0)]);
+    interp.advance(4.0 * TICK);
+    interp.push(4, vec![state(2, 4.0, 0.0)]);
+    // tick 2 overtaken by tick 4, and a copy of tick 4 with another position
+    interp.push(2, vec![state(2, 0.5, 0.0)]);
+    interp.push(4, vec![state(2, 9.0, 0.0)]);
+    let clock = interp.clock().unwrap() as f32;
+    assert!(close(clock, TICK));
+    // blended between tick 0 and the late tick 2
+    let x = interp.players(1)[0].x;
+    assert!(close(x, 0.5 * clock / (2.0 * TICK)), "{x}");
+}
+
+#[test]
+fn extrapolation_stops_after_a_while() {
+    let mut interp = walking(10);
+    // the server goes quiet, a little past the newest snapshot they keep walking
+    interp.advance(INTERP_DELAY as f32 + 0.1);
+    let newest = 10.0 * TICK;
+    let x = interp.players(1)[0].x;
+    assert!(close(x, newest + 0.1), "{x}");
+    // then stop at MAX_EXTRAPOLATION past it however long the silence
+    for _ in 0..10 {
+        interp.advance(0.1);
+    }
+    let x = interp.players(1)[0].x;
+    assert!(close(x, newest + MAX_EXTRAPOLATION as f32), "{x}");
+    // and pick up again once snapshots are back
+    interp.push(100, vec![state(2, 5.0, 0.0)]);
+    assert!((interp.clock().unwrap() - (100.0 * TICK as f64 - INTERP_DELAY)).abs() < 1e-4);
+}
+
+#[test]
+fn respawns_and_deaths_are_not_smeared() {
+    let mut interp = Interpolator::new();
+    let mut dead = state(2, 1.0, 0.0);
+    dead.alive = false;
+    interp.push(0, vec![state(2, 1.0, 0.0)]);
+    interp.advance(3.0 * TICK);
+    interp.push(3, vec![dead]);
+    assert!(interp.players(1)[0].alive);
+    interp.advance(3.0 * TICK);
+    interp.push(6, vec![state(2, 8.0, 0.0)]);
+    assert!(!interp.players(1)[0].alive);
+    for _ in 0..6 {
+        interp.advance(TICK / 2.0);
+        let x = interp.players(1)[0].x;
+        // either where they died or where they came back, never in between
+        assert!(x == 1.0 || x == 8.0, "{x}");
+    }
+}
+
+#[test]
+fn leavers_stay_where_last_seen() {
+    let mut interp = Interpolator::new();
+    interp.push(0, vec![state(2, 1.0, 0.0), state(3, 2.0, 0.0)]);
+    interp.advance(3.0 * TICK);
+    interp.push(3, vec![state(3, 2.5, 0.0)]);
+    interp.advance(TICK);
+    let others = interp.players(1);
+    assert_eq!(others.len(), 2);
+    assert_eq!(others[0].x, 1.0);
+    // gone once the clock passes the snapshot without them
+    interp.advance(3.0 * TICK);
+    assert_eq!(interp.players(1).len(), 1);
+}
@@ -0,0 +1,143 @@
+use game::config::MapConfig;
+use game::mapfile::{MapFile, Pickup, PickupKind};
+use game::spawn::auto_spawns;
+use game::tilemap::{Tile, TileMap};
+
+const ARENA: &str = "\
+# a small test arena
+name: Corridors
+author: someone
+version: 3
+---
+#########
+#S..h..S#
+#.##2##D#
+#~=.a..w#
+#########
+";
+
+#[test]
+fn parses_header_grid_spawns_and_pickups() {
+    let map = MapFile::parse(ARENA).unwrap();
+    assert_eq!(map.name, "Corridors");
+    assert_eq!(map.author, "someone");
+    assert_eq!(map.version, 3);
+    assert_eq!((map.tiles.width(), map.tiles.height()), (9, 5));
+    let row: Vec<Tile> = (0..9).map(|x| map.tiles.get(x, 2).unwrap()).collect();
+    let (w, e) = (Tile::Wall(1), Tile::Empty);
+    assert_eq!(row, vec![w, e, w, w, Tile::Wall(2), w, w, Tile::Door, w]);
+    assert_eq!(map.tiles.get(1, 3), Some(Tile::Hazard));
+    assert_eq!(map.tiles.get(2, 3), Some(Tile::Window));
+    assert_eq!(map.spawns, vec![(1, 1), (7, 1)]);
+    assert_eq!(
+        map.pickups,
+        vec![
+            Pickup {
+                x: 4,
+                y: 1,
+                kind: PickupKind::Health
+            },
+            Pickup {
+                x: 4,
+                y: 3,
+                kind: PickupKind::Armor
+            },
+            Pickup {
+                x: 7,
+                y: 3,
+                kind: PickupKind::Weapon
+            },
+        ]
+    );
+    assert_eq!(map.tiles.get(1, 1), Some(Tile::Spawn));
+    assert_eq!(map.tiles.get(4, 1), Some(Tile::Empty));
+}
+
+#[test]
+fn text_round_trips() {
+    let map = MapFile::parse(ARENA).unwrap();
+    assert_eq!(MapFile::parse(&map.to_text()).unwrap(), map);
+}
+
+#[test]
+fn exported_generated_maze_loads_back() {
+    let cfg = MapConfig {
+        seed: 5,
+        width: 12,
+        height: 9,
+        ..MapConfig::default()
+    };
+    let level = cfg.build_level().unwrap();
+    let mut expected = TileMap::from_grid(&cfg.build_grid());
+    let spawns = auto_spawns(&expected);
+    for &(x, y) in &spawns {
+        *expected.get_mut(x, y).unwrap() = Tile::Spawn;
+    }
+    assert_eq!(level.tiles, expected);
+    assert_eq!(level.spawns, spawns);
+
+    let path = std::env::temp_dir().join(format!("mapfile-test-{}.map", std::process::id()));
+    level.save(&path).unwrap();
+    let loaded = MapFile::load(&path);
+    let from_flag =
+        MapConfig::take_from_args(&mut vec!["--map".to_string(), path.display().to_string()])
+            .map(|cfg| cfg.build_level());
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(loaded.unwrap(), level);
+    assert_eq!(from_flag.unwrap().unwrap(), level);
+}
+
+#[test]
+fn sky_header_opens_the_ceiling() {
+    assert!(!MapFile::parse(ARENA).unwrap().tiles.open_sky());
+    let map = MapFile::parse(&ARENA.replace("version: 3", "version: 3\nsky: yes")).unwrap();
+    assert!(map.tiles.open_sky());
+    assert!(map.to_text().contains("sky: yes"));
+    assert_eq!(MapFile::parse(&map.to_text()).unwrap(), map);
+
+    let cfg = MapConfig::take_from_args(&mut vec!["--sky".to_string()]).unwrap();
+    assert!(cfg.build_level().unwrap().tiles.open_sky());
+}
+
+fn error_at(text: &str) -> (usize, usize) {
+    let e = MapFile::parse(text).unwrap_err();
+    (e.line, e.column)
+}
+
+#[test]
+fn errors_point_at_line_and_column() {
+    let header = "name: x\nauthor: y\nversion: 1\n---\n";
+    // unknown tile
+    assert_eq!(error_at(&format!("{header}####\n#.?#\n####\n")), (6, 3));
+    // ragged row
+    assert_eq!(error_at(&format!("{header}####\n#..#\n###\n")), (7, 4));
+    // hole in the border
+    assert_eq!(error_at(&format!("{header}####\n...#\n####\n")), (6, 1));
+    // a window in the border, rays would go through it and off the map
+    assert_eq!(error_at(&format!("{header}####\n#..=\n####\n")), (6, 4));
+    // too small
+    assert_eq!(error_at(&format!("{header}##\n##\n")), (5, 1));
+    // bad version
+    assert_eq!(error_at("name: x\nauthor: y\nversion: one\n---\n"), (3, 10));
+    // missing header field, reported on the --- line
+    assert_eq!(
+        error_at("name: x\nversion: 1\n---\n###\n#.#\n###\n"),
+        (3, 1)
+    );
+    // no --- at all
+    assert_eq!(error_at("name: x\nauthor: y\n"), (2, 1));
+    // bad sky
+    assert_eq!(error_at("sky: maybe\n---\n"), (1, 6));
+    // unknown key
+    assert_eq!(error_at("size: 3\n---\n"), (1, 1));
+    // columns count chars, a no-break space is two bytes but one column
+    assert_eq!(error_at("version:\u{a0}one\n---\n"), (1, 10));
+    assert_eq!(error_at("\u{a0}size: 3\n---\n"), (1, 2));
+}
+
+#[test]
+fn error_message_has_the_position() {
+    let e = MapFile::parse("name: x\nauthor: y\nversion: 1\n---\n###\n#x#\n###\n").unwrap_err();
+    assert_eq!(e.to_string(), "6:2: unknown tile 'x'");
+}
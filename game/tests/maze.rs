@@ -0,0 +1,176 @@
+// maze generation must be reproducible from the seed alone, that is what the server sends
+
+use game::config::MapConfig;
+use game::map::{generate_maze, generate_maze_using, maze_to_grid};
+use game::mazegen::MazeAlgorithm;
+use game::rooms::RoomConfig;
+
+#[test]
+fn same_seed_same_grid() {
+    for seed in [0, 1, 42, u64::MAX] {
+        let a = maze_to_grid(&generate_maze(20, 15, seed));
+        let b = maze_to_grid(&generate_maze(20, 15, seed));
+        assert_eq!(a, b, "seed {seed} gave two different grids");
+    }
+}
+
+#[test]
+fn different_seeds_differ() {
+    let a = maze_to_grid(&generate_maze(20, 15, 1));
+    let b = maze_to_grid(&generate_maze(20, 15, 2));
+    assert_ne!(a, b);
+}
+
+#[test]
+fn config_builds_the_seeded_grid() {
+    let cfg = MapConfig {
+        seed: 1234,
+        width: 8,
+        height: 6,
+        algorithm: MazeAlgorithm::Backtracker,
+        braid: 0,
+        rooms: RoomConfig::default(),
+        file: None,
+        sky: false,
+    };
+    assert_eq!(cfg.build_grid(), maze_to_grid(&generate_maze(8, 6, 1234)));
+}
+
+#[test]
+fn seed_flag_is_parsed() {
+    let mut args: Vec<String> = [
+        "--seed",
+        "99",
+        "--size",
+        "4x3",
+        "127.0.0.1:4000",
+        "--maze",
+        "wilson",
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect();
+    let cfg = MapConfig::take_from_args(&mut args).unwrap();
+    assert_eq!((cfg.seed, cfg.width, cfg.height), (99, 4, 3));
+    assert_eq!(cfg.algorithm, MazeAlgorithm::Wilson);
+    assert_eq!(args, vec!["127.0.0.1:4000".to_string()]);
+
+    let mut bad = vec!["--seed".to_string(), "abc".to_string()];
+    assert!(MapConfig::take_from_args(&mut bad).is_err());
+    let mut bad = vec!["--maze".to_string(), "labyrinth".to_string()];
+    assert!(MapConfig::take_from_args(&mut bad).is_err());
+}
+
+// pinned so a rand upgrade that changes the maze for a seed gets noticed,
+// old and new builds would no longer agree on the map
+#[test]
+fn seed_output_is_stable() {
+    let grid = maze_to_grid(&generate_maze(4, 3, 42));
+    let rows: Vec<String> = grid
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|&c| if c == 0 { '.' } else { '#' })
+                .collect()
+        })
+        .collect();
+    assert_eq!(rows, PINNED);
+}
+
+const PINNED: [&str; 7] = [
+    "#########",
+    "#.#.....#",
+    "#.###.###",
+    "#...#...#",
+    "###.###.#",
+    "#.......#",
+    "#########",
+];
+
+// a perfect maze is a spanning tree: every cell reachable and exactly cells - 1 passages
+fn assert_perfect(grid: &[Vec<u8>], w: usize, h: usize) {
+    let mut passages = 0;
+    for cy in 0..h {
+        for cx in 0..w {
+            let (gx, gy) = (2 * cx + 1, 2 * cy + 1);
+            if cx + 1 < w && grid[gy][gx + 1] == 0 {
+                passages += 1;
+            }
+            if cy + 1 < h && grid[gy + 1][gx] == 0 {
+                passages += 1;
+            }
+        }
+    }
+    assert_eq!(passages, w * h - 1, "not a tree");
+
+    let mut seen = vec![vec![false; grid[0].len()]; grid.len()];
+    let mut stack = vec![(1usize, 1usize)];
+    seen[1][1] = true;
+    let mut reached = 0;
+    while let Some((x, y)) = stack.pop() {
+        if x % 2 == 1 && y % 2 == 1 {
+            reached += 1;
+        }
+        for (nx, ny) in [(x + 1, y), (x - 1, y), (x, y + 1), (x, y - 1)] {
+            if grid[ny][nx] == 0 && !seen[ny][nx] {
+                seen[ny][nx] = true;
+                stack.push((nx, ny));
+            }
+        }
+    }
+    assert_eq!(reached, w * h, "some cells can't be reached");
+}
+
+#[test]
+fn generated_mazes_are_perfect() {
+    for (w, h, seed) in [(1, 1, 0), (20, 15, 3), (7, 40, 9), (64, 64, 5)] {
+        let grid = maze_to_grid(&generate_maze(w, h, seed));
+        assert_perfect(&grid, w, h);
+    }
+}
+
+#[test]
+fn every_algorithm_makes_a_perfect_reproducible_maze() {
+    for algo in MazeAlgorithm::ALL {
+        for (w, h, seed) in [(1, 1, 0), (2, 1, 1), (1, 5, 2), (20, 15, 3), (33, 17, 4)] {
+            let maze = generate_maze_using(algo.generator(), w, h, seed);
+            let grid = maze_to_grid(&maze);
+            assert_perfect(&grid, w, h);
+            let again = maze_to_grid(&generate_maze_using(algo.generator(), w, h, seed));
+            assert_eq!(grid, again, "{algo} is not deterministic");
+        }
+    }
+}
+
+#[test]
+fn backtracker_generator_matches_generate_maze() {
+    let a = maze_to_grid(&generate_maze(20, 15, 77));
+    let b = maze_to_grid(&generate_maze_using(
+        MazeAlgorithm::Backtracker.generator(),
+        20,
+        15,
+        77,
+    ));
+    assert_eq!(a, b);
+}
+
+#[test]
+fn algorithm_names_round_trip() {
+    for algo in MazeAlgorithm::ALL {
+        assert_eq!(algo.name().parse(), Ok(algo));
+        assert_eq!(MazeAlgorithm::from_u8(algo.to_u8()), Some(algo));
+    }
+}
+
+// used to overflow the stack at 500x500 when the generator was recursive.
+// runs on a fresh thread so it gets the default 2 MiB stack whatever the test runner uses
+#[test]
+fn huge_maze_on_default_stack() {
+    std::thread::spawn(|| {
+        let grid = maze_to_grid(&generate_maze(2000, 2000, 11));
+        assert_eq!((grid.len(), grid[0].len()), (4001, 4001));
+        assert_perfect(&grid, 2000, 2000);
+    })
+    .join()
+    .expect("2000x2000 maze failed");
+}
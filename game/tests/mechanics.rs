@@ -0,0 +1,183 @@
+// movement only goes through PlayerInput, so every direction can be checked without sdl
+
+mod common;
+
+use common::room;
+use game::mechanics::{PlayerInput, RADIUS, update_player};
+use game::render::Player;
+use std::f32::consts::{FRAC_PI_2, FRAC_PI_4, PI, TAU};
+
+// walking speed in tiles per second and turning speed in radians per second
+const SPEED: f32 = 1.5;
+const TURN: f32 = 1.0;
+
+// one second of `input` in frames of 1/30
+fn hold(p: &mut Player, input: PlayerInput) {
+    let map = room();
+    for tick in 1..=30 {
+        let frame = PlayerInput {
+            tick,
+            dt: 1.0 / 30.0,
+            ..input
+        };
+        update_player(p, &map, &frame);
+    }
+}
+
+fn close(a: f32, b: f32) -> bool {
+    (a - b).abs() < 1e-3
+}
+
+#[test]
+fn no_input_no_movement() {
+    let mut p = Player::at(5.5, 5.5, 0.3);
+    hold(&mut p, PlayerInput::default());
+    assert_eq!((p.x, p.y, p.angle), (5.5, 5.5, 0.3));
+}
+
+#[test]
+fn forward_and_back_follow_the_facing() {
+    for (angle, dx, dy) in [(0.0, 1.0, 0.0), (FRAC_PI_2, 0.0, 1.0), (PI, -1.0, 0.0)] {
+        for forward in [1.0, -1.0] {
+            let mut p = Player::at(5.5, 5.5, angle);
+            let input = PlayerInput {
+                forward,
+                ..PlayerInput::default()
+            };
+            hold(&mut p, input);
+            let (ex, ey) = (5.5 + dx * forward * SPEED, 5.5 + dy * forward * SPEED);
+            assert!(close(p.x, ex) && close(p.y, ey), "{angle} {forward}: {p:?}");
+            assert_eq!(p.angle, angle);
+        }
+    }
+}
+
+#[test]
+fn strafing_goes_sideways() {
+    // facing +x, +y is to the right on the map
+    for (strafe, ey) in [(1.0, 5.5 + SPEED), (-1.0, 5.5 - SPEED)] {
+        let mut p = Player::at(5.5, 5.5, 0.0);
+        let input = PlayerInput {
+            strafe,
+            ..PlayerInput::default()
+        };
+        hold(&mut p, input);
+        assert!(close(p.x, 5.5) && close(p.y, ey), "{strafe}: {p:?}");
+    }
+}
+
+#[test]
+fn turning_with_keys_and_mouse() {
+    for turn in [1.0, -1.0] {
+        let mut p = Player::at(5.5, 5.5, 0.0);
+        let input = PlayerInput {
+            turn,
+            ..PlayerInput::default()
+        };
+        hold(&mut p, input);
+        assert!(close(p.angle, turn * TURN));
+        assert_eq!((p.x, p.y), (5.5, 5.5));
+    }
+    // the mouse turn is radians, not scaled by the frame time
+    let mut p = Player::at(5.5, 5.5, 0.0);
+    let look = PlayerInput {
+        dt: 0.5,
+        yaw: 0.25,
+        ..PlayerInput::default()
+    };
+    update_player(&mut p, &room(), &look);
+    assert_eq!(p.angle, 0.25);
+}
+
+#[test]
+fn turning_past_half_a_turn_wraps_around() {
+    let mut p = Player::at(5.5, 5.5, 3.0);
+    let look = PlayerInput {
+        dt: 1.0 / 60.0,
+        yaw: 0.5,
+        ..PlayerInput::default()
+    };
+    update_player(&mut p, &room(), &look);
+    assert!(close(p.angle, 3.5 - TAU), "{}", p.angle);
+    // and the same the other way round
+    let mut p = Player::at(5.5, 5.5, -3.0);
+    update_player(&mut p, &room(), &PlayerInput { yaw: -0.5, ..look });
+    assert!(close(p.angle, TAU - 3.5), "{}", p.angle);
+}
+
+#[test]
+fn axes_are_clamped() {
+    let mut fast = Player::at(5.5, 5.5, 0.0);
+    let mut normal = fast;
+    hold(
+        &mut fast,
+        PlayerInput {
+            forward: 10.0,
+            turn: -10.0,
+            ..PlayerInput::default()
+        },
+    );
+    hold(
+        &mut normal,
+        PlayerInput {
+            forward: 1.0,
+            turn: -1.0,
+            ..PlayerInput::default()
+        },
+    );
+    assert_eq!(fast, normal);
+}
+
+#[test]
+fn walls_stop_a_head_on_walk() {
+    let mut p = Player::at(2.5, 5.5, PI);
+    hold(
+        &mut p,
+        PlayerInput {
+            forward: 1.0,
+            ..PlayerInput::default()
+        },
+    );
+    // the wall starts at x = 1
+    assert!(p.x >= 1.0 + RADIUS && p.x < 1.0 + RADIUS + 0.05, "{p:?}");
+    assert!(close(p.y, 5.5));
+}
+
+#[test]
+fn walking_into_a_wall_at_an_angle_slides_along_it() {
+    // heading down and to the left into the west wall
+    let mut p = Player::at(1.5, 3.5, FRAC_PI_2 + FRAC_PI_4);
+    hold(
+        &mut p,
+        PlayerInput {
+            forward: 1.0,
+            ..PlayerInput::default()
+        },
+    );
+    assert!(p.x >= 1.0 + RADIUS && p.x < 1.0 + RADIUS + 0.05, "{p:?}");
+    // the part of the move along the wall still happens
+    assert!(close(p.y, 3.5 + SPEED * FRAC_PI_4.sin()), "{p:?}");
+
+    // strafing into the north wall slides along x the same way
+    let mut p = Player::at(3.5, 1.5, 0.0);
+    hold(
+        &mut p,
+        PlayerInput {
+            forward: 1.0,
+            strafe: -1.0,
+            ..PlayerInput::default()
+        },
+    );
+    assert!(p.y >= 1.0 + RADIUS && p.y < 1.0 + RADIUS + 0.05, "{p:?}");
+    assert!(close(p.x, 3.5 + SPEED), "{p:?}");
+}
+
+#[test]
+fn fire_is_a_button() {
+    assert!(!PlayerInput::default().fire());
+    let held = PlayerInput {
+        buttons: PlayerInput::FIRE,
+        ..PlayerInput::default()
+    };
+    assert!(held.fire());
+}
@@ -0,0 +1,152 @@
+mod common;
+
+use common::room;
+use game::mechanics::{PlayerInput, update_player};
+use game::prediction::{MAX_PENDING, Prediction};
+use game::protocol::{ClientMessage, PlayerState};
+use game::render::{Player, Team};
+use game::sprites::Animation;
+
+fn player() -> Player {
+    Player::at(2.5, 2.5, 0.0)
+}
+
+// us in a snapshot, standing at (x, y) after input `ack`
+fn state(p: &Player, ack: u32) -> PlayerState {
+    PlayerState {
+        id: 1,
+        name: String::new(),
+        team: Team::None,
+        x: p.x,
+        y: p.y,
+        angle: p.angle,
+        health: p.health,
+        armor: p.armor,
+        alive: p.health > 0,
+        animation: Animation::Idle,
+        ack,
+    }
+}
+
+// one frame walking forward
+fn walk(tick: u32) -> PlayerInput {
+    PlayerInput {
+        tick,
+        dt: 0.05,
+        forward: 1.0,
+        ..PlayerInput::default()
+    }
+}
+
+#[test]
+fn inputs_move_right_away_and_wait_for_the_server() {
+    let map = room();
+    let mut p = player();
+    let mut prediction = Prediction::new();
+    let fire = PlayerInput {
+        buttons: PlayerInput::FIRE,
+        ..walk(1)
+    };
+    assert_eq!(
+        prediction.predict(&mut p, &map, fire),
+        ClientMessage::Input(fire)
+    );
+    assert!(p.x > 2.5);
+    let hitch = PlayerInput { dt: 5.0, ..walk(2) };
+    let ClientMessage::Input(sent) = prediction.predict(&mut p, &map, hitch) else {
+        panic!("expected an input");
+    };
+    // a long hitch is cut the same way the server cuts it
+    assert_eq!(sent.dt, 0.1);
+    assert_eq!(prediction.pending().len(), 2);
+}
+
+#[test]
+fn acknowledged_inputs_are_replayed_from_the_server_position() {
+    let map = room();
+    let mut p = player();
+    let mut server = player();
+    let mut prediction = Prediction::new();
+    for tick in 1..=5 {
+        prediction.predict(&mut p, &map, walk(tick));
+    }
+    // the server has seen three of them
+    for tick in 1..=3 {
+        update_player(&mut server, &map, &walk(tick));
+    }
+    let before = p;
+    prediction.reconcile(&mut p, &map, &state(&server, 3));
+    assert_eq!(prediction.pending().len(), 2);
+    assert!((p.x - before.x).abs() < 1e-5);
+    assert!(prediction.offset.0.abs() < 1e-5);
+}
+
+#[test]
+fn a_correction_fades_instead_of_snapping() {
+    let map = room();
+    let mut p = player();
+    let mut prediction = Prediction::new();
+    prediction.predict(&mut p, &map, walk(1));
+    let drawn = prediction.view(&p);
+
+    // knocked back a bit by something we didn't predict
+    let mut server = p;
+    server.x -= 0.3;
+    prediction.reconcile(&mut p, &map, &state(&server, 1));
+    assert_eq!(p.x, server.x);
+    // still drawn where we were, then sliding over
+    assert!((prediction.view(&p).x - drawn.x).abs() < 1e-5);
+    prediction.smooth(0.1);
+    let x = prediction.view(&p).x;
+    assert!(x < drawn.x && x > p.x);
+    for _ in 0..60 {
+        prediction.smooth(1.0 / 60.0);
+    }
+    assert!((prediction.view(&p).x - p.x).abs() < 1e-3);
+}
+
+#[test]
+fn a_respawn_is_not_smoothed() {
+    let map = room();
+    let mut p = player();
+    let mut prediction = Prediction::new();
+    prediction.predict(&mut p, &map, walk(1));
+    let mut server = p;
+    server.x = 7.5;
+    server.y = 7.5;
+    prediction.reconcile(&mut p, &map, &state(&server, 1));
+    assert_eq!(prediction.offset, (0.0, 0.0));
+    assert_eq!(prediction.view(&p).x, 7.5);
+}
+
+#[test]
+fn late_snapshots_are_ignored() {
+    let map = room();
+    let mut p = player();
+    let mut prediction = Prediction::new();
+    for tick in 1..=3 {
+        prediction.predict(&mut p, &map, walk(tick));
+    }
+    let newer = state(&p, 3);
+    prediction.reconcile(&mut p, &map, &newer);
+    let mut older = state(&player(), 1);
+    older.x = 4.0;
+    prediction.reconcile(&mut p, &map, &older);
+    assert_eq!(p.x, newer.x);
+}
+
+#[test]
+fn the_dead_do_not_move_and_the_buffer_stays_bounded() {
+    let map = room();
+    let mut p = Player {
+        health: 0,
+        ..player()
+    };
+    let mut prediction = Prediction::new();
+    for tick in 1..=MAX_PENDING as u32 + 10 {
+        prediction.predict(&mut p, &map, walk(tick));
+    }
+    assert_eq!(p.x, 2.5);
+    assert_eq!(prediction.pending().len(), MAX_PENDING);
+    assert_eq!(prediction.pending()[0].tick, 11);
+}
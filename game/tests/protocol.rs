@@ -0,0 +1,459 @@
+// round trip property tests for the wire format
+
+use game::mazegen::MazeAlgorithm;
+use game::mechanics::PlayerInput;
+use game::protocol::{
+    Baselines, ClientMessage, ErrorCode, PROTOCOL_VERSION, PlayerState, ServerMessage,
+    check_version, quantize_angle, quantize_position,
+};
+use game::reliable::{Ack, Channel, ReliableMessage, ReliablePacket};
+use game::render::Team;
+use game::rooms::{Carve, RoomConfig};
+use game::server::MAX_PLAYERS;
+use game::sprites::Animation;
+use game::weapons::{MAX_PROJECTILES, ProjectileKind, ProjectileState, Weapon};
+use proptest::prelude::*;
+use std::f32::consts::PI;
+
+// NaN never compares equal, the game never sends it anyway
+fn coord() -> impl Strategy<Value = f32> {
+    -1.0e6f32..1.0e6f32
+}
+
+// snapshots carry positions and angles quantized, only those come back unchanged
+fn position() -> impl Strategy<Value = f32> {
+    (-16000.0f32..16000.0).prop_map(quantize_position)
+}
+
+fn angle() -> impl Strategy<Value = f32> {
+    (-PI..PI).prop_map(quantize_angle)
+}
+
+fn player_state() -> impl Strategy<Value = PlayerState> {
+    (
+        any::<u32>(),
+        // 4 chars of up to 4 bytes stay under the 16 byte cap
+        "\\PC{0,4}",
+        prop::sample::select(vec![Team::None, Team::Red, Team::Blue]),
+        (position(), position(), angle()),
+        any::<[u8; 2]>(),
+        any::<bool>(),
+        prop::sample::select(vec![Animation::Idle, Animation::Walk, Animation::Dead]),
+        any::<u32>(),
+    )
+        .prop_map(
+            |(id, name, team, (x, y, angle), [health, armor], alive, animation, ack)| PlayerState {
+                id,
+                name,
+                team,
+                x,
+                y,
+                angle,
+                health,
+                armor,
+                alive,
+                animation,
+                ack,
+            },
+        )
+}
+
+fn projectile_state() -> impl Strategy<Value = ProjectileState> {
+    (
+        any::<u32>(),
+        prop::sample::select(vec![ProjectileKind::Rocket, ProjectileKind::Grenade]),
+        position(),
+        position(),
+    )
+        .prop_map(|(id, kind, x, y)| ProjectileState { id, kind, x, y })
+}
+
+// what the server sends: ids from a small range so two snapshots share players, sorted
+fn players() -> impl Strategy<Value = Vec<PlayerState>> {
+    prop::collection::vec((0u32..24, player_state()), 0..16).prop_map(|players| {
+        let mut players: Vec<PlayerState> = players
+            .into_iter()
+            .map(|(id, p)| PlayerState { id, ..p })
+            .collect();
+        players.sort_by_key(|p| p.id);
+        players.dedup_by_key(|p| p.id);
+        players
+    })
+}
+
+fn reliable_packet() -> impl Strategy<Value = ReliablePacket> {
+    let ack = (any::<u16>(), any::<u32>()).prop_map(|(seq, bits)| Ack { seq, bits });
+    let message = (
+        prop::sample::select(Channel::ALL.to_vec()),
+        any::<u16>(),
+        prop::collection::vec(any::<u8>(), 0..32),
+    )
+        .prop_map(|(channel, seq, payload)| ReliableMessage {
+            channel,
+            seq,
+            payload,
+        });
+    ([ack.clone(), ack], prop::collection::vec(message, 0..8))
+        .prop_map(|(acks, messages)| ReliablePacket { acks, messages })
+}
+
+fn error_code() -> impl Strategy<Value = ErrorCode> {
+    prop_oneof![
+        Just(ErrorCode::VersionMismatch),
+        Just(ErrorCode::ServerFull),
+        (3u8..=255).prop_map(ErrorCode::Other),
+    ]
+}
+
+fn client_message() -> impl Strategy<Value = ClientMessage> {
+    prop_oneof![
+        any::<u16>().prop_map(|version| ClientMessage::Join { version }),
+        (
+            any::<u32>(),
+            (coord(), coord(), coord(), coord(), coord()),
+            any::<u8>()
+        )
+            .prop_map(|(tick, (dt, forward, strafe, turn, yaw), buttons)| {
+                ClientMessage::Input(PlayerInput {
+                    tick,
+                    dt,
+                    forward,
+                    strafe,
+                    turn,
+                    yaw,
+                    buttons,
+                })
+            }),
+        Just(ClientMessage::Leave),
+        any::<u32>().prop_map(|nonce| ClientMessage::Ping { nonce }),
+        any::<u32>().prop_map(|tick| ClientMessage::SnapshotAck { tick }),
+        "\\PC{0,4}".prop_map(|name| ClientMessage::SetName { name }),
+        prop::sample::select(Weapon::ALL.to_vec())
+            .prop_map(|weapon| ClientMessage::SelectWeapon { weapon }),
+        ".{0,32}".prop_map(|text| ClientMessage::Say { text }),
+        reliable_packet().prop_map(ClientMessage::Reliable),
+    ]
+}
+
+fn welcome() -> impl Strategy<Value = ServerMessage> {
+    (
+        any::<u32>(),
+        any::<u64>(),
+        any::<u16>(),
+        any::<u16>(),
+        prop::sample::select(MazeAlgorithm::ALL.to_vec()),
+        0u8..=100,
+        room_config(),
+        // 16 chars of up to 4 bytes stay under the 64 byte cap
+        "\\PC{0,16}",
+        any::<u32>(),
+        any::<bool>(),
+    )
+        .prop_map(
+            |(id, seed, width, height, algorithm, braid, rooms, map_name, map_version, sky)| {
+                ServerMessage::Welcome {
+                    id,
+                    seed,
+                    width,
+                    height,
+                    algorithm,
+                    braid,
+                    rooms,
+                    map_name,
+                    map_version,
+                    sky,
+                }
+            },
+        )
+}
+
+fn carve() -> impl Strategy<Value = Carve> {
+    any::<[u8; 3]>().prop_map(|[count, min, max]| Carve { count, min, max })
+}
+
+fn room_config() -> impl Strategy<Value = RoomConfig> {
+    (carve(), carve(), carve()).prop_map(|(rooms, plazas, wide_corridors)| RoomConfig {
+        rooms,
+        plazas,
+        wide_corridors,
+    })
+}
+
+fn server_message() -> impl Strategy<Value = ServerMessage> {
+    prop_oneof![
+        welcome(),
+        (
+            any::<u32>(),
+            prop::collection::vec(player_state(), 0..32),
+            prop::collection::vec(projectile_state(), 0..8),
+        )
+            .prop_map(|(tick, players, projectiles)| ServerMessage::Snapshot {
+                tick,
+                players,
+                projectiles,
+            }),
+        any::<u32>().prop_map(|nonce| ServerMessage::Pong { nonce }),
+        (error_code(), ".{0,64}")
+            .prop_map(|(code, message)| ServerMessage::Error { code, message }),
+        (
+            any::<u32>(),
+            any::<u32>(),
+            prop::sample::select(Weapon::ALL.to_vec())
+        )
+            .prop_map(|(killer, victim, weapon)| ServerMessage::Kill {
+                killer,
+                victim,
+                weapon
+            }),
+        (any::<u32>(), ".{0,32}").prop_map(|(from, text)| ServerMessage::Chat { from, text }),
+        (any::<u32>(), "\\PC{0,4}").prop_map(|(id, name)| ServerMessage::Joined { id, name }),
+        any::<u32>().prop_map(|id| ServerMessage::Left { id }),
+        reliable_packet().prop_map(ServerMessage::Reliable),
+    ]
+}
+
+proptest! {
+    #[test]
+    fn client_messages_round_trip(msg in client_message()) {
+        prop_assert_eq!(ClientMessage::decode(&msg.encode()), Ok(msg));
+    }
+
+    #[test]
+    fn server_messages_round_trip(msg in server_message()) {
+        prop_assert_eq!(ServerMessage::decode(&msg.encode()), Ok(msg));
+    }
+
+    #[test]
+    fn truncated_packets_are_errors(msg in server_message(), cut in 1usize..16) {
+        let bytes = msg.encode();
+        let len = bytes.len().saturating_sub(cut);
+        prop_assert!(ServerMessage::decode(&bytes[..len]).is_err());
+    }
+
+    #[test]
+    fn deltas_decode_to_exactly_the_snapshot(
+        base in players(),
+        next in players(),
+        // which of the players in both stay as they were
+        same in any::<u32>(),
+        age in 1u32..=32,
+        projectiles in prop::collection::vec(projectile_state(), 0..8),
+    ) {
+        let next: Vec<PlayerState> = next
+            .into_iter()
+            .map(|p| match base.iter().find(|b| b.id == p.id) {
+                Some(b) if same & (1 << p.id) != 0 => b.clone(),
+                _ => p,
+            })
+            .collect();
+        let mut client = Baselines::new();
+        let first = ServerMessage::Snapshot {
+            tick: 100,
+            players: base.clone(),
+            projectiles: Vec::new(),
+        };
+        prop_assert_eq!(client.decode(&first.encode()), Ok(first));
+        let msg = ServerMessage::Snapshot {
+            tick: 100 + age,
+            players: next,
+            projectiles,
+        };
+        let delta = msg.encode_delta(100, &base);
+        prop_assert_eq!(client.decode(&delta), Ok(msg));
+    }
+
+    #[test]
+    fn garbage_never_panics(bytes in prop::collection::vec(any::<u8>(), 0..64)) {
+        let _ = ClientMessage::decode(&bytes);
+        let _ = ServerMessage::decode(&bytes);
+        let _ = Baselines::new().decode(&bytes);
+    }
+}
+
+#[test]
+fn version_handshake() {
+    assert!(check_version(PROTOCOL_VERSION).is_ok());
+    let Err(ServerMessage::Error { code, message }) = check_version(PROTOCOL_VERSION + 1) else {
+        panic!("newer client must be rejected");
+    };
+    assert_eq!(code, ErrorCode::VersionMismatch);
+    assert!(message.contains("update your server"));
+    assert_eq!(
+        ClientMessage::join(),
+        ClientMessage::Join {
+            version: PROTOCOL_VERSION
+        }
+    );
+}
+
+#[test]
+fn long_error_text_is_cut_on_a_char_boundary() {
+    let msg = ServerMessage::Error {
+        code: ErrorCode::ServerFull,
+        message: "é".repeat(1000),
+    };
+    let Ok(ServerMessage::Error { message, .. }) = ServerMessage::decode(&msg.encode()) else {
+        panic!("error message must still decode");
+    };
+    assert_eq!(message, "é".repeat(256));
+}
+
+#[test]
+fn long_player_names_are_cut() {
+    let msg = ServerMessage::Snapshot {
+        tick: 1,
+        players: vec![PlayerState {
+            id: 1,
+            name: "abcdefghij".repeat(3),
+            team: Team::Blue,
+            x: 1.5,
+            y: 2.5,
+            angle: 0.5,
+            health: 80,
+            armor: 20,
+            alive: true,
+            animation: Animation::Walk,
+            ack: 7,
+        }],
+        projectiles: Vec::new(),
+    };
+    let Ok(ServerMessage::Snapshot { players, .. }) = ServerMessage::decode(&msg.encode()) else {
+        panic!("snapshot must still decode");
+    };
+    assert_eq!(players[0].name, "abcdefghijabcdef");
+    assert_eq!(players[0].team, Team::Blue);
+
+    let other = players[0].to_other();
+    assert_eq!((other.id, other.health, other.alive), (1, 80, true));
+    assert_eq!((other.x, other.y), (1.5, 2.5));
+    assert!((other.angle - 0.5).abs() < 1e-4);
+    assert_eq!(other.animation, Animation::Walk);
+}
+
+#[test]
+fn a_full_snapshot_fits_in_a_packet() {
+    let player = PlayerState {
+        id: u32::MAX,
+        name: "x".repeat(64),
+        team: Team::Red,
+        x: 1.0,
+        y: 1.0,
+        angle: 1.0,
+        health: 100,
+        armor: 100,
+        alive: true,
+        animation: Animation::Walk,
+        ack: u32::MAX,
+    };
+    let msg = ServerMessage::Snapshot {
+        tick: u32::MAX,
+        players: vec![player; MAX_PLAYERS],
+        projectiles: vec![ProjectileState::default(); MAX_PROJECTILES],
+    };
+    // the server reads and sends at most 1400 bytes, under the usual 1500 byte mtu
+    assert!(msg.encode().len() <= 1400, "{} bytes", msg.encode().len());
+}
+
+fn walker(id: u32, x: f32) -> PlayerState {
+    PlayerState {
+        id,
+        name: format!("player {id}"),
+        team: Team::Red,
+        x,
+        y: 3.5,
+        angle: 0.0,
+        health: 100,
+        armor: 50,
+        alive: true,
+        animation: Animation::Idle,
+        ack: 40,
+    }
+}
+
+#[test]
+fn a_delta_only_carries_what_changed() {
+    let base: Vec<PlayerState> = (1..=MAX_PLAYERS as u32)
+        .map(|id| walker(id, id as f32 + 0.5))
+        .collect();
+    let mut next = base.clone();
+    // one of them walks a tick's worth and sends another input
+    next[3].x += 0.05;
+    next[3].animation = Animation::Walk;
+    next[3].ack += 1;
+    let msg = ServerMessage::Snapshot {
+        tick: 12,
+        players: next
+            .iter()
+            .map(|p| PlayerState {
+                x: quantize_position(p.x),
+                ..p.clone()
+            })
+            .collect(),
+        projectiles: Vec::new(),
+    };
+    let full = msg.encode();
+    let delta = msg.encode_delta(11, &base);
+    // kind, tick, age, three counts and one entry with id, mask, x, status and ack
+    assert!(delta.len() <= 21, "{} bytes", delta.len());
+    assert!(full.len() > 16 * delta.len(), "{} bytes", full.len());
+
+    let mut client = Baselines::new();
+    let first = ServerMessage::Snapshot {
+        tick: 11,
+        players: base,
+        projectiles: Vec::new(),
+    };
+    client.decode(&first.encode()).unwrap();
+    assert_eq!(client.decode(&delta), Ok(msg));
+}
+
+#[test]
+fn deltas_need_their_baseline() {
+    let base = vec![walker(1, 1.5), walker(2, 2.5)];
+    let msg = ServerMessage::Snapshot {
+        tick: 20,
+        players: vec![walker(2, 3.5), walker(3, 4.5)],
+        projectiles: Vec::new(),
+    };
+    let delta = msg.encode_delta(18, &base);
+    assert!(ServerMessage::decode(&delta).is_err());
+    let mut client = Baselines::new();
+    let other = ServerMessage::Snapshot {
+        tick: 19,
+        players: base.clone(),
+        projectiles: Vec::new(),
+    };
+    client.decode(&other.encode()).unwrap();
+    assert!(client.decode(&delta).is_err());
+
+    // with the right one the leaver is gone and the newcomer is there
+    let first = ServerMessage::Snapshot {
+        tick: 18,
+        players: base,
+        projectiles: Vec::new(),
+    };
+    client.decode(&first.encode()).unwrap();
+    assert_eq!(client.decode(&delta), Ok(msg));
+    // and a full snapshot decodes without one
+    let full = ServerMessage::Snapshot {
+        tick: 21,
+        players: Vec::new(),
+        projectiles: Vec::new(),
+    };
+    assert_eq!(Baselines::new().decode(&full.encode()), Ok(full));
+}
+
+#[test]
+fn quantizing_twice_changes_nothing() {
+    for v in [0.0, 1.5, -3.25, 0.1234, 1000.001] {
+        let q = quantize_position(v);
+        assert!((q - v).abs() <= 0.5 / game::protocol::POSITION_SCALE);
+        assert_eq!(quantize_position(q), q);
+    }
+    for a in [0.0, 0.5, -3.1, PI - 1e-4, PI, -PI, 7.0] {
+        let q = quantize_angle(a);
+        assert!((-PI..PI).contains(&q), "{q}");
+        assert_eq!(quantize_angle(q), q);
+    }
+    assert_eq!(quantize_angle(0.0), 0.0);
+}
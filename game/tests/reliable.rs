@@ -0,0 +1,366 @@
+// the reliable channels over a pretend network that loses, duplicates and reorders packets.
+// every packet goes through the real encoding on the way
+
+use game::protocol::{ClientMessage, MAX_PACKET, ServerMessage};
+use game::reliable::{Channel, MAX_MESSAGE, RESEND_TIME, Reliable, ReliablePacket, WINDOW};
+use game::weapons::Weapon;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use std::collections::HashSet;
+
+const TICK: f32 = 1.0 / 30.0;
+
+// what the network does to every packet, each a chance from 0 to 1
+#[derive(Clone, Copy)]
+struct Conditions {
+    loss: f64,
+    duplicate: f64,
+    // held back for a few ticks so later packets overtake it
+    reorder: f64,
+}
+
+const PERFECT: Conditions = Conditions {
+    loss: 0.0,
+    duplicate: 0.0,
+    reorder: 0.0,
+};
+
+// one direction of the network, packets come out a tick after they go in unless held back
+struct Link {
+    conditions: Conditions,
+    rng: ChaCha8Rng,
+    // (tick it comes out, packet)
+    in_flight: Vec<(u32, Vec<u8>)>,
+    sent: usize,
+}
+
+impl Link {
+    fn new(conditions: Conditions, seed: u64) -> Self {
+        Self {
+            conditions,
+            rng: ChaCha8Rng::seed_from_u64(seed),
+            in_flight: Vec::new(),
+            sent: 0,
+        }
+    }
+
+    fn send(&mut self, now: u32, packet: Vec<u8>) {
+        self.sent += 1;
+        if self.rng.random_bool(self.conditions.loss) {
+            return;
+        }
+        let copies = if self.rng.random_bool(self.conditions.duplicate) {
+            2
+        } else {
+            1
+        };
+        for _ in 0..copies {
+            let delay = if self.rng.random_bool(self.conditions.reorder) {
+                self.rng.random_range(2..=6)
+            } else {
+                1
+            };
+            self.in_flight.push((now + delay, packet.clone()));
+        }
+    }
+
+    // what comes out this tick, in the order it went in
+    fn deliver(&mut self, now: u32) -> Vec<Vec<u8>> {
+        let (due, later) = self.in_flight.drain(..).partition(|&(at, _)| at <= now);
+        self.in_flight = later;
+        due.into_iter().map(|(_, packet)| packet).collect()
+    }
+}
+
+// a client and a server end joined by a link each way
+struct Network {
+    client: Reliable,
+    server: Reliable,
+    up: Link,
+    down: Link,
+    now: u32,
+    // what each end handed out so far
+    at_server: Vec<(Channel, Vec<u8>)>,
+    at_client: Vec<(Channel, Vec<u8>)>,
+}
+
+impl Network {
+    fn new(conditions: Conditions, seed: u64) -> Self {
+        Self {
+            client: Reliable::new(),
+            server: Reliable::new(),
+            up: Link::new(conditions, seed),
+            down: Link::new(conditions, seed + 1),
+            now: 0,
+            at_server: Vec::new(),
+            at_client: Vec::new(),
+        }
+    }
+
+    fn tick(&mut self) {
+        self.now += 1;
+        for packet in self.up.deliver(self.now) {
+            let Ok(ClientMessage::Reliable(packet)) = ClientMessage::decode(&packet) else {
+                panic!("garbled packet");
+            };
+            self.server.receive(&packet);
+        }
+        for packet in self.down.deliver(self.now) {
+            let Ok(ServerMessage::Reliable(packet)) = ServerMessage::decode(&packet) else {
+                panic!("garbled packet");
+            };
+            self.client.receive(&packet);
+        }
+        while let Some(msg) = self.server.poll() {
+            self.at_server.push(msg);
+        }
+        while let Some(msg) = self.client.poll() {
+            self.at_client.push(msg);
+        }
+        if let Some(packet) = self.client.update(TICK) {
+            self.up
+                .send(self.now, ClientMessage::Reliable(packet).encode());
+        }
+        if let Some(packet) = self.server.update(TICK) {
+            self.down
+                .send(self.now, ServerMessage::Reliable(packet).encode());
+        }
+    }
+
+    // ticks until both ends have nothing left in flight, gives up after `limit` ticks
+    fn settle(&mut self, limit: u32) {
+        for _ in 0..limit {
+            self.tick();
+            if self.client.in_flight() == 0 && self.server.in_flight() == 0 {
+                return;
+            }
+        }
+        panic!(
+            "still {} + {} in flight",
+            self.client.in_flight(),
+            self.server.in_flight()
+        );
+    }
+}
+
+fn kill(n: u32) -> Vec<u8> {
+    ServerMessage::Kill {
+        killer: n,
+        victim: n + 1,
+        weapon: Weapon::Rifle,
+    }
+    .encode()
+}
+
+fn chat(n: u32) -> Vec<u8> {
+    ClientMessage::Say {
+        text: format!("line {n}"),
+    }
+    .encode()
+}
+
+fn on(channel: Channel, got: &[(Channel, Vec<u8>)]) -> Vec<Vec<u8>> {
+    got.iter()
+        .filter(|(c, _)| *c == channel)
+        .map(|(_, payload)| payload.clone())
+        .collect()
+}
+
+#[test]
+fn everything_arrives_once_and_in_order_over_a_bad_network() {
+    let bad = Conditions {
+        loss: 0.3,
+        duplicate: 0.2,
+        reorder: 0.3,
+    };
+    for seed in 0..5 {
+        let mut net = Network::new(bad, seed);
+        let mut sent_game = Vec::new();
+        let mut sent_chat = Vec::new();
+        // a burst every few ticks in both directions
+        for n in 0..150 {
+            net.server.send(Channel::Game, kill(n)).unwrap();
+            sent_game.push(kill(n));
+            if n % 3 == 0 {
+                net.client.send(Channel::Chat, chat(n)).unwrap();
+                sent_chat.push(chat(n));
+            }
+            if n % 4 == 0 {
+                net.tick();
+            }
+        }
+        net.settle(2000);
+        assert_eq!(on(Channel::Game, &net.at_client), sent_game, "seed {seed}");
+        assert_eq!(on(Channel::Chat, &net.at_server), sent_chat, "seed {seed}");
+        assert!(net.at_server.iter().all(|(c, _)| *c == Channel::Chat));
+        assert!(net.at_client.iter().all(|(c, _)| *c == Channel::Game));
+    }
+}
+
+#[test]
+fn a_perfect_network_sends_everything_once() {
+    let mut net = Network::new(PERFECT, 1);
+    for n in 0..20 {
+        net.server.send(Channel::Game, kill(n)).unwrap();
+        net.tick();
+    }
+    net.settle(100);
+    assert_eq!(on(Channel::Game, &net.at_client).len(), 20);
+    // one packet a tick with the new message, nothing resent
+    assert_eq!(net.down.sent, 20);
+    // and nothing more once it is all acked
+    for _ in 0..30 {
+        net.tick();
+    }
+    assert_eq!(net.down.sent, 20);
+}
+
+#[test]
+fn acks_go_back_even_without_anything_to_say() {
+    let mut net = Network::new(PERFECT, 2);
+    net.server.send(Channel::Game, kill(1)).unwrap();
+    net.tick();
+    assert_eq!(net.server.in_flight(), 1);
+    net.settle(10);
+    // the client only ever sent acks
+    assert_eq!(net.up.sent, 1);
+    assert!(net.at_server.is_empty());
+    // a quiet connection sends nothing at all
+    assert!(net.client.update(TICK).is_none());
+    assert!(net.server.update(TICK).is_none());
+}
+
+#[test]
+fn a_lost_message_is_resent_and_holds_up_only_its_channel() {
+    let mut server = Reliable::new();
+    let mut client = Reliable::new();
+    server.send(Channel::Game, kill(1)).unwrap();
+    // lost on the way
+    server.update(TICK).unwrap();
+    server.send(Channel::Game, kill(2)).unwrap();
+    server.send(Channel::Chat, chat(1)).unwrap();
+    let packet = server.update(TICK).unwrap();
+    // not due again yet, only the new ones went out
+    assert_eq!(packet.messages.len(), 2);
+    client.receive(&packet);
+    // the chat line comes out, the second kill waits for the first
+    assert_eq!(client.poll(), Some((Channel::Chat, chat(1))));
+    assert_eq!(client.poll(), None);
+
+    // the ack tells the server the second kill got there early
+    server.receive(&client.update(TICK).unwrap());
+    assert_eq!(server.in_flight(), 1);
+    let mut resent: Option<ReliablePacket> = None;
+    for _ in 0..10 {
+        resent = server.update(TICK);
+        if resent.is_some() {
+            break;
+        }
+    }
+    let resent = resent.expect("the lost one is never resent");
+    assert_eq!(resent.messages.len(), 1);
+    assert_eq!(resent.messages[0].payload, kill(1));
+    client.receive(&resent);
+    assert_eq!(client.poll(), Some((Channel::Game, kill(1))));
+    assert_eq!(client.poll(), Some((Channel::Game, kill(2))));
+    server.receive(&client.update(TICK).unwrap());
+    assert_eq!(server.in_flight(), 0);
+}
+
+#[test]
+fn resends_wait_for_the_timer() {
+    let mut server = Reliable::new();
+    server.send(Channel::Game, kill(1)).unwrap();
+    assert!(server.update(TICK).is_some());
+    let mut waited = 0.0;
+    while server.update(TICK).is_none() {
+        waited += TICK;
+        assert!(waited < 1.0);
+    }
+    assert!(waited + TICK >= RESEND_TIME - 1e-4, "{waited}");
+}
+
+#[test]
+fn sequence_numbers_wrap_around() {
+    let mut net = Network::new(
+        Conditions {
+            loss: 0.1,
+            duplicate: 0.1,
+            reorder: 0.1,
+        },
+        3,
+    );
+    let total = 70_000;
+    let mut queued = 0;
+    // keep a steady stream going well past u16::MAX
+    while queued < total {
+        for _ in 0..40 {
+            net.server.send(Channel::Game, kill(queued)).unwrap();
+            queued += 1;
+        }
+        net.tick();
+    }
+    net.settle(20_000);
+    let got = on(Channel::Game, &net.at_client);
+    assert_eq!(got.len(), total as usize);
+    assert!(got.iter().enumerate().all(|(n, p)| *p == kill(n as u32)));
+}
+
+#[test]
+fn full_windows_of_small_messages_still_fit_in_a_packet() {
+    let mut server = Reliable::new();
+    let hello = ServerMessage::Chat {
+        from: 1,
+        text: "hello world".to_string(),
+    }
+    .encode();
+    for channel in Channel::ALL {
+        for _ in 0..WINDOW {
+            server.send(channel, hello.clone()).unwrap();
+        }
+    }
+    // what doesn't fit goes in the packets right after
+    let mut sent = HashSet::new();
+    while sent.len() < Channel::ALL.len() * WINDOW as usize {
+        let packet = ServerMessage::Reliable(server.update(TICK).unwrap()).encode();
+        assert!(packet.len() <= MAX_PACKET, "{} bytes", packet.len());
+        let Ok(ServerMessage::Reliable(packet)) = ServerMessage::decode(&packet) else {
+            panic!("garbled packet");
+        };
+        assert!(!packet.messages.is_empty());
+        sent.extend(packet.messages.iter().map(|m| (m.channel.to_u8(), m.seq)));
+    }
+}
+
+#[test]
+fn a_message_too_big_for_a_packet_is_refused() {
+    let mut server = Reliable::new();
+    assert!(
+        server
+            .send(Channel::Chat, vec![0; MAX_MESSAGE + 1])
+            .is_err()
+    );
+    assert_eq!(server.in_flight(), 0);
+    // the biggest one there is fills a packet exactly
+    server.send(Channel::Chat, vec![0; MAX_MESSAGE]).unwrap();
+    let packet = ServerMessage::Reliable(server.update(TICK).unwrap()).encode();
+    assert_eq!(packet.len(), MAX_PACKET);
+    assert!(ServerMessage::decode(&packet).is_ok());
+}
+
+#[test]
+fn everything_after_a_gap_is_acked() {
+    let mut server = Reliable::new();
+    let mut client = Reliable::new();
+    for n in 0..WINDOW as u32 {
+        server.send(Channel::Game, kill(n)).unwrap();
+    }
+    let mut packet = server.update(TICK).unwrap();
+    // the first one gets lost, the rest of the window comes in early
+    packet.messages.remove(0);
+    client.receive(&packet);
+    assert_eq!(client.poll(), None);
+    server.receive(&client.update(TICK).unwrap());
+    // only the lost one is left to resend
+    assert_eq!(server.in_flight(), 1);
+}
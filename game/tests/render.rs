@@ -0,0 +1,410 @@
+use game::render::{
+    Actors, NAME_TAG_RANGE, OtherPlayer, Player, Team, cast_and_draw_columns, render_frame,
+};
+use game::textures::TextureSet;
+use game::tilemap::{Tile, TileMap};
+use game::weapons::{ProjectileKind, ProjectileState};
+
+const W: usize = 64;
+const H: usize = 48;
+
+fn textures() -> TextureSet {
+    TextureSet::load_dir(std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("textures")).unwrap()
+}
+
+// 9x3 corridor, the player stands at the west end looking east
+fn corridor() -> TileMap {
+    let mut row = vec![0; 9];
+    row[0] = 1;
+    row[8] = 1;
+    TileMap::from_grid(&[vec![1; 9], row, vec![1; 9]])
+}
+
+fn player() -> Player {
+    Player::at(1.5, 1.5, 0.0)
+}
+
+// 21x21 room, the walls are at least 9 tiles from the middle so the top rows are all
+// ceiling or sky
+fn open_room(sky: bool) -> TileMap {
+    let mut map = TileMap::new(21, 21, Tile::Wall(1));
+    for y in 1..20 {
+        for x in 1..20 {
+            *map.get_mut(x, y).unwrap() = Tile::Empty;
+        }
+    }
+    map.set_open_sky(sky);
+    map
+}
+
+fn render_from(map: &TileMap, p: &Player, others: &[OtherPlayer]) -> Vec<u32> {
+    let mut fb = vec![0; W * H];
+    cast_and_draw_columns(
+        &mut fb,
+        &mut Vec::new(),
+        W,
+        H,
+        map,
+        &textures(),
+        p,
+        Actors::players(others),
+    );
+    fb
+}
+
+fn render(map: &TileMap, others: &[OtherPlayer]) -> Vec<u32> {
+    render_from(map, &player(), others)
+}
+
+fn top_row(fb: &[u32]) -> &[u32] {
+    &fb[..W]
+}
+
+fn bottom_row(fb: &[u32]) -> &[u32] {
+    &fb[(H - 1) * W..]
+}
+
+fn center(fb: &[u32]) -> u32 {
+    fb[H / 2 * W + W / 2]
+}
+
+#[test]
+fn fills_every_pixel() {
+    let fb = render(&corridor(), &[]);
+    assert!(
+        fb.iter().all(|&px| px & 0xff == 0xff),
+        "unwritten or transparent pixel"
+    );
+    assert_eq!(fb, render(&corridor(), &[]));
+}
+
+#[test]
+fn far_wall_is_textured() {
+    let fb = render(&corridor(), &[]);
+    // the end wall is 7 tiles away, about 48 / 7 rows tall around the horizon.
+    // a flat shaded wall would be one color, the stone texture has mortar and noise
+    let wall: std::collections::HashSet<u32> = (H / 2 - 2..H / 2 + 2)
+        .flat_map(|y| (W / 2 - 4..W / 2 + 4).map(move |x| (x, y)))
+        .map(|(x, y)| fb[y * W + x])
+        .collect();
+    assert!(wall.len() > 2, "{wall:x?}");
+    assert!(!wall.contains(&fb[W / 2]));
+}
+
+#[test]
+fn floor_and_ceiling_are_textured_and_move_with_the_player() {
+    let fb = render(&corridor(), &[]);
+    let floor: std::collections::HashSet<u32> = bottom_row(&fb).iter().copied().collect();
+    assert!(floor.len() > 2, "flat floor {floor:x?}");
+
+    let mut ahead = player();
+    ahead.x += 0.3;
+    let moved = render_from(&corridor(), &ahead, &[]);
+    assert_ne!(bottom_row(&moved), bottom_row(&fb));
+    assert_ne!(top_row(&moved), top_row(&fb));
+}
+
+#[test]
+fn hazard_tiles_have_their_own_floor_and_ceiling() {
+    let mut map = corridor();
+    let plain = render(&map, &[]);
+    // the bottom row of a 48 row view shows the floor about one tile ahead, the top row the
+    // ceiling over it
+    *map.get_mut(2, 1).unwrap() = Tile::Hazard;
+    let lava = render(&map, &[]);
+    assert_ne!(bottom_row(&lava), bottom_row(&plain));
+    assert_ne!(top_row(&lava), top_row(&plain));
+    // a spawn point only marks the floor
+    *map.get_mut(2, 1).unwrap() = Tile::Spawn;
+    assert_eq!(top_row(&render(&map, &[])), top_row(&plain));
+}
+
+#[test]
+fn sky_turns_with_the_view_but_not_with_position() {
+    let mut p = player();
+    (p.x, p.y) = (10.5, 10.5);
+    let sky = render_from(&open_room(true), &p, &[]);
+    assert_ne!(
+        top_row(&sky),
+        top_row(&render_from(&open_room(false), &p, &[]))
+    );
+
+    let mut walked = p;
+    walked.x += 2.0;
+    walked.y -= 1.0;
+    assert_eq!(
+        top_row(&render_from(&open_room(true), &walked, &[])),
+        top_row(&sky)
+    );
+
+    let mut turned = p;
+    turned.angle += 0.5;
+    assert_ne!(
+        top_row(&render_from(&open_room(true), &turned, &[])),
+        top_row(&sky)
+    );
+
+    // a full turn later it's the same sky again
+    turned.angle = p.angle + std::f32::consts::TAU;
+    let again = render_from(&open_room(true), &turned, &[]);
+    let same = top_row(&again)
+        .iter()
+        .zip(top_row(&sky))
+        .filter(|(a, b)| a == b)
+        .count();
+    assert!(same >= W - 2, "{same} of {W}");
+}
+
+#[test]
+fn other_player_in_front_is_drawn() {
+    let fb = render(&corridor(), &[OtherPlayer::at(4.5, 1.5)]);
+    // 3 tiles away the sprite is 16 rows tall around the horizon, seen from behind
+    // the green shirt shows around the backpack
+    let green = |px: &u32| {
+        let [r, g, b, _] = px.to_be_bytes();
+        g > r + 20 && g > b + 20
+    };
+    assert!(fb[16 * W..32 * W].iter().any(green));
+    assert!(!render(&corridor(), &[]).iter().any(green));
+    assert_ne!(fb, render(&corridor(), &[]));
+}
+
+#[test]
+fn sprite_holes_show_the_level_behind() {
+    let empty = render(&corridor(), &[]);
+    let fb = render(&corridor(), &[OtherPlayer::at(4.5, 1.5)]);
+    // the 16x16 billboard spans columns 24..40 and rows 16..32, its corners are transparent
+    for (x, y) in [(24, 16), (39, 16), (24, 31), (39, 31)] {
+        assert_eq!(fb[y * W + x], empty[y * W + x], "({x}, {y})");
+    }
+}
+
+#[test]
+fn near_sprites_cover_far_ones_whatever_the_order() {
+    let near = OtherPlayer::at(3.5, 1.5);
+    let far = OtherPlayer::at(6.5, 1.5);
+    let fb = render(&corridor(), &[near.clone(), far.clone()]);
+    assert_eq!(fb, render(&corridor(), &[far, near.clone()]));
+    assert_eq!(center(&fb), center(&render(&corridor(), &[near])));
+}
+
+#[test]
+fn other_player_behind_a_wall_is_hidden() {
+    let mut map = corridor();
+    *map.get_mut(3, 1).unwrap() = Tile::Wall(2);
+    let fb = render(&map, &[OtherPlayer::at(5.5, 1.5)]);
+    assert_eq!(fb, render(&map, &[]));
+}
+
+#[test]
+fn window_tints_what_is_behind_it() {
+    let open = render(&corridor(), &[]);
+    let mut map = corridor();
+    *map.get_mut(4, 1).unwrap() = Tile::Window;
+    let glass = render(&map, &[]);
+    assert_ne!(center(&glass), center(&open));
+    // the side walls and the ceiling right above the player are in front of the glass
+    assert_eq!(glass[W / 2], open[W / 2]);
+    assert_eq!(glass[H / 2 * W], open[H / 2 * W]);
+}
+
+#[test]
+fn only_the_view_is_touched() {
+    let mut fb = vec![0; W * H + 10];
+    cast_and_draw_columns(
+        &mut fb,
+        &mut Vec::new(),
+        W,
+        H,
+        &corridor(),
+        &textures(),
+        &player(),
+        Actors::default(),
+    );
+    assert!(fb[W * H..].iter().all(|&px| px == 0));
+}
+
+#[test]
+#[should_panic(expected = "framebuffer too small")]
+fn small_framebuffer_panics() {
+    let mut fb = vec![0; W * H - 1];
+    cast_and_draw_columns(
+        &mut fb,
+        &mut Vec::new(),
+        W,
+        H,
+        &corridor(),
+        &textures(),
+        &player(),
+        Actors::default(),
+    );
+}
+
+fn tags(map: &TileMap, others: &[OtherPlayer]) -> Vec<(String, Team)> {
+    let mut fb = vec![0; W * H];
+    cast_and_draw_columns(
+        &mut fb,
+        &mut Vec::new(),
+        W,
+        H,
+        map,
+        &textures(),
+        &player(),
+        Actors::players(others),
+    )
+    .into_iter()
+    .map(|t| (t.name, t.team))
+    .collect()
+}
+
+fn named(name: &str, team: Team, x: f32) -> OtherPlayer {
+    OtherPlayer {
+        name: name.to_string(),
+        team,
+        ..OtherPlayer::at(x, 1.5)
+    }
+}
+
+#[test]
+fn name_tags_only_for_living_players_in_sight() {
+    let mut map = corridor();
+    let near = named("near", Team::Red, 3.5);
+    let dead = OtherPlayer {
+        alive: false,
+        ..near.clone()
+    };
+    assert_eq!(tags(&map, &[near]), vec![("near".to_string(), Team::Red)]);
+    assert!(tags(&map, &[dead]).is_empty());
+
+    // behind a wall
+    *map.get_mut(3, 1).unwrap() = Tile::Wall(2);
+    assert!(tags(&map, &[named("hidden", Team::Blue, 5.5)]).is_empty());
+
+    // too far
+    let mut long = TileMap::new(40, 3, Tile::Wall(1));
+    for x in 1..39 {
+        *long.get_mut(x, 1).unwrap() = Tile::Empty;
+    }
+    let far = named("far", Team::None, 1.5 + NAME_TAG_RANGE + 1.0);
+    assert!(tags(&long, &[far]).is_empty());
+}
+
+#[test]
+fn name_tag_sits_above_the_health_bar_in_team_color() {
+    let mut fb = vec![0; W * H];
+    let red = named("red", Team::Red, 5.5);
+    let tags = cast_and_draw_columns(
+        &mut fb,
+        &mut Vec::new(),
+        W,
+        H,
+        &corridor(),
+        &textures(),
+        &player(),
+        Actors::players(&[red]),
+    );
+    // 4 tiles away the sprite is 12 rows tall from row 18, the bar is 4 wide and 2 high
+    // right above it
+    assert_eq!((tags[0].x, tags[0].y), (W as i32 / 2, 14));
+    let bar_row = &fb[16 * W..17 * W];
+    assert!(bar_row.contains(&u32::from_be_bytes([220, 60, 60, 255])));
+}
+
+#[test]
+fn health_bar_shrinks_with_health() {
+    let team_px = |health| {
+        let hurt = OtherPlayer {
+            health,
+            ..named("red", Team::Red, 3.5)
+        };
+        render(&corridor(), &[hurt])
+            .iter()
+            .filter(|&&px| px == u32::from_be_bytes([220, 60, 60, 255]))
+            .count()
+    };
+    assert!(team_px(100) > team_px(50));
+    assert!(team_px(50) > team_px(0));
+    assert_eq!(team_px(0), 0);
+}
+
+#[test]
+fn facing_and_death_change_the_sprite() {
+    let toward = OtherPlayer {
+        angle: std::f32::consts::PI,
+        ..OtherPlayer::at(3.5, 1.5)
+    };
+    let away = OtherPlayer::at(3.5, 1.5);
+    let dead = OtherPlayer {
+        alive: false,
+        ..OtherPlayer::at(3.5, 1.5)
+    };
+    let (a, b, c) = (
+        render(&corridor(), &[toward]),
+        render(&corridor(), &[away]),
+        render(&corridor(), &[dead]),
+    );
+    assert_ne!(a, b);
+    assert_ne!(b, c);
+    // lying down, nothing of them at eye level
+    assert_eq!(center(&c), center(&render(&corridor(), &[])));
+}
+
+#[test]
+fn minimap_shows_others_in_team_colors() {
+    let (w, h) = (120, 100);
+    let others = [named("a", Team::Red, 3.5), named("b", Team::Blue, 6.5)];
+    let mut fb = vec![0; w * h];
+    render_frame(
+        &mut fb,
+        &mut Vec::new(),
+        w,
+        h,
+        &corridor(),
+        &textures(),
+        &player(),
+        Actors::players(&others),
+    );
+    let minimap = &fb[w * h * 4 / 5..];
+    for team in [Team::Red, Team::Blue] {
+        let (r, g, b) = team.color();
+        assert!(
+            minimap.contains(&u32::from_be_bytes([r, g, b, 255])),
+            "{team:?}"
+        );
+    }
+}
+
+#[test]
+fn projectiles_show_in_the_view_and_on_the_minimap() {
+    let (w, h) = (120, 100);
+    let view = w * h * 4 / 5;
+    let draw = |actors: Actors<'_>| {
+        let mut fb = vec![0; w * h];
+        render_frame(
+            &mut fb,
+            &mut Vec::new(),
+            w,
+            h,
+            &corridor(),
+            &textures(),
+            &player(),
+            actors,
+        );
+        fb
+    };
+    let empty = draw(Actors::default());
+    for kind in [ProjectileKind::Rocket, ProjectileKind::Grenade] {
+        let shot = [ProjectileState {
+            kind,
+            x: 2.5,
+            y: 1.5,
+            ..ProjectileState::default()
+        }];
+        let fb = draw(Actors {
+            others: &[],
+            projectiles: &shot,
+        });
+        assert_ne!(fb[..view], empty[..view], "{kind:?} not in the 3d view");
+        assert_ne!(fb[view..], empty[view..], "{kind:?} not on the minimap");
+    }
+}
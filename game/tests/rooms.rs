@@ -0,0 +1,141 @@
+use game::config::MapConfig;
+use game::map::{generate_maze, maze_to_grid};
+use game::mazegen::MazeAlgorithm;
+use game::rooms::{Carve, RoomConfig, carve_rooms, connect_regions, is_connected};
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+fn open_tiles(grid: &[Vec<u8>]) -> usize {
+    grid.iter().flatten().filter(|&&t| t == 0).count()
+}
+
+fn border_intact(grid: &[Vec<u8>]) -> bool {
+    let (h, w) = (grid.len(), grid[0].len());
+    (0..w).all(|x| grid[0][x] != 0 && grid[h - 1][x] != 0)
+        && (0..h).all(|y| grid[y][0] != 0 && grid[y][w - 1] != 0)
+}
+
+fn arena() -> RoomConfig {
+    RoomConfig {
+        rooms: Carve {
+            count: 4,
+            min: 3,
+            max: 6,
+        },
+        plazas: Carve {
+            count: 2,
+            min: 7,
+            max: 11,
+        },
+        wide_corridors: Carve {
+            count: 5,
+            min: 6,
+            max: 14,
+        },
+    }
+}
+
+#[test]
+fn carved_maps_stay_connected() {
+    for algo in MazeAlgorithm::ALL {
+        for seed in 0..10 {
+            let cfg = MapConfig {
+                seed,
+                algorithm: algo,
+                rooms: arena(),
+                ..MapConfig::default()
+            };
+            let grid = cfg.build_grid();
+            let plain = MapConfig {
+                rooms: RoomConfig::default(),
+                ..cfg.clone()
+            }
+            .build_grid();
+            assert!(is_connected(&grid), "{algo} seed {seed} got split");
+            assert!(
+                border_intact(&grid),
+                "{algo} seed {seed} broke the outer wall"
+            );
+            assert!(open_tiles(&grid) > open_tiles(&plain));
+            assert_eq!(grid, cfg.build_grid(), "not reproducible");
+        }
+    }
+}
+
+#[test]
+fn rooms_bigger_than_the_map_are_clamped() {
+    let mut grid = maze_to_grid(&generate_maze(3, 2, 1));
+    let cfg = RoomConfig {
+        rooms: Carve {
+            count: 3,
+            min: 50,
+            max: 60,
+        },
+        ..RoomConfig::default()
+    };
+    carve_rooms(&mut grid, &cfg, &mut ChaCha8Rng::seed_from_u64(0));
+    assert!(border_intact(&grid));
+    assert!(is_connected(&grid));
+}
+
+#[test]
+fn plazas_keep_pillars() {
+    let mut grid = vec![vec![1u8; 21]; 21];
+    let cfg = RoomConfig {
+        plazas: Carve {
+            count: 1,
+            min: 19,
+            max: 19,
+        },
+        ..RoomConfig::default()
+    };
+    carve_rooms(&mut grid, &cfg, &mut ChaCha8Rng::seed_from_u64(0));
+    assert_eq!(grid[2][2], 1);
+    assert_eq!(grid[1][1], 0);
+    assert_eq!(grid[2][3], 0);
+    assert!(is_connected(&grid));
+}
+
+#[test]
+fn split_grids_get_a_tunnel() {
+    let mut grid: Vec<Vec<u8>> = [
+        "#######", //
+        "#..#..#", //
+        "####..#", //
+        "#.#####", //
+        "#######",
+    ]
+    .iter()
+    .map(|row| row.bytes().map(|b| u8::from(b == b'#')).collect())
+    .collect();
+    assert!(!is_connected(&grid));
+    connect_regions(&mut grid);
+    assert!(is_connected(&grid));
+    assert!(border_intact(&grid));
+}
+
+#[test]
+fn room_flags_are_parsed() {
+    let mut args: Vec<String> = ["--rooms", "3", "--plazas", "1:8-10", "--wide", "2:5-5"]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+    let cfg = MapConfig::take_from_args(&mut args).unwrap();
+    assert!(args.is_empty());
+    assert_eq!(cfg.rooms.rooms.count, 3);
+    assert_eq!(cfg.rooms.rooms.min, RoomConfig::default().rooms.min);
+    assert_eq!(
+        cfg.rooms.plazas,
+        Carve {
+            count: 1,
+            min: 8,
+            max: 10
+        }
+    );
+    assert_eq!(cfg.rooms.wide_corridors.count, 2);
+
+    for bad in ["x", "2:5", "2:6-3", "2:0-3"] {
+        let mut args = vec!["--rooms".to_string(), bad.to_string()];
+        assert!(MapConfig::take_from_args(&mut args).is_err(), "{bad}");
+    }
+}
@@ -0,0 +1,660 @@
+// drives a real server over 127.0.0.1, ticking it by hand so the test is deterministic
+
+mod common;
+
+use common::{corridor, corridor_server};
+use game::config::MapConfig;
+use game::health::{MAX_HEALTH, RESPAWN_DELAY, SPAWN_ARMOR, apply_damage};
+use game::mapfile::MapFile;
+use game::mechanics::PlayerInput;
+use game::prediction::Prediction;
+use game::protocol::{
+    Baselines, ClientMessage, ErrorCode, POSITION_SCALE, PROTOCOL_VERSION, PlayerState,
+    SNAPSHOT_HISTORY, ServerMessage,
+};
+use game::reliable::{Channel, Reliable, WINDOW};
+use game::render::Player;
+use game::server::{CHAT_BURST, Server};
+use game::sprites::Animation;
+use game::tilemap::TileMap;
+use game::weapons::{ProjectileKind, ProjectileState, Weapon};
+use std::cell::{Cell, RefCell};
+use std::net::UdpSocket;
+use std::ops::Deref;
+use std::time::Duration;
+
+fn recv_packet(sock: &UdpSocket) -> Vec<u8> {
+    let mut buf = [0u8; 1400];
+    let len = sock.recv(&mut buf).expect("no packet from server");
+    buf[..len].to_vec()
+}
+
+// snapshots come in full as long as the client never acks one
+fn recv(sock: &UdpSocket) -> ServerMessage {
+    ServerMessage::decode(&recv_packet(sock)).unwrap()
+}
+
+#[test]
+fn join_move_and_leave_over_loopback() {
+    let mut server = corridor_server();
+    let client = UdpSocket::bind("127.0.0.1:0").unwrap();
+    client
+        .set_read_timeout(Some(Duration::from_secs(2)))
+        .unwrap();
+    client.connect(server.local_addr().unwrap()).unwrap();
+
+    client.send(&ClientMessage::join().encode()).unwrap();
+    server.tick(1.0 / 30.0).unwrap();
+    let ServerMessage::Welcome { id, seed, .. } = recv(&client) else {
+        panic!("expected welcome first");
+    };
+    assert_eq!(seed, 7);
+    let ServerMessage::Snapshot { players, .. } = recv(&client) else {
+        panic!("expected a snapshot after welcome");
+    };
+    assert_eq!(players.len(), 1);
+    assert_eq!(players[0].id, id);
+    assert_eq!(players[0].name, format!("player {id}"));
+    assert_eq!((players[0].health, players[0].alive), (100, true));
+    assert_eq!(players[0].animation, Animation::Idle);
+    let start_x = players[0].x;
+
+    let mut last = None;
+    for seq in 1..=10 {
+        client.send(&input(seq, 1.0, false)).unwrap();
+        server.tick(1.0 / 30.0).unwrap();
+        last = Some(recv(&client));
+    }
+    let Some(ServerMessage::Snapshot { players, .. }) = last else {
+        panic!("expected snapshots while moving");
+    };
+    assert!(players[0].x > start_x, "server did not move the player");
+    assert!((players[0].y - 1.5).abs() < 1e-4);
+    assert_eq!(players[0].animation, Animation::Walk);
+    assert_eq!(players[0].ack, 10);
+
+    client.send(&ClientMessage::Leave.encode()).unwrap();
+    server.tick(1.0 / 30.0).unwrap();
+    assert_eq!(server.player_count(), 0);
+}
+
+// a client socket with its end of the reliable channels
+struct Peer {
+    sock: UdpSocket,
+    reliable: RefCell<Reliable>,
+    // reliable packets still to be thrown away as if they got lost
+    drop_reliable: Cell<usize>,
+}
+
+impl Deref for Peer {
+    type Target = UdpSocket;
+
+    fn deref(&self) -> &UdpSocket {
+        &self.sock
+    }
+}
+
+impl Peer {
+    // sends what is due on the reliable channels, acks included
+    fn flush(&self) {
+        if let Some(packet) = self.reliable.borrow_mut().update(1.0 / 30.0) {
+            self.send(&ClientMessage::Reliable(packet).encode())
+                .unwrap();
+        }
+    }
+}
+
+fn connect(server: &Server) -> Peer {
+    let client = UdpSocket::bind("127.0.0.1:0").unwrap();
+    client
+        .set_read_timeout(Some(Duration::from_secs(2)))
+        .unwrap();
+    client.connect(server.local_addr().unwrap()).unwrap();
+    Peer {
+        sock: client,
+        reliable: RefCell::new(Reliable::new()),
+        drop_reliable: Cell::new(0),
+    }
+}
+
+// one frame's worth of input at the tick rate
+fn input(tick: u32, forward: f32, fire: bool) -> Vec<u8> {
+    ClientMessage::Input(PlayerInput {
+        tick,
+        dt: 1.0 / 30.0,
+        forward,
+        buttons: if fire { PlayerInput::FIRE } else { 0 },
+        ..PlayerInput::default()
+    })
+    .encode()
+}
+
+// what one client saw over a few ticks
+#[derive(Default)]
+struct Seen {
+    players: Vec<PlayerState>,
+    projectiles: Vec<ProjectileState>,
+    // from the reliable channels, in the order they were handed out
+    events: Vec<ServerMessage>,
+}
+
+// reads up to and including this tick's snapshot
+fn read_tick(peer: &Peer, seen: &mut Seen) {
+    loop {
+        match recv(peer) {
+            ServerMessage::Snapshot {
+                players,
+                projectiles,
+                ..
+            } => {
+                seen.players = players;
+                seen.projectiles = projectiles;
+                return;
+            }
+            ServerMessage::Reliable(_) if peer.drop_reliable.get() > 0 => {
+                peer.drop_reliable.set(peer.drop_reliable.get() - 1);
+            }
+            ServerMessage::Reliable(packet) => {
+                let mut reliable = peer.reliable.borrow_mut();
+                reliable.receive(&packet);
+                while let Some((_, payload)) = reliable.poll() {
+                    seen.events.push(ServerMessage::decode(&payload).unwrap());
+                }
+            }
+            other => panic!("unexpected {other:?}"),
+        }
+    }
+}
+
+// ticks n times, every client reads its messages and acks, gives back what `reader` saw
+fn tick_for(server: &mut Server, n: usize, reader: &Peer, others: &[&Peer]) -> Seen {
+    let (mut seen, mut ignored) = (Seen::default(), Seen::default());
+    for _ in 0..n {
+        server.tick(1.0 / 30.0).unwrap();
+        for peer in others {
+            read_tick(peer, &mut ignored);
+            peer.flush();
+        }
+        read_tick(reader, &mut seen);
+        reader.flush();
+    }
+    seen
+}
+
+// (shooter id, target id), both joined. the corridor gets a spawn point at each end, the
+// shooter takes (1, 1) and the target the one furthest from it
+fn duel(server: &mut Server) -> (Peer, Peer, (u32, u32)) {
+    let shooter = connect(server);
+    let (shooter_id, _) = join(server, &shooter);
+    let target = connect(server);
+    target.send(&ClientMessage::join().encode()).unwrap();
+    server.tick(1.0 / 30.0).unwrap();
+    // the shooter hears about the target joining
+    let mut seen = Seen::default();
+    read_tick(&shooter, &mut seen);
+    let ServerMessage::Welcome { id, .. } = recv(&target) else {
+        panic!("expected welcome first");
+    };
+    recv(&target);
+    assert_eq!(
+        seen.events,
+        [ServerMessage::Joined {
+            id,
+            name: format!("player {id}")
+        }]
+    );
+    (shooter, target, (shooter_id, id))
+}
+
+fn find(players: &[PlayerState], id: u32) -> PlayerState {
+    players.iter().find(|p| p.id == id).unwrap().clone()
+}
+
+#[test]
+fn shots_are_resolved_by_the_server() {
+    let mut server = corridor_server();
+    let (shooter, target, (shooter_id, id)) = duel(&mut server);
+    let seen = tick_for(&mut server, 1, &target, &[&shooter]);
+    let start = find(&seen.players, id);
+    assert_eq!((start.x, start.y), (6.5, 1.5));
+    assert_eq!(find(&seen.players, shooter_id).x, 1.5);
+    assert_eq!((start.health, start.armor), (MAX_HEALTH, SPAWN_ARMOR));
+
+    // the same hit applied by hand, armor takes part of it
+    let mut expected = Player {
+        health: start.health,
+        armor: start.armor,
+        ..Player::at(start.x, start.y, start.angle)
+    };
+    apply_damage(&mut expected, Weapon::Pistol.damage());
+
+    // the first shot goes off right away, the next ones wait for the pistol cooldown
+    shooter.send(&input(1, 0.0, true)).unwrap();
+    let seen = tick_for(&mut server, 1, &target, &[&shooter]);
+    let hurt = find(&seen.players, id);
+    assert_eq!((hurt.health, hurt.armor), (expected.health, expected.armor));
+    let seen = tick_for(&mut server, 5, &target, &[&shooter]);
+    assert_eq!(find(&seen.players, id).health, expected.health);
+
+    let seen = tick_for(&mut server, 60, &target, &[&shooter]);
+    shooter.send(&input(2, 0.0, false)).unwrap();
+    let dead = find(&seen.players, id);
+    assert_eq!((dead.health, dead.alive), (0, false));
+    assert_eq!(dead.animation, Animation::Dead);
+    let kill = ServerMessage::Kill {
+        killer: shooter_id,
+        victim: id,
+        weapon: Weapon::Pistol,
+    };
+    assert_eq!(seen.events, [kill]);
+
+    // the dead can't walk away
+    target.send(&input(1, -1.0, false)).unwrap();
+    let seen = tick_for(&mut server, 10, &target, &[&shooter]);
+    assert_eq!(find(&seen.players, id).x, dead.x);
+    target.send(&input(2, 0.0, false)).unwrap();
+
+    // and come back with full health on the spawn point after the delay
+    let ticks = (RESPAWN_DELAY * 30.0) as usize;
+    let seen = tick_for(&mut server, ticks, &target, &[&shooter]);
+    let back = find(&seen.players, id);
+    assert!(back.alive);
+    assert_eq!((back.health, back.armor), (MAX_HEALTH, SPAWN_ARMOR));
+    // (1, 1) is where the shooter stands, so the far end again
+    assert_eq!((back.x, back.y), (6.5, 1.5));
+}
+
+#[test]
+fn rockets_fly_in_snapshots_and_splash() {
+    let mut server = corridor_server();
+    let (shooter, target, (_, id)) = duel(&mut server);
+    let select = ClientMessage::SelectWeapon {
+        weapon: Weapon::Rocket,
+    };
+    shooter.send(&select.encode()).unwrap();
+    shooter.send(&input(1, 0.0, true)).unwrap();
+    let seen = tick_for(&mut server, 1, &target, &[&shooter]);
+    shooter.send(&input(2, 0.0, false)).unwrap();
+    // launched but still on its way
+    assert_eq!(seen.projectiles.len(), 1);
+    assert_eq!(seen.projectiles[0].kind, ProjectileKind::Rocket);
+    assert_eq!(find(&seen.players, id).health, 100);
+
+    let seen = tick_for(&mut server, 30, &target, &[&shooter]);
+    assert!(seen.projectiles.is_empty());
+    let hurt = find(&seen.players, id).health;
+    assert!(hurt < 100 && hurt > 0, "health {hurt}");
+}
+
+// joins and reads the welcome and the first snapshot, gives back our id and where we stand
+fn join(server: &mut Server, client: &UdpSocket) -> (u32, PlayerState) {
+    client.send(&ClientMessage::join().encode()).unwrap();
+    server.tick(1.0 / 30.0).unwrap();
+    let ServerMessage::Welcome { id, .. } = recv(client) else {
+        panic!("expected welcome first");
+    };
+    let ServerMessage::Snapshot { players, .. } = recv(client) else {
+        panic!("expected a snapshot after welcome");
+    };
+    (id, find(&players, id))
+}
+
+#[test]
+fn old_and_repeated_inputs_are_skipped() {
+    let mut server = corridor_server();
+    let client = connect(&server);
+    let (id, start) = join(&mut server, &client);
+    client.send(&input(2, 1.0, false)).unwrap();
+    server.tick(1.0 / 30.0).unwrap();
+    let ServerMessage::Snapshot { players, .. } = recv(&client) else {
+        panic!("expected a snapshot");
+    };
+    let moved = find(&players, id);
+    assert!(moved.x > start.x);
+    assert_eq!(moved.ack, 2);
+
+    // the same packet twice and one that got overtaken
+    client.send(&input(2, 1.0, false)).unwrap();
+    client.send(&input(1, 1.0, false)).unwrap();
+    server.tick(1.0 / 30.0).unwrap();
+    let ServerMessage::Snapshot { players, .. } = recv(&client) else {
+        panic!("expected a snapshot");
+    };
+    assert_eq!(find(&players, id), moved);
+}
+
+#[test]
+fn inputs_cannot_move_faster_than_real_time() {
+    let mut server = corridor_server();
+    let client = connect(&server);
+    let (id, start) = join(&mut server, &client);
+    // a second of walking crammed into one tick
+    for tick in 1..=10 {
+        let msg = ClientMessage::Input(PlayerInput {
+            tick,
+            dt: 0.1,
+            forward: 1.0,
+            ..PlayerInput::default()
+        });
+        client.send(&msg.encode()).unwrap();
+    }
+    server.tick(1.0 / 30.0).unwrap();
+    let ServerMessage::Snapshot { players, .. } = recv(&client) else {
+        panic!("expected a snapshot");
+    };
+    let p = find(&players, id);
+    assert_eq!(p.ack, 10);
+    // one tick of walking at 1.5 tiles per second
+    assert!(
+        p.x - start.x <= 1.5 / 30.0 + 1.0 / POSITION_SCALE,
+        "moved {}",
+        p.x - start.x
+    );
+}
+
+#[test]
+fn prediction_agrees_with_the_server() {
+    let mut server = corridor_server();
+    let client = connect(&server);
+    let (id, start) = join(&mut server, &client);
+    let tiles = TileMap::from_grid(&corridor());
+    let mut player = Player {
+        health: start.health,
+        armor: start.armor,
+        ..Player::at(start.x, start.y, start.angle)
+    };
+    let mut prediction = Prediction::new();
+    // walking and strafing into the wall while turning a bit, three frames per tick with
+    // the last one still on its way when the snapshot comes back
+    let mut tick = 0;
+    let mut frame = |prediction: &mut Prediction, player: &mut Player| {
+        tick += 1;
+        let input = PlayerInput {
+            tick,
+            dt: 1.0 / 90.0,
+            forward: 1.0,
+            strafe: 1.0,
+            yaw: 0.01,
+            ..PlayerInput::default()
+        };
+        let msg = prediction.predict(player, &tiles, input);
+        client.send(&msg.encode()).unwrap();
+    };
+    for _ in 0..10 {
+        frame(&mut prediction, &mut player);
+        frame(&mut prediction, &mut player);
+        server.tick(1.0 / 30.0).unwrap();
+        let ServerMessage::Snapshot { players, .. } = recv(&client) else {
+            panic!("expected a snapshot");
+        };
+        frame(&mut prediction, &mut player);
+        let predicted = prediction.view(&player);
+        prediction.reconcile(&mut player, &tiles, &find(&players, id));
+        assert_eq!(prediction.pending().len(), 1);
+        let drawn = prediction.view(&player);
+        assert!((drawn.x - predicted.x).abs() < 1e-5);
+        // all that is left is the position being rounded for the wire
+        assert!(prediction.offset.0.hypot(prediction.offset.1) < 1.0 / POSITION_SCALE);
+    }
+    assert!(player.x > start.x + 0.3);
+    // slid along the wall without going into it
+    assert!(player.y > start.y && player.y <= 1.8 + 1e-4);
+}
+
+#[test]
+fn snapshots_become_deltas_once_acked() {
+    let mut server = corridor_server();
+    let acking = connect(&server);
+    let (id, start) = join(&mut server, &acking);
+    let lazy = connect(&server);
+    lazy.send(&ClientMessage::join().encode()).unwrap();
+    let mut baselines = Baselines::new();
+    let mut last = Vec::new();
+    for seq in 1..=20 {
+        acking.send(&input(seq, 1.0, false)).unwrap();
+        server.tick(1.0 / 30.0).unwrap();
+        // past the join notice for the other one
+        let (tick, players) = loop {
+            let msg = baselines.decode(&recv_packet(&acking)).unwrap();
+            if let ServerMessage::Snapshot { tick, players, .. } = msg {
+                break (tick, players);
+            }
+        };
+        acking
+            .send(&ClientMessage::SnapshotAck { tick }.encode())
+            .unwrap();
+        // the other one never acks and keeps getting full snapshots of the same world
+        let full = loop {
+            if let ServerMessage::Snapshot { players, .. } = recv(&lazy) {
+                break players;
+            }
+        };
+        assert_eq!(players, full);
+        last = players;
+    }
+    assert_eq!(last.len(), 2);
+    assert!(find(&last, id).x > start.x);
+
+    let stats = server.bandwidth();
+    let (acked, full) = (stats[0].1, stats[1].1);
+    assert_eq!(stats[0].0, id);
+    // the first two went out before the first ack came back
+    assert_eq!((acked.snapshots, acked.deltas), (21, 19));
+    assert!(acked.snapshot_bytes * 2 < acked.full_bytes, "{acked:?}");
+    assert_eq!((full.snapshots, full.deltas), (20, 0));
+    assert_eq!(full.snapshot_bytes, full.full_bytes);
+    // plus the welcome
+    assert_eq!(full.packets, 21);
+    assert!(full.bytes > full.snapshot_bytes);
+}
+
+#[test]
+fn an_ack_too_old_gets_a_full_snapshot() {
+    let mut server = corridor_server();
+    let client = connect(&server);
+    join(&mut server, &client);
+    let mut baselines = Baselines::new();
+    server.tick(1.0 / 30.0).unwrap();
+    let ServerMessage::Snapshot { tick, .. } = baselines.decode(&recv_packet(&client)).unwrap()
+    else {
+        panic!("expected a snapshot");
+    };
+    client
+        .send(&ClientMessage::SnapshotAck { tick }.encode())
+        .unwrap();
+    // no more acks, deltas against that one for as long as the server keeps it
+    for _ in 0..SNAPSHOT_HISTORY {
+        server.tick(1.0 / 30.0).unwrap();
+        let packet = recv_packet(&client);
+        assert!(ServerMessage::decode(&packet).is_err());
+        baselines.decode(&packet).unwrap();
+    }
+    server.tick(1.0 / 30.0).unwrap();
+    assert!(matches!(recv(&client), ServerMessage::Snapshot { .. }));
+}
+
+#[test]
+fn chat_and_leaves_get_through_lost_packets() {
+    let mut server = corridor_server();
+    let (a, b, (a_id, _)) = duel(&mut server);
+    let say = ClientMessage::Say {
+        text: "  hello\n".to_string(),
+    };
+    a.reliable
+        .borrow_mut()
+        .send(Channel::Chat, say.encode())
+        .unwrap();
+    // lost on the way to the server
+    a.reliable.borrow_mut().update(1.0 / 30.0).unwrap();
+    let seen = tick_for(&mut server, 10, &b, &[&a]);
+    let chat = ServerMessage::Chat {
+        from: a_id,
+        text: "hello".to_string(),
+    };
+    assert_eq!(seen.events, [chat]);
+
+    // the leave notice is lost twice on the way to b
+    a.send(&ClientMessage::Leave.encode()).unwrap();
+    b.drop_reliable.set(2);
+    let seen = tick_for(&mut server, 15, &b, &[]);
+    assert_eq!(b.drop_reliable.get(), 0);
+    assert_eq!(seen.events, [ServerMessage::Left { id: a_id }]);
+}
+
+fn say(n: usize) -> Vec<u8> {
+    ClientMessage::Say {
+        text: format!("line {n}"),
+    }
+    .encode()
+}
+
+#[test]
+fn chat_is_rate_limited() {
+    let mut server = corridor_server();
+    let (a, b, _) = duel(&mut server);
+    for n in 0..20 {
+        a.send(&say(n)).unwrap();
+    }
+    let seen = tick_for(&mut server, 10, &b, &[&a]);
+    assert_eq!(seen.events.len(), CHAT_BURST as usize);
+    // and another line a second after that
+    let seen = tick_for(&mut server, 30, &b, &[&a]);
+    assert!(seen.events.is_empty());
+    a.send(&say(20)).unwrap();
+    let seen = tick_for(&mut server, 5, &b, &[&a]);
+    assert_eq!(seen.events.len(), 1);
+}
+
+#[test]
+fn chat_stops_queueing_for_a_client_that_never_acks() {
+    let mut server = corridor_server();
+    let (a, b, _) = duel(&mut server);
+    b.drop_reliable.set(usize::MAX);
+    let (mut ignored, mut lost) = (Seen::default(), Seen::default());
+    // a line a second is let through, well over a window of them
+    for n in 0..(WINDOW as usize + 10) * 30 {
+        a.send(&say(n)).unwrap();
+        // keeps b from timing out
+        b.send(&input(n as u32 + 1, 0.0, false)).unwrap();
+        server.tick(1.0 / 30.0).unwrap();
+        read_tick(&a, &mut ignored);
+        a.flush();
+        read_tick(&b, &mut lost);
+    }
+    assert!(ignored.events.len() > WINDOW as usize);
+    b.drop_reliable.set(0);
+    let seen = tick_for(&mut server, 10, &b, &[&a]);
+    // the oldest ones, the rest never got queued
+    assert_eq!(seen.events, ignored.events[..WINDOW as usize]);
+}
+
+#[test]
+fn a_map_change_comes_as_a_new_welcome() {
+    let mut server = corridor_server();
+    let (a, b, (a_id, b_id)) = duel(&mut server);
+    // the same corridor standing up
+    let grid: Vec<Vec<u8>> = (0..8)
+        .map(|y| vec![1, u8::from(y == 0 || y == 7), 1])
+        .collect();
+    let mut level = MapFile::from_grid("", "", &grid);
+    level.add_auto_spawns();
+    let map = MapConfig {
+        seed: 9,
+        width: 1,
+        height: 3,
+        ..MapConfig::default()
+    };
+    server.change_map(map, level);
+    b.drop_reliable.set(1);
+    let seen = tick_for(&mut server, 10, &b, &[&a]);
+    let [ServerMessage::Welcome { id, seed, .. }] = seen.events.as_slice() else {
+        panic!("expected one welcome, got {:?}", seen.events);
+    };
+    assert_eq!((*id, *seed), (b_id, 9));
+    // both on the new map, apart from each other
+    let (pa, pb) = (find(&seen.players, a_id), find(&seen.players, b_id));
+    assert_eq!((pa.x, pb.x), (1.5, 1.5));
+    assert!((pa.y - pb.y).abs() > 3.0);
+}
+
+#[test]
+fn maps_rotate_to_the_next_seed() {
+    let mut server = corridor_server();
+    let (a, b, (_, b_id)) = duel(&mut server);
+    server.rotate_maps(0.5).unwrap();
+    let seen = tick_for(&mut server, 20, &b, &[&a]);
+    let [ServerMessage::Welcome { id, seed, .. }] = seen.events.as_slice() else {
+        panic!("expected one welcome, got {:?}", seen.events);
+    };
+    assert_eq!((*id, *seed), (b_id, 8));
+    assert_eq!(server.map().seed, 8);
+}
+
+#[test]
+fn names_are_cleaned_up() {
+    let mut server = corridor_server();
+    let client = UdpSocket::bind("127.0.0.1:0").unwrap();
+    client
+        .set_read_timeout(Some(Duration::from_secs(2)))
+        .unwrap();
+    client.connect(server.local_addr().unwrap()).unwrap();
+    client.send(&ClientMessage::join().encode()).unwrap();
+    server.tick(1.0 / 30.0).unwrap();
+    let ServerMessage::Welcome { id, .. } = recv(&client) else {
+        panic!("expected welcome first");
+    };
+    recv(&client);
+
+    let mut name_after = |name: &str| {
+        let msg = ClientMessage::SetName {
+            name: name.to_string(),
+        };
+        client.send(&msg.encode()).unwrap();
+        server.tick(1.0 / 30.0).unwrap();
+        let ServerMessage::Snapshot { players, .. } = recv(&client) else {
+            panic!("expected a snapshot");
+        };
+        players[0].name.clone()
+    };
+    assert_eq!(name_after("  alice\n"), "alice");
+    assert_eq!(name_after("\t\u{7}"), format!("player {id}"));
+    assert_eq!(name_after(&"ü".repeat(20)), "ü".repeat(8));
+}
+
+#[test]
+fn mismatched_version_is_rejected() {
+    let mut server = corridor_server();
+    let client = UdpSocket::bind("127.0.0.1:0").unwrap();
+    client
+        .set_read_timeout(Some(Duration::from_secs(2)))
+        .unwrap();
+    client.connect(server.local_addr().unwrap()).unwrap();
+
+    let join = ClientMessage::Join {
+        version: PROTOCOL_VERSION + 1,
+    };
+    client.send(&join.encode()).unwrap();
+    server.tick(1.0 / 30.0).unwrap();
+    let ServerMessage::Error { code, message } = recv(&client) else {
+        panic!("expected an error for the wrong version");
+    };
+    assert_eq!(code, ErrorCode::VersionMismatch);
+    assert!(message.contains(&format!("v{}", PROTOCOL_VERSION + 1)));
+    assert_eq!(server.player_count(), 0);
+}
+
+#[test]
+fn ping_is_answered_with_pong() {
+    let mut server = corridor_server();
+    let client = UdpSocket::bind("127.0.0.1:0").unwrap();
+    client
+        .set_read_timeout(Some(Duration::from_secs(2)))
+        .unwrap();
+    client.connect(server.local_addr().unwrap()).unwrap();
+
+    client
+        .send(&ClientMessage::Ping { nonce: 42 }.encode())
+        .unwrap();
+    server.tick(1.0 / 30.0).unwrap();
+    assert_eq!(recv(&client), ServerMessage::Pong { nonce: 42 });
+}
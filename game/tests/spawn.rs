@@ -0,0 +1,157 @@
+use game::config::MapConfig;
+use game::mapfile::MapFile;
+use game::spawn::{MAX_SPAWNS, auto_spawns, choose_spawn, is_open, pick_spawns, walk_distances};
+use game::tilemap::{Tile, TileMap};
+
+// 8x3 corridor
+fn corridor() -> TileMap {
+    TileMap::from_grid(&[
+        vec![1, 1, 1, 1, 1, 1, 1, 1],
+        vec![1, 0, 0, 0, 0, 0, 0, 1],
+        vec![1, 1, 1, 1, 1, 1, 1, 1],
+    ])
+}
+
+#[test]
+fn the_spawn_furthest_from_enemies_wins() {
+    let map = corridor();
+    let spawns = [(1, 1), (3, 1), (6, 1)];
+    assert_eq!(choose_spawn(&map, &spawns, &[(1.5, 1.5)], &[]), (6.5, 1.5));
+    assert_eq!(choose_spawn(&map, &spawns, &[(6.0, 1.5)], &[]), (1.5, 1.5));
+    // the closest enemy counts, not the average
+    assert_eq!(
+        choose_spawn(&map, &spawns, &[(1.5, 1.5), (6.5, 1.5)], &[]),
+        (3.5, 1.5)
+    );
+    // nobody around, the first one
+    assert_eq!(choose_spawn(&map, &spawns, &[], &[]), (1.5, 1.5));
+}
+
+#[test]
+fn spawns_only_land_on_open_floor() {
+    let mut map = corridor();
+    *map.get_mut(6, 1).unwrap() = Tile::Hazard;
+    assert!(!is_open(&map, 6, 1));
+    assert!(!is_open(&map, 0, 1));
+    assert!(!is_open(&map, 50, 50));
+    assert!(is_open(&map, 3, 1));
+    // a wall, lava and a point off the map are never picked, even far from the enemy
+    let spawns = [(0, 1), (6, 1), (70, 1), (2, 1)];
+    assert_eq!(choose_spawn(&map, &spawns, &[(1.5, 1.5)], &[]), (2.5, 1.5));
+    // without any usable spawn point the first open cell will do
+    assert_eq!(choose_spawn(&map, &[(0, 0)], &[], &[]), (1.5, 1.5));
+    assert_eq!(choose_spawn(&map, &[], &[], &[]), (1.5, 1.5));
+}
+
+#[test]
+fn walls_cannot_become_spawn_points() {
+    let mut level = MapFile::from_grid("", "", &[vec![1, 1, 1], vec![1, 0, 1], vec![1, 1, 1]]);
+    level.add_spawn(0, 0);
+    level.add_spawn(1, 1);
+    assert_eq!(level.spawns, [(1, 1)]);
+    assert_eq!(level.tiles.get(0, 0), Some(Tile::Wall(1)));
+    assert_eq!(level.tiles.get(1, 1), Some(Tile::Spawn));
+}
+
+#[test]
+fn nobody_spawns_inside_another_player() {
+    let map = corridor();
+    let spawns = [(1, 1), (6, 1)];
+    // a teammate on the far spawn isn't an enemy but still takes it
+    let enemy = (3.0, 1.5);
+    assert_eq!(
+        choose_spawn(&map, &spawns, &[enemy], &[enemy, (6.4, 1.5)]),
+        (1.5, 1.5)
+    );
+    // a bit further along the corridor leaves the spawn free again
+    assert_eq!(
+        choose_spawn(&map, &spawns, &[enemy], &[enemy, (5.9, 1.5)]),
+        (6.5, 1.5)
+    );
+    // every spawn point taken, the safest free cell instead
+    assert_eq!(
+        choose_spawn(&map, &spawns, &[(1.5, 1.5)], &[(1.5, 1.5), (6.5, 1.5)]),
+        (5.5, 1.5)
+    );
+}
+
+#[test]
+fn walking_distance_goes_around_walls() {
+    // a u shaped corridor, the two ends are close but six steps apart
+    let map = TileMap::from_grid(&[
+        vec![1, 1, 1, 1, 1],
+        vec![1, 0, 1, 0, 1],
+        vec![1, 0, 1, 0, 1],
+        vec![1, 0, 0, 0, 1],
+        vec![1, 1, 1, 1, 1],
+        vec![1, 0, 1, 1, 1],
+    ]);
+    let dist = walk_distances(&map, &[(1, 1)]);
+    assert_eq!(dist[1][3], Some(6));
+    assert_eq!(dist[3][2], Some(3));
+    assert_eq!(dist[0][0], None);
+    // cut off from the rest
+    assert_eq!(dist[5][1], None);
+    // the closest source counts
+    let dist = walk_distances(&map, &[(1, 1), (3, 1)]);
+    assert_eq!(dist[3][2], Some(3));
+    assert_eq!(dist[3][3], Some(2));
+}
+
+#[test]
+fn picked_spawns_spread_out() {
+    let map = corridor();
+    assert_eq!(pick_spawns(&map, 1), [(1, 1)]);
+    assert_eq!(pick_spawns(&map, 2), [(1, 1), (6, 1)]);
+    assert_eq!(pick_spawns(&map, 3), [(1, 1), (6, 1), (3, 1)]);
+    // never more than there is floor
+    assert_eq!(pick_spawns(&map, 20).len(), 6);
+    assert!(pick_spawns(&TileMap::from_grid(&[vec![1, 1]]), 4).is_empty());
+}
+
+#[test]
+fn spawns_go_to_the_biggest_stretch_of_floor() {
+    // a walled off pocket at the top comes first in reading order
+    let map = TileMap::from_grid(&[
+        vec![1, 1, 1, 1, 1, 1, 1, 1],
+        vec![1, 0, 0, 1, 1, 1, 1, 1],
+        vec![1, 1, 1, 1, 1, 1, 1, 1],
+        vec![1, 0, 0, 0, 0, 0, 0, 1],
+        vec![1, 1, 1, 1, 1, 1, 1, 1],
+    ]);
+    assert_eq!(pick_spawns(&map, 2), [(1, 3), (6, 3)]);
+    assert!(pick_spawns(&map, 10).iter().all(|&(_, y)| y == 3));
+}
+
+#[test]
+fn generated_mazes_get_spawns_spread_over_the_floor() {
+    let cfg = MapConfig {
+        seed: 3,
+        width: 12,
+        height: 10,
+        ..MapConfig::default()
+    };
+    let level = cfg.build_level().unwrap();
+    let tiles = TileMap::from_grid(&cfg.build_grid());
+    assert_eq!(level.spawns, auto_spawns(&tiles));
+    assert!(level.spawns.len() > 2 && level.spawns.len() <= MAX_SPAWNS);
+    assert_eq!(level.spawns[0], (1, 1));
+    for &(x, y) in &level.spawns {
+        assert_eq!(level.tiles.get(x, y), Some(Tile::Spawn));
+    }
+    // no two of them next to each other
+    for (i, &a) in level.spawns.iter().enumerate() {
+        let dist = walk_distances(&tiles, &[a]);
+        for &(x, y) in &level.spawns[i + 1..] {
+            assert!(dist[y][x].unwrap() >= 4, "{a:?} and {:?}", (x, y));
+        }
+    }
+}
+
+#[test]
+fn authored_spawns_are_kept() {
+    let mut level =
+        MapFile::parse("name: a\nauthor: b\nversion: 1\n---\n#####\n#..S#\n#####\n").unwrap();
+    level.add_auto_spawns();
+    assert_eq!(level.spawns, [(3, 1)]);
+}
@@ -0,0 +1,209 @@
+use game::render::{Player, draw_sprites};
+use game::sprites::{PLAYER_SPRITE, ROTATIONS, Sprite, SpriteSheet, animation_frame, rotation};
+use game::textures::{Texture, TextureSet};
+use std::f32::consts::{FRAC_PI_2, PI};
+
+const W: usize = 64;
+const H: usize = 48;
+
+fn sprite_at(x: f32, y: f32, angle: f32) -> Sprite {
+    Sprite {
+        x,
+        y,
+        angle,
+        sheet: PLAYER_SPRITE,
+        frame: 0,
+    }
+}
+
+// 2 rotations of 2x2 frames, 3 frames high, every pixel holds its own position
+fn numbered_sheet() -> Texture {
+    Texture {
+        width: 4,
+        height: 6,
+        pixels: (0..24).collect(),
+    }
+}
+
+fn textures() -> TextureSet {
+    TextureSet::load_dir(std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("textures")).unwrap()
+}
+
+// sprites over an empty (all 0) view from (1.5, 1.5) looking east, anything the sprites
+// leave alone stays 0
+fn draw(sprites: &[Sprite], zbuffer: &[f32]) -> Vec<u32> {
+    let p = Player::at(1.5, 1.5, 0.0);
+    let mut fb = vec![0; W * H];
+    draw_sprites(
+        &mut fb,
+        &mut Vec::new(),
+        W,
+        H,
+        zbuffer,
+        &textures(),
+        &p,
+        sprites,
+    );
+    fb
+}
+
+fn no_walls() -> Vec<f32> {
+    vec![f32::INFINITY; W]
+}
+
+// 2 tiles ahead, 0.3 to the side: 2.02 away in a straight line but nearer the camera
+// than B, which is 2.01 straight ahead
+const A: (f32, f32) = (3.5, 1.8);
+const B: (f32, f32) = (3.51, 1.5);
+// as deep as A, on the other side
+const C: (f32, f32) = (3.5, 1.2);
+
+#[test]
+fn nearer_to_the_camera_wins_over_nearer_in_a_straight_line() {
+    let (a, b) = (sprite_at(A.0, A.1, 0.0), sprite_at(B.0, B.1, 0.0));
+    let (only_a, only_b) = (draw(&[a], &no_walls()), draw(&[b], &no_walls()));
+    let both = draw(&[a, b], &no_walls());
+    let overlap: Vec<usize> = (0..W * H)
+        .filter(|&i| only_a[i] != 0 && only_b[i] != 0)
+        .collect();
+    assert!(!overlap.is_empty());
+    for i in overlap {
+        assert_eq!(both[i], only_a[i], "pixel {i}");
+    }
+}
+
+#[test]
+fn slice_order_does_not_matter() {
+    let [a, b, c] = [A, B, C].map(|(x, y)| sprite_at(x, y, 0.0));
+    let expected = draw(&[a, b, c], &no_walls());
+    for order in [[a, c, b], [b, a, c], [b, c, a], [c, a, b], [c, b, a]] {
+        assert_eq!(draw(&order, &no_walls()), expected);
+    }
+}
+
+#[test]
+fn far_sprites_show_through_the_holes_of_near_ones() {
+    let near = sprite_at(3.5, 1.5, 0.0);
+    let far = sprite_at(5.5, 1.5, 0.0);
+    let (only_near, only_far) = (draw(&[near], &no_walls()), draw(&[far], &no_walls()));
+    let both = draw(&[near, far], &no_walls());
+    let through = (0..W * H)
+        .filter(|&i| only_near[i] == 0 && only_far[i] != 0)
+        .inspect(|&i| assert_eq!(both[i], only_far[i]))
+        .count();
+    assert!(through > 0);
+}
+
+#[test]
+fn walls_hide_sprites_column_by_column() {
+    // a wall 1 tile away covers the left half of the view
+    let mut zbuffer = no_walls();
+    zbuffer[..W / 2].fill(1.0);
+    let fb = draw(&[sprite_at(B.0, B.1, 0.0)], &zbuffer);
+    let drawn_in =
+        |cols: std::ops::Range<usize>| (0..H).any(|y| cols.clone().any(|x| fb[y * W + x] != 0));
+    assert!(!drawn_in(0..W / 2));
+    assert!(drawn_in(W / 2..W));
+    // a wall behind the sprite doesn't hide it
+    zbuffer[..W / 2].fill(3.0);
+    assert_eq!(
+        draw(&[sprite_at(B.0, B.1, 0.0)], &zbuffer),
+        draw(&[sprite_at(B.0, B.1, 0.0)], &no_walls())
+    );
+}
+
+#[test]
+fn sprites_behind_the_camera_are_skipped() {
+    let fb = draw(
+        &[sprite_at(0.5, 1.5, 0.0), sprite_at(1.5, 1.5, 0.0)],
+        &no_walls(),
+    );
+    assert!(fb.iter().all(|&px| px == 0));
+}
+
+#[test]
+fn sheet_splits_into_rotations_and_frames() {
+    let sheet = SpriteSheet::new(numbered_sheet(), 2).unwrap();
+    assert_eq!((sheet.frame_size, sheet.rotations, sheet.frames), (2, 2, 3));
+    assert_eq!(sheet.texel(0, 0, 0, 0), 0);
+    assert_eq!(sheet.texel(0, 1, 1, 0), 3);
+    assert_eq!(sheet.texel(2, 1, 1, 1), 23);
+    // frames and rotations wrap around
+    assert_eq!(sheet.texel(3, 2, 0, 0), sheet.texel(0, 0, 0, 0));
+}
+
+#[test]
+fn sheets_that_dont_split_are_errors() {
+    assert!(SpriteSheet::new(numbered_sheet(), 3).is_err());
+    assert!(SpriteSheet::new(numbered_sheet(), 0).is_err());
+    // 4 wide in 4 rotations is 1px frames, 6 of them, fine
+    assert_eq!(SpriteSheet::new(numbered_sheet(), 4).unwrap().frames, 6);
+    let tall = Texture {
+        width: 4,
+        height: 5,
+        pixels: vec![0; 20],
+    };
+    assert!(SpriteSheet::new(tall, 2).is_err());
+}
+
+#[test]
+fn rotation_follows_the_viewer() {
+    // facing east
+    let s = sprite_at(5.0, 5.0, 0.0);
+    // viewer in front of it
+    assert_eq!(rotation(&s, 9.0, 5.0, ROTATIONS), 0);
+    // behind
+    assert_eq!(rotation(&s, 1.0, 5.0, ROTATIONS), 4);
+    // south of it, on its right side
+    assert_eq!(rotation(&s, 5.0, 9.0, ROTATIONS), 2);
+    assert_eq!(rotation(&s, 5.0, 1.0, ROTATIONS), 6);
+    assert_eq!(rotation(&s, 8.0, 8.0, ROTATIONS), 1);
+    // right at the edge of the front slice still rounds to it
+    assert_eq!(rotation(&s, 9.0, 4.0, ROTATIONS), 0);
+    assert_eq!(rotation(&s, 5.0, 9.0, 1), 0);
+}
+
+#[test]
+fn rotation_turns_with_the_sprite() {
+    let viewer = (9.0, 5.0);
+    for (angle, expected) in [(0.0, 0), (FRAC_PI_2, 6), (PI, 4), (-FRAC_PI_2, 2), (-PI, 4)] {
+        let s = sprite_at(5.0, 5.0, angle);
+        assert_eq!(
+            rotation(&s, viewer.0, viewer.1, ROTATIONS),
+            expected,
+            "{angle}"
+        );
+    }
+    // a few full turns don't matter
+    let s = sprite_at(5.0, 5.0, 6.0 * PI + FRAC_PI_2);
+    assert_eq!(rotation(&s, viewer.0, viewer.1, ROTATIONS), 6);
+}
+
+#[test]
+fn animation_loops() {
+    assert_eq!(animation_frame(0.0, 8.0, 4), 0);
+    assert_eq!(animation_frame(0.13, 8.0, 4), 1);
+    assert_eq!(animation_frame(0.5, 8.0, 4), 0);
+    assert_eq!(animation_frame(0.9, 8.0, 4), 3);
+    assert_eq!(animation_frame(-1.0, 8.0, 4), 0);
+    assert_eq!(animation_frame(3.0, 8.0, 0), 0);
+}
+
+#[test]
+fn player_sheet_has_every_direction() {
+    let set = textures();
+    let sheet = set.sprite(PLAYER_SPRITE).unwrap();
+    assert_eq!(sheet.rotations, ROTATIONS);
+    assert!(sheet.frames > 1);
+    let column = |rot| {
+        let size = sheet.frame_size;
+        (0..size * size)
+            .map(|i| sheet.texel(0, rot, i % size, i / size))
+            .collect::<Vec<_>>()
+    };
+    assert_ne!(column(0), column(4));
+    assert_ne!(column(2), column(6));
+    // the corners are see-through
+    assert_eq!(sheet.texel(0, 0, 0, 0) & 0xff, 0);
+    assert!(set.sprite(200).is_none());
+}
@@ -0,0 +1,109 @@
+use game::textures::{FLAT_FILES, Texture, TextureSet, WALL_FILES, texture_x};
+use game::tilemap::{FLOOR_TEXTURE, Tile};
+
+fn textures_dir() -> std::path::PathBuf {
+    std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("textures")
+}
+
+fn encode(w: u32, h: u32, color: png::ColorType, data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    let mut enc = png::Encoder::new(&mut out, w, h);
+    enc.set_color(color);
+    enc.set_depth(png::BitDepth::Eight);
+    enc.write_header().unwrap().write_image_data(data).unwrap();
+    out
+}
+
+#[test]
+fn every_wall_texture_loads() {
+    let set = TextureSet::load_dir(textures_dir()).unwrap();
+    for (id, file) in WALL_FILES {
+        let t = set.wall(id);
+        assert_eq!((t.width, t.height), (64, 64), "{file}");
+        assert_eq!(t.pixels.len(), 64 * 64);
+    }
+    assert_eq!(set.walls().count(), WALL_FILES.len());
+}
+
+#[test]
+fn tile_kinds_get_different_textures() {
+    let set = TextureSet::load_dir(textures_dir()).unwrap();
+    let stone = set.wall(Tile::Wall(1).wall_texture().unwrap());
+    let brick = set.wall(Tile::Wall(2).wall_texture().unwrap());
+    let door = set.wall(Tile::Door.wall_texture().unwrap());
+    assert_ne!(stone, brick);
+    assert_ne!(stone, door);
+    // no file for 9, falls back to the plain wall
+    assert_eq!(set.wall(9), stone);
+}
+
+#[test]
+fn floors_ceilings_and_sky_load() {
+    let set = TextureSet::load_dir(textures_dir()).unwrap();
+    for (id, file) in FLAT_FILES {
+        let t = set.flat(id);
+        assert_eq!((t.width, t.height), (64, 64), "{file}");
+    }
+    let floor = set.flat(Tile::Empty.floor_texture());
+    assert_ne!(floor, set.flat(Tile::Hazard.floor_texture()));
+    assert_ne!(floor, set.flat(Tile::Spawn.floor_texture()));
+    let ceiling = set.flat(Tile::Empty.ceiling_texture());
+    assert_ne!(floor, ceiling);
+    assert_ne!(ceiling, set.flat(Tile::Hazard.ceiling_texture()));
+    assert_eq!(ceiling, set.flat(Tile::Spawn.ceiling_texture()));
+    assert_eq!(set.flat(99), set.flat(FLOOR_TEXTURE));
+    // the panorama wraps around a full turn, wider than tall
+    assert!(set.sky().width > set.sky().height);
+}
+
+#[test]
+fn missing_dir_is_an_error() {
+    let err = TextureSet::load_dir(textures_dir().join("nope"))
+        .err()
+        .unwrap();
+    assert!(err.contains("stone.png"), "{err}");
+}
+
+#[test]
+fn decodes_rgb_rgba_and_gray() {
+    let rgb = Texture::decode(&encode(2, 1, png::ColorType::Rgb, &[255, 0, 0, 0, 0, 255])).unwrap();
+    assert_eq!(rgb.pixels, vec![0xff0000ff, 0x0000ffff]);
+
+    let rgba = Texture::decode(&encode(
+        1,
+        2,
+        png::ColorType::Rgba,
+        &[1, 2, 3, 4, 5, 6, 7, 8],
+    ))
+    .unwrap();
+    assert_eq!((rgba.width, rgba.height), (1, 2));
+    assert_eq!(rgba.pixels, vec![0x01020304, 0x05060708]);
+
+    let gray = Texture::decode(&encode(1, 1, png::ColorType::Grayscale, &[0x80])).unwrap();
+    assert_eq!(gray.pixels, vec![0x808080ff]);
+
+    assert!(Texture::decode(b"not a png").is_err());
+}
+
+#[test]
+fn texel_wraps_around() {
+    let t = Texture {
+        width: 2,
+        height: 2,
+        pixels: vec![1, 2, 3, 4],
+    };
+    assert_eq!(t.texel(0, 0), 1);
+    assert_eq!(t.texel(3, 0), 2);
+    assert_eq!(t.texel(2, 3), 3);
+}
+
+#[test]
+fn texture_x_from_the_hit_point() {
+    assert_eq!(texture_x(3.0, 64, false), 0);
+    assert_eq!(texture_x(3.5, 64, false), 32);
+    assert_eq!(texture_x(3.999, 64, false), 63);
+    // seen from the other side the same spot on the wall is the other end of the texture
+    assert_eq!(texture_x(3.0, 64, true), 63);
+    assert_eq!(texture_x(3.999, 64, true), 0);
+    assert_eq!(texture_x(3.25, 64, false) + texture_x(3.25, 64, true), 63);
+}
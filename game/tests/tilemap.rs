@@ -0,0 +1,83 @@
+use game::map::{generate_maze, maze_to_grid};
+use game::mechanics::{PlayerInput, update_player};
+use game::render::Player;
+use game::tilemap::{Tile, TileMap};
+
+fn player_at(x: f32, y: f32) -> Player {
+    Player::at(x, y, 0.0)
+}
+
+// 5x3 room, facing +x from (1.5, 1.5) the player walks into (2, 1)
+fn row_with(tile: Tile) -> TileMap {
+    let mut map = TileMap::from_grid(&[
+        vec![1, 1, 1, 1, 1],
+        vec![1, 0, 0, 0, 1],
+        vec![1, 1, 1, 1, 1],
+    ]);
+    *map.get_mut(2, 1).unwrap() = tile;
+    map
+}
+
+#[test]
+fn tile_properties() {
+    for t in [Tile::Wall(1), Tile::Wall(7), Tile::Door] {
+        assert!(t.is_solid() && t.is_opaque(), "{t:?}");
+        assert!(t.wall_texture().is_some());
+    }
+    assert!(Tile::Window.is_solid());
+    assert!(!Tile::Window.is_opaque());
+    for t in [Tile::Empty, Tile::Spawn, Tile::Hazard] {
+        assert!(!t.is_solid() && !t.is_opaque(), "{t:?}");
+        assert_eq!(t.wall_texture(), None);
+    }
+    assert!(Tile::Hazard.is_hazard());
+    assert_eq!(Tile::Wall(4).wall_texture(), Some(4));
+}
+
+#[test]
+fn from_grid_keeps_the_layout() {
+    let grid = maze_to_grid(&generate_maze(6, 4, 11));
+    let map = TileMap::from_grid(&grid);
+    assert_eq!((map.width(), map.height()), (13, 9));
+    for (x, y, tile) in map.iter() {
+        assert_eq!(tile, Tile::from_u8(grid[y][x]));
+        assert_eq!(tile.is_solid(), grid[y][x] != 0);
+    }
+    assert_eq!(map.iter().count(), 13 * 9);
+}
+
+#[test]
+fn accessors_are_bounds_checked() {
+    let map = row_with(Tile::Empty);
+    assert_eq!(map.get(4, 2), Some(Tile::Wall(1)));
+    assert_eq!(map.get(5, 0), None);
+    assert_eq!(map.get(0, 3), None);
+    assert_eq!(map.at(-1, 0), None);
+    assert_eq!(map.at(0, -1), None);
+    assert_eq!(map.at_point(1.9, 1.1), Some(Tile::Empty));
+    assert_eq!(map.at_point(-0.5, 1.0), None);
+}
+
+#[test]
+fn solid_tiles_block_movement() {
+    for (tile, passes) in [
+        (Tile::Empty, true),
+        (Tile::Spawn, true),
+        (Tile::Hazard, true),
+        (Tile::Window, false),
+        (Tile::Door, false),
+        (Tile::Wall(3), false),
+    ] {
+        let map = row_with(tile);
+        let mut p = player_at(1.5, 1.5);
+        for _ in 0..60 {
+            let walk = PlayerInput {
+                dt: 1.0 / 30.0,
+                forward: 1.0,
+                ..PlayerInput::default()
+            };
+            update_player(&mut p, &map, &walk);
+        }
+        assert_eq!(p.x > 2.0, passes, "{tile:?} ended at x = {}", p.x);
+    }
+}
@@ -0,0 +1,335 @@
+use game::mechanics::RADIUS;
+use game::raycast::solid_distance;
+use game::tilemap::{Tile, TileMap};
+use game::weapons::{
+    Attack, Hit, MAX_PROJECTILES, Projectile, ProjectileKind, Trigger, Weapon, fire, hitscan,
+    ray_circle, splash, update_projectiles,
+};
+use std::f32::consts::{FRAC_PI_2, PI};
+
+// 10 wide corridor, walls all around, a pillar at (6, 1) when asked for
+fn corridor(pillar: bool) -> TileMap {
+    let mut grid = vec![
+        vec![1u8; 10],
+        vec![1, 0, 0, 0, 0, 0, 0, 0, 0, 1],
+        vec![1u8; 10],
+    ];
+    if pillar {
+        grid[1][6] = 1;
+    }
+    TileMap::from_grid(&grid)
+}
+
+#[test]
+fn solid_distance_stops_at_the_first_wall() {
+    let map = corridor(false);
+    let d = solid_distance(&map, 1.5, 1.5, 0.0, 100.0).unwrap();
+    assert!((d - 7.5).abs() < 1e-4, "{d}");
+    let d = solid_distance(&map, 1.5, 1.5, FRAC_PI_2, 100.0).unwrap();
+    assert!((d - 0.5).abs() < 1e-4, "{d}");
+    assert_eq!(solid_distance(&map, 1.5, 1.5, 0.0, 3.0), None);
+}
+
+#[test]
+fn windows_stop_bullets() {
+    let mut map = corridor(false);
+    *map.get_mut(4, 1).unwrap() = Tile::Window;
+    let shot = hitscan(&map, (1.5, 1.5), 0.0, 30.0, &[(2, 6.5, 1.5)]);
+    assert_eq!(shot.hit, None);
+    assert!((shot.x - 4.0).abs() < 1e-4);
+}
+
+#[test]
+fn ray_circle_distances() {
+    // straight on, the ray enters one radius before the center
+    let t = ray_circle((0.0, 0.0), (1.0, 0.0), (5.0, 0.0), 0.5).unwrap();
+    assert!((t - 4.5).abs() < 1e-5);
+    // off to the side and behind both miss
+    assert_eq!(ray_circle((0.0, 0.0), (1.0, 0.0), (5.0, 0.6), 0.5), None);
+    assert_eq!(ray_circle((0.0, 0.0), (1.0, 0.0), (-5.0, 0.0), 0.5), None);
+    // grazing still counts, starting inside hits right away
+    assert!(ray_circle((0.0, 0.0), (1.0, 0.0), (5.0, 0.5), 0.5).is_some());
+    assert_eq!(
+        ray_circle((0.0, 0.0), (1.0, 0.0), (0.1, 0.0), 0.5),
+        Some(0.0)
+    );
+}
+
+#[test]
+fn the_nearest_player_takes_the_bullet() {
+    let map = corridor(false);
+    let targets = [(7, 7.5, 1.5), (3, 4.5, 1.5), (5, 6.0, 1.5)];
+    let shot = hitscan(&map, (1.5, 1.5), 0.0, 30.0, &targets);
+    assert_eq!(shot.hit, Some(3));
+    assert!((shot.x - (4.5 - RADIUS)).abs() < 1e-4);
+    assert!((shot.y - 1.5).abs() < 1e-4);
+    // the other way there is nobody
+    let shot = hitscan(&map, (1.5, 1.5), PI, 30.0, &targets);
+    assert_eq!(shot.hit, None);
+    assert!((shot.x - 1.0).abs() < 1e-4);
+}
+
+#[test]
+fn players_are_circles_not_points() {
+    let map = corridor(false);
+    let just_inside = [(2, 5.5, 1.5 + RADIUS * 0.9)];
+    assert_eq!(
+        hitscan(&map, (1.5, 1.5), 0.0, 30.0, &just_inside).hit,
+        Some(2)
+    );
+    let just_outside = [(2, 5.5, 1.5 + RADIUS * 1.1)];
+    assert_eq!(
+        hitscan(&map, (1.5, 1.5), 0.0, 30.0, &just_outside).hit,
+        None
+    );
+}
+
+#[test]
+fn walls_and_range_protect_players() {
+    let shot = hitscan(&corridor(true), (1.5, 1.5), 0.0, 30.0, &[(2, 7.5, 1.5)]);
+    assert_eq!(shot.hit, None);
+    assert!((shot.x - 6.0).abs() < 1e-4, "stopped at {}", shot.x);
+
+    let shot = hitscan(&corridor(false), (1.5, 1.5), 0.0, 3.0, &[(2, 7.5, 1.5)]);
+    assert_eq!(shot.hit, None);
+    assert!((shot.x - 4.5).abs() < 1e-4, "stopped at {}", shot.x);
+}
+
+#[test]
+fn equal_distance_goes_to_the_lower_id() {
+    let map = corridor(false);
+    let a = hitscan(&map, (1.5, 1.5), 0.0, 30.0, &[(9, 4.5, 1.5), (4, 4.5, 1.5)]);
+    let b = hitscan(&map, (1.5, 1.5), 0.0, 30.0, &[(4, 4.5, 1.5), (9, 4.5, 1.5)]);
+    assert_eq!((a.hit, b.hit), (Some(4), Some(4)));
+}
+
+#[test]
+fn holding_the_trigger_fires_at_the_weapon_rate() {
+    for weapon in [Weapon::Pistol, Weapon::Rifle] {
+        let mut trigger = Trigger {
+            weapon,
+            ..Trigger::default()
+        };
+        let dt = 1.0 / 30.0;
+        let shots = (0..90).filter(|_| trigger.update(true, dt)).count();
+        let expected = (3.0 / weapon.cooldown()).ceil() as usize;
+        assert!(shots.abs_diff(expected) <= 1, "{weapon:?}: {shots} shots");
+    }
+}
+
+#[test]
+fn the_first_shot_is_instant_after_letting_go() {
+    let mut trigger = Trigger::default();
+    assert!(trigger.update(true, 0.01));
+    assert!(!trigger.update(true, 0.01));
+    // releasing doesn't skip the cooldown
+    assert!(!trigger.update(false, 0.01));
+    assert!(!trigger.update(true, 0.01));
+    // but a long pause doesn't bank shots either
+    assert!(!trigger.update(false, 10.0));
+    assert!(trigger.update(true, 0.01));
+    assert!(!trigger.update(true, 0.01));
+}
+
+#[test]
+fn pistol_and_rifle_are_hitscan_rockets_and_grenades_fly() {
+    let kinds: Vec<Attack> = Weapon::ALL.iter().map(|w| w.attack()).collect();
+    assert_eq!(
+        kinds,
+        [
+            Attack::Hitscan { range: 30.0 },
+            Attack::Hitscan { range: 50.0 },
+            Attack::Projectile(ProjectileKind::Rocket),
+            Attack::Projectile(ProjectileKind::Grenade),
+        ]
+    );
+}
+
+// steps until it goes off or max seconds pass, Some(where, how long it took)
+fn fly(
+    p: &mut Projectile,
+    map: &TileMap,
+    targets: &[(u32, f32, f32)],
+    dt: f32,
+    max: f32,
+) -> Option<((f32, f32), f32)> {
+    let mut t = 0.0;
+    while t < max {
+        t += dt;
+        if let Some(at) = p.step(map, targets, dt) {
+            return Some((at, t));
+        }
+    }
+    None
+}
+
+#[test]
+fn rockets_go_off_at_the_first_wall() {
+    let map = corridor(true);
+    let mut rocket = Projectile::new(1, 1, ProjectileKind::Rocket, (1.5, 1.5), 0.0);
+    let ((x, y), _) = fly(&mut rocket, &map, &[], 1.0 / 30.0, 5.0).unwrap();
+    // stopped against the pillar at x = 6 with its radius to spare
+    assert!(x < 6.0 - ProjectileKind::Rocket.radius() + 1e-4, "{x}");
+    assert!(x > 5.5, "{x}");
+    assert!((y - 1.5).abs() < 1e-4);
+}
+
+#[test]
+fn fast_projectiles_do_not_skip_through_thin_walls() {
+    // a whole second per step is 8 tiles for a rocket, more than the distance to the pillar
+    // and past it
+    let map = corridor(true);
+    let mut rocket = Projectile::new(1, 1, ProjectileKind::Rocket, (1.5, 1.5), 0.0);
+    let ((x, _), _) = fly(&mut rocket, &map, &[], 1.0, 5.0).unwrap();
+    assert!(x < 6.0, "went through the pillar to {x}");
+    let target = [(2, 3.5, 1.5)];
+    let mut rocket = Projectile::new(1, 1, ProjectileKind::Rocket, (1.5, 1.5), 0.0);
+    let ((x, _), _) = fly(&mut rocket, &map, &target, 1.0, 5.0).unwrap();
+    assert!(x < 3.5, "went through the player to {x}");
+}
+
+#[test]
+fn rockets_hit_players_but_not_their_owner() {
+    let map = corridor(false);
+    // the owner stands where it starts
+    let targets = [(1, 1.5, 1.5), (2, 4.5, 1.5)];
+    let mut rocket = Projectile::new(1, 1, ProjectileKind::Rocket, (1.5, 1.5), 0.0);
+    let ((x, _), _) = fly(&mut rocket, &map, &targets, 1.0 / 30.0, 5.0).unwrap();
+    let touch = RADIUS + ProjectileKind::Rocket.radius();
+    assert!((x - (4.5 - touch)).abs() < 0.3, "{x}");
+}
+
+#[test]
+fn grenades_bounce_and_wait_for_the_fuse() {
+    let map = corridor(false);
+    // thrown at the end wall, comes back the other way before it goes off
+    let mut grenade = Projectile::new(1, 1, ProjectileKind::Grenade, (7.5, 1.5), 0.0);
+    let mut bounced = false;
+    let mut went_off = None;
+    let dt = 1.0 / 30.0;
+    for tick in 1..300 {
+        if let Some(at) = grenade.step(&map, &[], dt) {
+            went_off = Some((at, tick as f32 * dt));
+            break;
+        }
+        bounced |= grenade.vx < 0.0;
+        assert!(grenade.x < 9.0 - ProjectileKind::Grenade.radius() + 1e-4);
+    }
+    assert!(bounced, "never came back off the wall");
+    let (_, t) = went_off.expect("never went off");
+    assert!(
+        (t - ProjectileKind::Grenade.fuse()).abs() < 2.0 * dt,
+        "went off after {t}s"
+    );
+}
+
+#[test]
+fn grenades_bounce_off_side_walls_too() {
+    let map = corridor(false);
+    // thrown diagonally into the top wall, the sideways speed flips and the forward speed stays
+    let mut grenade = Projectile::new(1, 1, ProjectileKind::Grenade, (2.5, 1.5), -0.3);
+    for _ in 0..10 {
+        assert_eq!(grenade.step(&map, &[], 1.0 / 30.0), None);
+    }
+    assert!(grenade.vy > 0.0, "still going up at {}", grenade.vy);
+    assert!(grenade.vx > 0.0, "turned around at {}", grenade.vx);
+}
+
+#[test]
+fn splash_fades_with_distance_and_stops_at_walls() {
+    let map = corridor(true);
+    let kind = ProjectileKind::Rocket;
+    // right at the explosion, a bit off and just out of reach
+    let targets = [
+        (1, 3.0, 1.5),
+        (2, 4.0, 1.5),
+        (3, 3.0 + kind.splash_radius() + RADIUS + 0.1, 1.5),
+    ];
+    let damage = splash(&map, (3.0, 1.5), kind, &targets);
+    let of = |id| damage.iter().find(|d| d.0 == id).map(|d| d.1);
+    assert_eq!(of(1), Some(kind.damage()));
+    assert!(of(2).unwrap() < kind.damage() && of(2).unwrap() > 0);
+    assert_eq!(of(3), None);
+    // in reach but the pillar is in the way
+    let damage = splash(&map, (5.7, 1.5), kind, &[(4, 6.9, 1.5)]);
+    assert_eq!(damage, []);
+    let damage = splash(&corridor(false), (5.7, 1.5), kind, &[(4, 6.9, 1.5)]);
+    assert_eq!(damage.len(), 1);
+}
+
+#[test]
+fn update_projectiles_drops_the_ones_that_went_off() {
+    let map = corridor(false);
+    let mut projectiles = vec![
+        Projectile::new(1, 1, ProjectileKind::Rocket, (1.5, 1.5), 0.0),
+        // bouncing between the corridor walls
+        Projectile::new(2, 1, ProjectileKind::Grenade, (1.5, 1.5), -FRAC_PI_2),
+    ];
+    let targets = [(2, 3.0, 1.5)];
+    let mut damage = Vec::new();
+    for _ in 0..10 {
+        damage.extend(update_projectiles(
+            &mut projectiles,
+            &map,
+            &targets,
+            1.0 / 30.0,
+        ));
+    }
+    // the rocket got there first, the grenade is still rolling
+    assert_eq!(projectiles.len(), 1);
+    assert_eq!(projectiles[0].id, 2);
+    assert_eq!(damage.len(), 1);
+    assert_eq!((damage[0].attacker, damage[0].target), (1, 2));
+    assert_eq!(damage[0].weapon, Weapon::Rocket);
+}
+
+#[test]
+fn fire_hits_now_or_launches() {
+    let map = corridor(false);
+    let targets = [(1, 1.5, 1.5), (2, 4.5, 1.5)];
+    let mut projectiles = Vec::new();
+    let mut next_id = 7;
+    let shooter = (1, 1.5, 1.5, 0.0);
+    let hit = fire(
+        Weapon::Pistol,
+        &map,
+        shooter,
+        &targets,
+        &mut projectiles,
+        &mut next_id,
+    );
+    let pistol = Hit {
+        attacker: 1,
+        target: 2,
+        damage: Weapon::Pistol.damage(),
+        weapon: Weapon::Pistol,
+    };
+    assert_eq!(hit, Some(pistol));
+    assert!(projectiles.is_empty());
+
+    for _ in 0..MAX_PROJECTILES + 5 {
+        let hit = fire(
+            Weapon::Rocket,
+            &map,
+            shooter,
+            &targets,
+            &mut projectiles,
+            &mut next_id,
+        );
+        assert_eq!(hit, None);
+    }
+    assert_eq!(projectiles.len(), MAX_PROJECTILES);
+    assert_eq!((projectiles[0].id, projectiles[0].owner), (7, 1));
+    assert_eq!(next_id, 7 + MAX_PROJECTILES as u32);
+}
+
+#[test]
+fn weapons_survive_the_wire() {
+    for weapon in Weapon::ALL {
+        assert_eq!(Weapon::from_u8(weapon.to_u8()), weapon);
+    }
+    assert_eq!(Weapon::from_u8(200), Weapon::Pistol);
+    for kind in [ProjectileKind::Rocket, ProjectileKind::Grenade] {
+        assert_eq!(ProjectileKind::from_u8(kind.to_u8()), kind);
+    }
+}